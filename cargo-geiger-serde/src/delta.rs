@@ -0,0 +1,100 @@
+use crate::{Count, CounterBlock};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign};
+
+/// Signed difference between two `Count`s
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CountDelta {
+    /// Change in the number of safe items
+    pub safe: i64,
+    /// Change in the number of unsafe items
+    pub unsafe_: i64,
+}
+
+impl CountDelta {
+    /// Difference going from `old` to `new`
+    pub fn new(old: &Count, new: &Count) -> Self {
+        CountDelta {
+            safe: new.safe as i64 - old.safe as i64,
+            unsafe_: new.unsafe_ as i64 - old.unsafe_ as i64,
+        }
+    }
+}
+
+impl Add for CountDelta {
+    type Output = CountDelta;
+
+    fn add(self, other: CountDelta) -> CountDelta {
+        CountDelta {
+            safe: self.safe + other.safe,
+            unsafe_: self.unsafe_ + other.unsafe_,
+        }
+    }
+}
+
+impl AddAssign for CountDelta {
+    fn add_assign(&mut self, rhs: CountDelta) {
+        *self = self.clone() + rhs;
+    }
+}
+
+/// Signed difference between two `CounterBlock`s, field by field
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CounterBlockDelta {
+    pub functions: CountDelta,
+    pub exprs: CountDelta,
+    pub item_impls: CountDelta,
+    pub item_traits: CountDelta,
+    pub methods: CountDelta,
+}
+
+impl CounterBlockDelta {
+    /// Difference going from `old` to `new`
+    pub fn new(old: &CounterBlock, new: &CounterBlock) -> Self {
+        CounterBlockDelta {
+            functions: CountDelta::new(&old.functions, &new.functions),
+            exprs: CountDelta::new(&old.exprs, &new.exprs),
+            item_impls: CountDelta::new(&old.item_impls, &new.item_impls),
+            item_traits: CountDelta::new(&old.item_traits, &new.item_traits),
+            methods: CountDelta::new(&old.methods, &new.methods),
+        }
+    }
+
+    /// Returns true if any of the unsafe counters changed
+    pub fn has_unsafe_changes(&self) -> bool {
+        self.functions.unsafe_ != 0
+            || self.exprs.unsafe_ != 0
+            || self.item_impls.unsafe_ != 0
+            || self.item_traits.unsafe_ != 0
+            || self.methods.unsafe_ != 0
+    }
+
+    /// Sum of the changes of all unsafe counters
+    pub fn unsafe_total(&self) -> i64 {
+        self.functions.unsafe_
+            + self.exprs.unsafe_
+            + self.item_impls.unsafe_
+            + self.item_traits.unsafe_
+            + self.methods.unsafe_
+    }
+}
+
+impl Add for CounterBlockDelta {
+    type Output = CounterBlockDelta;
+
+    fn add(self, other: CounterBlockDelta) -> CounterBlockDelta {
+        CounterBlockDelta {
+            functions: self.functions + other.functions,
+            exprs: self.exprs + other.exprs,
+            item_impls: self.item_impls + other.item_impls,
+            item_traits: self.item_traits + other.item_traits,
+            methods: self.methods + other.methods,
+        }
+    }
+}
+
+impl AddAssign for CounterBlockDelta {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.clone() + rhs;
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    CounterBlockDelta, PackageId, QuickSafetyReport, SafetyReport, UnsafeInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Differences between two reports, going from the old to the new one.
+///
+/// Packages are matched by name, so that a package bumped to another version
+/// is listed as changed rather than as removed and added.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SafetyReportDiff {
+    /// Packages only found in the new report
+    pub added_packages: Vec<PackageId>,
+    /// Packages only found in the old report
+    pub removed_packages: Vec<PackageId>,
+    /// Packages found in both reports, with another version or other unsafe
+    /// counts
+    pub changed_packages: Vec<PackageChange>,
+    /// Packages found in both reports, which declare `#![forbid(unsafe_code)]`
+    /// in only one of them
+    pub forbid_changes: Vec<ForbidChange>,
+    /// Files used by the build but not scanned, which are only in the new
+    /// report
+    pub new_unscanned_files: Vec<PathBuf>,
+}
+
+/// A package found in both reports, with another version or other unsafe
+/// counts
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PackageChange {
+    pub old: PackageId,
+    pub new: PackageId,
+    /// Change of the unsafe usage used by the build, not known when
+    /// comparing `QuickSafetyReport`s
+    pub used: Option<CounterBlockDelta>,
+    /// Change of the unsafe usage not used by the build, not known when
+    /// comparing `QuickSafetyReport`s
+    pub unused: Option<CounterBlockDelta>,
+}
+
+/// A package which declares `#![forbid(unsafe_code)]` in only one of the
+/// reports
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ForbidChange {
+    pub old: PackageId,
+    pub new: PackageId,
+    /// Whether the package forbids the use of `unsafe` in the new report
+    pub forbids_unsafe: bool,
+}
+
+impl SafetyReportDiff {
+    /// Differences going from the `old` to the `new` report
+    pub fn new(old: &SafetyReport, new: &SafetyReport) -> Self {
+        let snapshots = |report: &SafetyReport| {
+            report
+                .packages
+                .values()
+                .map(|entry| PackageSnapshot {
+                    id: entry.package.id.clone(),
+                    forbids_unsafe: entry.unsafety.forbids_unsafe,
+                    unsafety: Some(entry.unsafety.clone()),
+                })
+                .collect()
+        };
+        let mut diff = diff_packages(snapshots(old), snapshots(new));
+        diff.new_unscanned_files = new
+            .used_but_not_scanned_files
+            .difference(&old.used_but_not_scanned_files)
+            .cloned()
+            .collect();
+        diff.new_unscanned_files.sort();
+        diff
+    }
+
+    /// Differences going from the `old` to the `new` quick report, which
+    /// have no unsafe counts
+    pub fn from_quick_reports(
+        old: &QuickSafetyReport,
+        new: &QuickSafetyReport,
+    ) -> Self {
+        let snapshots = |report: &QuickSafetyReport| {
+            report
+                .packages
+                .values()
+                .map(|entry| PackageSnapshot {
+                    id: entry.package.id.clone(),
+                    forbids_unsafe: entry.forbids_unsafe,
+                    unsafety: None,
+                })
+                .collect()
+        };
+        diff_packages(snapshots(old), snapshots(new))
+    }
+
+    /// Returns true if the reports have no differences
+    pub fn is_empty(&self) -> bool {
+        self.added_packages.is_empty()
+            && self.removed_packages.is_empty()
+            && self.changed_packages.is_empty()
+            && self.forbid_changes.is_empty()
+            && self.new_unscanned_files.is_empty()
+    }
+}
+
+struct PackageSnapshot {
+    id: PackageId,
+    forbids_unsafe: bool,
+    unsafety: Option<UnsafeInfo>,
+}
+
+fn diff_packages(
+    old: Vec<PackageSnapshot>,
+    new: Vec<PackageSnapshot>,
+) -> SafetyReportDiff {
+    let mut by_name = BTreeMap::<String, (Vec<_>, Vec<_>)>::new();
+    for snapshot in old {
+        by_name
+            .entry(snapshot.id.name.clone())
+            .or_default()
+            .0
+            .push(snapshot);
+    }
+    for snapshot in new {
+        by_name
+            .entry(snapshot.id.name.clone())
+            .or_default()
+            .1
+            .push(snapshot);
+    }
+
+    let mut diff = SafetyReportDiff::default();
+    for (old, mut new) in by_name.into_values() {
+        // Versions found in both reports are paired first, the remaining
+        // versions are paired from oldest to newest.
+        let mut pairs = vec![];
+        let mut unpaired_old = vec![];
+        for old_snapshot in old {
+            match new.iter().position(|new_snapshot| {
+                new_snapshot.id.version == old_snapshot.id.version
+            }) {
+                Some(index) => pairs.push((old_snapshot, new.remove(index))),
+                None => unpaired_old.push(old_snapshot),
+            }
+        }
+        unpaired_old.sort_by(|a, b| a.id.cmp(&b.id));
+        new.sort_by(|a, b| a.id.cmp(&b.id));
+        let paired_count = unpaired_old.len().min(new.len());
+        diff.removed_packages.extend(
+            unpaired_old
+                .drain(paired_count..)
+                .map(|snapshot| snapshot.id),
+        );
+        diff.added_packages
+            .extend(new.drain(paired_count..).map(|snapshot| snapshot.id));
+        pairs.extend(unpaired_old.into_iter().zip(new));
+        pairs.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+
+        for (old_snapshot, new_snapshot) in pairs {
+            let (used, unused) =
+                match (&old_snapshot.unsafety, &new_snapshot.unsafety) {
+                    (Some(old_unsafety), Some(new_unsafety)) => (
+                        Some(CounterBlockDelta::new(
+                            &old_unsafety.used,
+                            &new_unsafety.used,
+                        )),
+                        Some(CounterBlockDelta::new(
+                            &old_unsafety.unused,
+                            &new_unsafety.unused,
+                        )),
+                    ),
+                    _ => (None, None),
+                };
+            let has_unsafe_changes = used
+                .iter()
+                .chain(&unused)
+                .any(CounterBlockDelta::has_unsafe_changes);
+
+            if old_snapshot.forbids_unsafe != new_snapshot.forbids_unsafe {
+                diff.forbid_changes.push(ForbidChange {
+                    old: old_snapshot.id.clone(),
+                    new: new_snapshot.id.clone(),
+                    forbids_unsafe: new_snapshot.forbids_unsafe,
+                });
+            }
+            if old_snapshot.id.version != new_snapshot.id.version
+                || has_unsafe_changes
+            {
+                diff.changed_packages.push(PackageChange {
+                    old: old_snapshot.id,
+                    new: new_snapshot.id,
+                    used,
+                    unused,
+                });
+            }
+        }
+    }
+    diff
+}
@@ -6,13 +6,18 @@
 #![forbid(unsafe_code)]
 #![deny(warnings)]
 
+mod delta;
+mod diff;
 mod package_id;
 mod report;
 mod source;
 
+pub use delta::{CountDelta, CounterBlockDelta};
+pub use diff::{ForbidChange, PackageChange, SafetyReportDiff};
 pub use package_id::PackageId;
 pub use report::{
-    Count, CounterBlock, DependencyKind, PackageInfo, QuickReportEntry,
-    QuickSafetyReport, ReportEntry, SafetyReport, UnsafeInfo,
+    Count, CounterBlock, DependencyKind, DuplicateVersionEntry,
+    DuplicateVersions, PackageInfo, PatchedPackage, PatchedPackageOriginal,
+    QuickReportEntry, QuickSafetyReport, ReportEntry, SafetyReport, UnsafeInfo,
 };
 pub use source::Source;
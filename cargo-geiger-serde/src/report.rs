@@ -1,4 +1,4 @@
-use crate::PackageId;
+use crate::{CounterBlockDelta, PackageId};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 use std::{
@@ -106,6 +106,21 @@ pub struct SafetyReport {
     pub packages_without_metrics: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub used_but_not_scanned_files: HashSet<PathBuf>,
+    /// Crates found in more than one version in the dependency graph
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_versions: Vec<DuplicateVersions>,
+    /// Packages which a dependency was resolved to because of `[patch]` or
+    /// `[replace]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patched_packages: Vec<PatchedPackage>,
+    /// Packages of the Rust standard library, which are not part of the
+    /// dependency graph
+    #[serde(
+        default,
+        with = "entry_serde",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub rust_std: HashMap<PackageId, ReportEntry>,
 }
 impl Debug for SafetyReport {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -115,10 +130,55 @@ impl Debug for SafetyReport {
         debug_fmt_set(f, &self.packages_without_metrics)?;
         write!(f, ", used_but_not_scanned_files: ")?;
         debug_fmt_set(f, &self.used_but_not_scanned_files)?;
+        write!(f, ", duplicate_versions: {:?}", self.duplicate_versions)?;
+        write!(f, ", patched_packages: {:?}", self.patched_packages)?;
+        write!(f, ", rust_std: ")?;
+        debug_fmt_map(f, &self.rust_std)?;
         write!(f, " }}")
     }
 }
 
+/// All versions of a crate which is found more than once in the dependency
+/// graph
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DuplicateVersions {
+    pub name: String,
+    /// The versions found, ordered from oldest to newest
+    pub versions: Vec<DuplicateVersionEntry>,
+    /// Whether an older version uses more unsafe code than a newer one
+    pub older_has_more_unsafe: bool,
+}
+
+/// A single version of a crate which is found more than once in the
+/// dependency graph
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DuplicateVersionEntry {
+    pub id: PackageId,
+    /// Unsafety scan results
+    pub unsafety: UnsafeInfo,
+}
+
+/// A package which a dependency was resolved to because of `[patch]` or
+/// `[replace]`
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PatchedPackage {
+    pub id: PackageId,
+    /// The registry package which was patched, if it is found in the local
+    /// registry cache
+    pub original: Option<PatchedPackageOriginal>,
+}
+
+/// The registry package a patched package replaces
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PatchedPackageOriginal {
+    pub id: PackageId,
+    /// All unsafe usage found in the original, used by the build or not
+    pub unsafety: CounterBlock,
+    /// Change of all unsafe usage found in the package, used by the build or
+    /// not, compared with the original
+    pub delta: CounterBlockDelta,
+}
+
 /// Unsafety usage in a package
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct UnsafeInfo {
@@ -196,6 +256,15 @@ impl CounterBlock {
             || self.item_traits.unsafe_ > 0
             || self.methods.unsafe_ > 0
     }
+
+    /// Sum of all unsafe counters
+    pub fn unsafe_total(&self) -> u64 {
+        self.functions.unsafe_
+            + self.exprs.unsafe_
+            + self.item_impls.unsafe_
+            + self.item_traits.unsafe_
+            + self.methods.unsafe_
+    }
 }
 
 impl Add for CounterBlock {
@@ -6,7 +6,17 @@ use url::Url;
     Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
 )]
 pub enum Source {
-    Git { url: Url, rev: String },
-    Registry { name: String, url: Url },
+    Git {
+        url: Url,
+        rev: String,
+    },
+    Registry {
+        name: String,
+        url: Url,
+    },
     Path(Url),
+    /// A directory of vendored packages, as created by `cargo vendor`
+    Directory(Url),
+    /// A local registry of `.crate` files
+    LocalRegistry(Url),
 }
@@ -5,6 +5,7 @@ use cargo::core::shell::ColorChoice;
 use cargo::util::context::GlobalContext;
 use cargo::CliResult;
 use pico_args::Arguments;
+use std::ffi::OsString;
 use std::path::PathBuf;
 
 /// Constant `&str` containing help text
@@ -13,16 +14,41 @@ pub const HELP: &str =
 
 USAGE:
     cargo geiger [OPTIONS]
+    cargo geiger dataset [OPTIONS] <DIR>
+    cargo geiger diff [OPTIONS] <OLD> <NEW>
+    cargo geiger update-impact [OPTIONS] --old-lock <PATH>
 
 OPTIONS:
     -p, --package <SPEC>          Package to be used as the root of the tree.
         --features <FEATURES>     Space-separated list of features to activate.
         --all-features            Activate all available features.
         --no-default-features     Do not activate the `default` feature.
-        --target <TARGET>         Set the target triple.
+        --feature-matrix          Scan the root package with its default
+                                  features, without default features and with
+                                  each declared feature enabled on its own, and
+                                  report how each feature changes the unsafe
+                                  usage.
+        --feature-matrix-powerset <N>
+                                  With --feature-matrix, also scan every
+                                  combination of up to N declared features.
+        --target <TARGET>         Set the target triple. May be given more than
+                                  once to compare the unsafe usage of several
+                                  targets side by side.
         --all-targets             Return dependencies for all targets. By
                                   default only the host target is matched.
         --manifest-path <PATH>    Path to Cargo.toml.
+        --lockfile <PATH>         Scan exactly the packages pinned by a
+                                  Cargo.lock, finding their sources in the
+                                  local cache, without resolving, building or
+                                  accessing the network. Every scanned file is
+                                  counted as used by the build.
+        --crate <NAME@VERSION>    Scan a crate found in the local registry
+                                  cache, and its dependencies, without a
+                                  project.
+        --rev <REV>               Scan the workspace as it was at a git
+                                  revision, checked out into a temporary
+                                  worktree of the local repository, without
+                                  touching the working copy.
     -i, --invert                  Invert the tree direction.
         --no-indent               Display the dependencies as a list (rather
                                   than a tree).
@@ -33,7 +59,16 @@ OPTIONS:
     --format <FORMAT>             Format string used for printing dependencies
                                   [default: {p}].
     --output-format               Output format for the report: Ascii, GitHubMarkdown,
-                                  Json, Utf8, Ratio [default: Utf8]
+                                  Json, Utf8, Ratio, Sarif, CycloneDx, Spdx,
+                                  Html, Dot, Mermaid, Csv, Tsv, JUnit,
+                                  GitLabCodeQuality, GitHubAnnotations,
+                                  OpenMetrics [default: Utf8]. See the README
+                                  for what each format contains.
+        --collapse-forbidding     With --output-format Dot or Mermaid, merge
+                                  the packages which forbid unsafe code into a
+                                  single node.
+        --per-file                With --output-format Csv or Tsv, print one
+                                  row per scanned file instead of per package.
     --update-readme               Writes output to ./README.md. Looks for a Safety
                                   Report section, replaces if found, adds if not.
                                   Throws an error if no README.md exists.
@@ -50,6 +85,13 @@ OPTIONS:
         --offline                 Run without accessing the network.
     -Z \"<FLAG>...\"                Unstable (nightly-only) flags to Cargo.
         --include-tests           Count unsafe usage in tests.
+        --include-std             Also scan core, alloc, std and the packages
+                                  they depend on from the rust-src component of
+                                  the active toolchain, listed under a separate
+                                  rust-std group. Every scanned file is counted
+                                  as used.
+        --include-std-in-totals   Like --include-std, and count the standard
+                                  library in the totals.
         --build-dependencies      Also analyze build dependencies.
         --dev-dependencies        Also analyze dev dependencies.
         --all-dependencies        Analyze all dependencies, including build and
@@ -60,13 +102,53 @@ OPTIONS:
                                   significantly faster than the default
                                   scanning mode. TODO: Add ability to combine
                                   this with a whitelist for use in CI.
+        --check                   Check the scan results against the policy in
+                                  geiger.toml or [workspace.metadata.geiger],
+                                  print each violation and exit with an error
+                                  if any are found.
+        --write-baseline <PATH>   Write the unsafe usage of each package found
+                                  by the scan to a baseline file.
+        --baseline <PATH>         Compare the scan with a baseline file, print
+                                  the packages which are new and use unsafe
+                                  code, use more unsafe code or no longer
+                                  forbid unsafe code, and exit with code 2 if
+                                  any are found.
+        --update-baseline         With --baseline, lower the counts in the
+                                  baseline file which went down.
     -h, --help                    Prints help information.
     -V, --version                 Prints version information.
+
+SUBCOMMANDS:
+    dataset <DIR>                 Scan every .crate archive in DIR without
+                                  extracting it, and write one JSON line per
+                                  crate with the metrics of each .rs file.
+        --output <PATH>           Write the JSON lines to PATH instead of
+                                  stdout.
+    diff <OLD> <NEW>              Compare two JSON reports, listing the added
+                                  and removed packages, the changed versions
+                                  and unsafe counts, the forbid status changes
+                                  and the newly unscanned files. Printed as
+                                  Markdown with --output-format GitHubMarkdown,
+                                  as JSON with --output-format Json, and as
+                                  text otherwise.
+    update-impact                 Compare the packages pinned by an older
+                                  Cargo.lock with the ones pinned by the
+                                  current one, found in the local cache, and
+                                  report how the used unsafe code changes for
+                                  every updated, added and removed package.
+                                  Only the packages which differ are scanned,
+                                  and every scanned file is counted as used.
+        --old-lock <PATH>         The older Cargo.lock, whose path packages are
+                                  looked up in the current workspace. The
+                                  current one can be chosen with --lockfile.
 ";
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Args {
     pub all: bool,
+    pub baseline_args: BaselineArgs,
+    pub check: bool,
+    pub collapse_forbidding: bool,
     pub color: Option<String>,
     pub deps_args: DepsArgs,
     pub features_args: FeaturesArgs,
@@ -74,17 +156,24 @@ pub struct Args {
     pub format: String,
     pub frozen: bool,
     pub help: bool,
+    pub include_std: bool,
+    pub include_std_in_totals: bool,
     pub include_tests: bool,
     pub invert: bool,
+    pub krate: Option<String>,
     pub locked: bool,
+    pub lockfile: Option<PathBuf>,
     pub manifest_path: Option<PathBuf>,
     pub no_indent: bool,
     pub offline: bool,
     pub output_format: OutputFormat,
     pub package: Option<String>,
+    pub per_file: bool,
     pub prefix_depth: bool,
     pub quiet: bool,
     pub readme_args: ReadmeArgs,
+    pub rev: Option<String>,
+    pub subcommand: Option<Subcommand>,
     pub target_args: TargetArgs,
     pub unstable_flags: Vec<String>,
     pub verbosity: Verbosity,
@@ -102,8 +191,20 @@ impl Args {
     pub fn parse_args(
         mut raw_args: Arguments,
     ) -> Result<Args, Box<dyn std::error::Error>> {
+        let output_path = raw_args.opt_value_from_str("--output")?;
+        let old_lockfile_path = raw_args.opt_value_from_str("--old-lock")?;
+        let include_std_in_totals =
+            raw_args.contains("--include-std-in-totals");
         let mut args = Args {
             all: raw_args.contains(["-a", "--all"]),
+            baseline_args: BaselineArgs {
+                baseline_path: raw_args.opt_value_from_str("--baseline")?,
+                update_baseline: raw_args.contains("--update-baseline"),
+                write_baseline_path: raw_args
+                    .opt_value_from_str("--write-baseline")?,
+            },
+            check: raw_args.contains("--check"),
+            collapse_forbidding: raw_args.contains("--collapse-forbidding"),
             color: raw_args.opt_value_from_str("--color")?,
             deps_args: DepsArgs {
                 all_deps: raw_args.contains("--all-dependencies"),
@@ -112,6 +213,9 @@ impl Args {
             },
             features_args: FeaturesArgs {
                 all_features: raw_args.contains("--all-features"),
+                feature_matrix: raw_args.contains("--feature-matrix"),
+                feature_matrix_powerset: raw_args
+                    .opt_value_from_str("--feature-matrix-powerset")?,
                 features: parse_features(
                     raw_args.opt_value_from_str("--features")?,
                 ),
@@ -123,13 +227,19 @@ impl Args {
                 .unwrap_or_else(|| "{p}".to_string()),
             frozen: raw_args.contains("--frozen"),
             help: raw_args.contains(["-h", "--help"]),
+            include_std: raw_args.contains("--include-std")
+                || include_std_in_totals,
+            include_std_in_totals,
             include_tests: raw_args.contains("--include-tests"),
             invert: raw_args.contains(["-i", "--invert"]),
+            krate: raw_args.opt_value_from_str("--crate")?,
             locked: raw_args.contains("--locked"),
+            lockfile: raw_args.opt_value_from_str("--lockfile")?,
             manifest_path: raw_args.opt_value_from_str("--manifest-path")?,
             no_indent: raw_args.contains("--no-indent"),
             offline: raw_args.contains("--offline"),
             package: raw_args.opt_value_from_str(["-p", "--package"])?,
+            per_file: raw_args.contains("--per-file"),
             prefix_depth: raw_args.contains("--prefix-depth"),
             quiet: raw_args.contains(["-q", "--quiet"]),
             readme_args: ReadmeArgs {
@@ -137,9 +247,11 @@ impl Args {
                 section_name: raw_args.opt_value_from_str("--section-name")?,
                 update_readme: raw_args.contains("--update-readme"),
             },
+            rev: raw_args.opt_value_from_str("--rev")?,
+            subcommand: None,
             target_args: TargetArgs {
                 all_targets: raw_args.contains("--all-targets"),
-                target: raw_args.opt_value_from_str("--target")?,
+                targets: raw_args.values_from_str("--target")?,
             },
             unstable_flags: raw_args
                 .opt_value_from_str("-Z")?
@@ -160,6 +272,12 @@ impl Args {
                 .unwrap_or(OutputFormat::Utf8),
         };
 
+        args.subcommand = parse_subcommand(
+            raw_args.finish(),
+            output_path,
+            old_lockfile_path,
+        )?;
+
         if args.readme_args.update_readme
             && args.output_format != OutputFormat::GitHubMarkdown
         {
@@ -174,6 +292,32 @@ impl Args {
         Ok(args)
     }
 
+    /// Returns true if the output format is only written by the default scan
+    /// of a single target and set of features
+    pub fn requires_default_scan(&self) -> bool {
+        matches!(
+            self.output_format,
+            OutputFormat::Csv
+                | OutputFormat::CycloneDx
+                | OutputFormat::Dot
+                | OutputFormat::GitHubAnnotations
+                | OutputFormat::GitLabCodeQuality
+                | OutputFormat::Html
+                | OutputFormat::JUnit
+                | OutputFormat::Mermaid
+                | OutputFormat::OpenMetrics
+                | OutputFormat::Sarif
+                | OutputFormat::Spdx
+                | OutputFormat::Tsv
+        )
+    }
+
+    /// Returns true if the output format reports the violations of the policy
+    /// checked by `--check` itself
+    pub fn reports_policy_violations(&self) -> bool {
+        self.output_format == OutputFormat::JUnit
+    }
+
     /// Update `cargo::util::Config` with values from `Args` struct, and set the shell
     /// colour choice
     /// ```
@@ -215,34 +359,89 @@ impl Args {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
+pub struct BaselineArgs {
+    pub baseline_path: Option<PathBuf>,
+    pub update_baseline: bool,
+    pub write_baseline_path: Option<PathBuf>,
+}
+
+impl BaselineArgs {
+    /// Returns true if the scan results should be written to or compared with
+    /// a baseline
+    pub fn is_baseline(&self) -> bool {
+        self.baseline_path.is_some() || self.write_baseline_path.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct DepsArgs {
     pub all_deps: bool,
     pub build_deps: bool,
     pub dev_deps: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct FeaturesArgs {
     pub all_features: bool,
+    pub feature_matrix: bool,
+    pub feature_matrix_powerset: Option<usize>,
     pub features: Vec<String>,
     pub no_default_features: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Subcommand {
+    Dataset(DatasetArgs),
+    Diff(DiffArgs),
+    UpdateImpact(UpdateImpactArgs),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DatasetArgs {
+    pub crates_dir: PathBuf,
+    pub output_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffArgs {
+    pub old_report_path: PathBuf,
+    pub new_report_path: PathBuf,
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct TargetArgs {
     pub all_targets: bool,
-    pub target: Option<String>,
+    pub targets: Vec<String>,
+}
+
+impl TargetArgs {
+    /// The target triple to build and scan for, the first one given if
+    /// `--target` was provided more than once
+    pub fn target(&self) -> Option<&str> {
+        self.targets.first().map(String::as_str)
+    }
+
+    /// Returns true if more than one target triple should be scanned and
+    /// compared
+    pub fn is_multi_target(&self) -> bool {
+        self.targets.len() > 1
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpdateImpactArgs {
+    pub old_lockfile_path: PathBuf,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ReadmeArgs {
     pub readme_path: Option<PathBuf>,
     pub section_name: Option<String>,
     pub update_readme: bool,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Verbosity {
     Verbose,
     Normal,
@@ -255,6 +454,59 @@ impl Default for Verbosity {
     }
 }
 
+/// Parse the free arguments left once all options are parsed. When run as
+/// `cargo geiger`, cargo passes `geiger` as the first of them.
+fn parse_subcommand(
+    free_args: Vec<OsString>,
+    output_path: Option<PathBuf>,
+    old_lockfile_path: Option<PathBuf>,
+) -> Result<Option<Subcommand>, Box<dyn std::error::Error>> {
+    let mut free_args = free_args.into_iter().peekable();
+    if free_args.peek().is_some_and(|arg| arg == "geiger") {
+        free_args.next();
+    }
+
+    let subcommand = free_args.next();
+    if output_path.is_some()
+        && subcommand.as_ref().is_none_or(|arg| arg != "dataset")
+    {
+        return Err(
+            "--output can only be used with the `dataset` subcommand".into()
+        );
+    }
+
+    match subcommand {
+        Some(subcommand) if subcommand == "dataset" => {
+            let crates_dir = free_args
+                .next()
+                .ok_or("the `dataset` subcommand requires a directory")?;
+            Ok(Some(Subcommand::Dataset(DatasetArgs {
+                crates_dir: PathBuf::from(crates_dir),
+                output_path,
+            })))
+        }
+        Some(subcommand) if subcommand == "diff" => {
+            match (free_args.next(), free_args.next()) {
+                (Some(old_report_path), Some(new_report_path)) => {
+                    Ok(Some(Subcommand::Diff(DiffArgs {
+                        old_report_path: PathBuf::from(old_report_path),
+                        new_report_path: PathBuf::from(new_report_path),
+                    })))
+                }
+                _ => Err("the `diff` subcommand requires two reports".into()),
+            }
+        }
+        Some(subcommand) if subcommand == "update-impact" => {
+            let old_lockfile_path = old_lockfile_path
+                .ok_or("the `update-impact` subcommand requires --old-lock")?;
+            Ok(Some(Subcommand::UpdateImpact(UpdateImpactArgs {
+                old_lockfile_path,
+            })))
+        }
+        _ => Ok(None),
+    }
+}
+
 fn parse_features(raw_features: Option<String>) -> Vec<String> {
     raw_features
         .as_ref()
@@ -340,6 +592,122 @@ pub mod args_tests {
         assert_eq!(args.verbosity, expected_verbosity)
     }
 
+    #[rstest(
+        input_argument_vector,
+        expected_targets,
+        expected_target,
+        expected_is_multi_target,
+        case(vec![], vec![], None, false),
+        case(
+            vec![
+                OsString::from("--target"),
+                OsString::from("wasm32-unknown-unknown")
+            ],
+            vec![String::from("wasm32-unknown-unknown")],
+            Some("wasm32-unknown-unknown"),
+            false
+        ),
+        case(
+            vec![
+                OsString::from("--target"),
+                OsString::from("x86_64-unknown-linux-gnu"),
+                OsString::from("--target"),
+                OsString::from("thumbv7em-none-eabihf")
+            ],
+            vec![
+                String::from("x86_64-unknown-linux-gnu"),
+                String::from("thumbv7em-none-eabihf")
+            ],
+            Some("x86_64-unknown-linux-gnu"),
+            true
+        )
+    )]
+    fn parse_args_test_targets(
+        input_argument_vector: Vec<OsString>,
+        expected_targets: Vec<String>,
+        expected_target: Option<&str>,
+        expected_is_multi_target: bool,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(input_argument_vector))
+            .unwrap();
+
+        assert_eq!(args.target_args.targets, expected_targets);
+        assert_eq!(args.target_args.target(), expected_target);
+        assert_eq!(
+            args.target_args.is_multi_target(),
+            expected_is_multi_target
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_include_std,
+        expected_include_std_in_totals,
+        case(vec![], false, false),
+        case(vec![OsString::from("--include-std")], true, false),
+        case(vec![OsString::from("--include-std-in-totals")], true, true)
+    )]
+    fn parse_args_test_include_std(
+        input_argument_vector: Vec<OsString>,
+        expected_include_std: bool,
+        expected_include_std_in_totals: bool,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(input_argument_vector))
+            .unwrap();
+
+        assert_eq!(args.include_std, expected_include_std);
+        assert_eq!(args.include_std_in_totals, expected_include_std_in_totals);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_baseline_path,
+        expected_update_baseline,
+        expected_write_baseline_path,
+        case(vec![], None, false, None),
+        case(
+            vec!["--baseline", "geiger-baseline.json", "--update-baseline"],
+            Some(PathBuf::from("geiger-baseline.json")),
+            true,
+            None
+        ),
+        case(
+            vec!["--write-baseline", "geiger-baseline.json"],
+            None,
+            false,
+            Some(PathBuf::from("geiger-baseline.json"))
+        )
+    )]
+    fn parse_args_test_baseline(
+        input_argument_vector: Vec<&str>,
+        expected_baseline_path: Option<PathBuf>,
+        expected_update_baseline: bool,
+        expected_write_baseline_path: Option<PathBuf>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.baseline_args.baseline_path, expected_baseline_path);
+        assert_eq!(
+            args.baseline_args.update_baseline,
+            expected_update_baseline
+        );
+        assert_eq!(
+            args.baseline_args.write_baseline_path,
+            expected_write_baseline_path
+        );
+        assert_eq!(
+            args.baseline_args.is_baseline(),
+            expected_baseline_path.is_some()
+                || expected_write_baseline_path.is_some()
+        );
+    }
+
     #[rstest(
         input_raw_features,
         expected_features,
@@ -371,6 +739,74 @@ pub mod args_tests {
         assert_eq!(parse_features(input_raw_features), expected_features);
     }
 
+    #[rstest(
+        input_argument_vector,
+        expected_subcommand,
+        case(vec!["geiger"], None),
+        case(
+            vec!["geiger", "dataset", "crates"],
+            Some(Subcommand::Dataset(DatasetArgs {
+                crates_dir: PathBuf::from("crates"),
+                output_path: None,
+            }))
+        ),
+        case(
+            vec!["dataset", "--output", "out.jsonl", "crates"],
+            Some(Subcommand::Dataset(DatasetArgs {
+                crates_dir: PathBuf::from("crates"),
+                output_path: Some(PathBuf::from("out.jsonl")),
+            }))
+        ),
+        case(
+            vec!["geiger", "diff", "old.json", "new.json"],
+            Some(Subcommand::Diff(DiffArgs {
+                old_report_path: PathBuf::from("old.json"),
+                new_report_path: PathBuf::from("new.json"),
+            }))
+        ),
+        case(
+            vec!["geiger", "update-impact", "--old-lock", "old.lock"],
+            Some(Subcommand::UpdateImpact(UpdateImpactArgs {
+                old_lockfile_path: PathBuf::from("old.lock"),
+            }))
+        )
+    )]
+    fn parse_args_subcommand_test(
+        input_argument_vector: Vec<&str>,
+        expected_subcommand: Option<Subcommand>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.subcommand, expected_subcommand);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        case(vec!["dataset"]),
+        case(vec!["diff", "old.json"]),
+        case(vec!["update-impact"]),
+        case(vec!["--output", "out.jsonl"]),
+        case(vec!["diff", "--output", "out.jsonl", "old.json", "new.json"])
+    )]
+    fn parse_args_subcommand_missing_arguments_test(
+        input_argument_vector: Vec<&str>,
+    ) {
+        let result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert!(result.is_err());
+    }
+
     #[rstest(
         input_quiet,
         input_verbosity,
@@ -0,0 +1,350 @@
+use anyhow::anyhow;
+use cargo::CargoResult;
+use cargo_geiger_serde::{PackageId, ReportEntry, SafetyReport};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The exit code used when the unsafe usage grew compared with the baseline
+pub const BASELINE_REGRESSION_EXIT_CODE: i32 = 2;
+
+/// A package whose unsafe usage grew compared with the baseline
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BaselineRegression {
+    pub package: PackageId,
+    pub kind: BaselineRegressionKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BaselineRegressionKind {
+    /// The package is not in the baseline, and unsafe code of it is used by
+    /// the build
+    NewPackage,
+    /// More unsafe items of a kind are used by the build
+    UnsafeIncreased {
+        kind: &'static str,
+        baseline: u64,
+        current: u64,
+    },
+    /// The package no longer declares `#![forbid(unsafe_code)]`
+    ForbidUnsafeRemoved,
+}
+
+impl fmt::Display for BaselineRegression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: ", self.package.name, self.package.version)?;
+        match &self.kind {
+            BaselineRegressionKind::NewPackage => {
+                write!(f, "new package using unsafe code")
+            }
+            BaselineRegressionKind::UnsafeIncreased {
+                kind,
+                baseline,
+                current,
+            } => write!(
+                f,
+                "unsafe {} increased from {} to {}",
+                kind, baseline, current
+            ),
+            BaselineRegressionKind::ForbidUnsafeRemoved => {
+                write!(f, "no longer declares #![forbid(unsafe_code)]")
+            }
+        }
+    }
+}
+
+/// Result of comparing a scan with the baseline
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BaselineComparison {
+    /// The packages whose unsafe usage grew, ordered by package
+    pub regressions: Vec<BaselineRegression>,
+    /// The baseline with the counts that went down lowered, the packages no
+    /// longer scanned removed and new packages without unsafe usage added.
+    /// Packages with regressions keep the counts which grew.
+    pub ratcheted: SafetyReport,
+    /// Whether `ratcheted` differs from the baseline
+    pub improved: bool,
+}
+
+/// Read a baseline written with `--write-baseline`
+pub fn read_baseline(path: &Path) -> CargoResult<SafetyReport> {
+    let baseline = fs::read_to_string(path).map_err(|e| {
+        anyhow!("failed to read baseline `{}`: {}", path.display(), e)
+    })?;
+    serde_json::from_str(&baseline).map_err(|e| {
+        anyhow!("failed to parse baseline `{}`: {}", path.display(), e)
+    })
+}
+
+/// Write the per package counts of the report as a baseline
+pub fn write_baseline(path: &Path, report: &SafetyReport) -> CargoResult<()> {
+    let baseline = SafetyReport {
+        packages: report.packages.clone(),
+        packages_without_metrics: report.packages_without_metrics.clone(),
+        ..Default::default()
+    };
+    let mut json = serde_json::to_string_pretty(&baseline)?;
+    json.push('\n');
+    fs::write(path, json).map_err(|e| {
+        anyhow!("failed to write baseline `{}`: {}", path.display(), e)
+    })
+}
+
+/// Compare the scanned packages with the baseline. Packages are matched by
+/// name like `SafetyReportDiff` does, so that a baseline stays valid for path
+/// packages checked out in another directory and the counts of a package are
+/// compared across version bumps.
+pub fn compare_with_baseline(
+    baseline: &SafetyReport,
+    report: &SafetyReport,
+) -> BaselineComparison {
+    let pairs = pair_with_baseline(baseline, report);
+    let paired_count = pairs
+        .iter()
+        .filter(|(baseline_entry, _)| baseline_entry.is_some())
+        .count();
+
+    let mut regressions = vec![];
+    let mut ratcheted = HashMap::new();
+    let mut improved = paired_count != baseline.packages.len();
+    for (baseline_entry, entry) in pairs {
+        let package_id = &entry.package.id;
+        let package_regressions = match baseline_entry {
+            Some(baseline_entry) => find_regressions(baseline_entry, entry),
+            None if entry.unsafety.used.has_unsafe() => {
+                vec![BaselineRegressionKind::NewPackage]
+            }
+            None => vec![],
+        };
+        if package_regressions.is_empty() {
+            improved |= baseline_entry.is_none_or(|baseline_entry| {
+                baseline_entry.package.id != *package_id
+                    || baseline_entry.unsafety != entry.unsafety
+            });
+            ratcheted.insert(package_id.clone(), entry.clone());
+        } else if let Some(baseline_entry) = baseline_entry {
+            let lowered = lower_counts(baseline_entry, entry);
+            improved |= lowered != *baseline_entry;
+            ratcheted.insert(package_id.clone(), lowered);
+        }
+        regressions.extend(package_regressions.into_iter().map(|kind| {
+            BaselineRegression {
+                package: package_id.clone(),
+                kind,
+            }
+        }));
+    }
+
+    BaselineComparison {
+        regressions,
+        ratcheted: SafetyReport {
+            packages: ratcheted,
+            packages_without_metrics: report.packages_without_metrics.clone(),
+            ..Default::default()
+        },
+        improved,
+    }
+}
+
+/// Every scanned package with the baseline entry of the same name, if any,
+/// ordered by package. Versions found in both are paired first, the
+/// remaining versions are paired from oldest to newest.
+fn pair_with_baseline<'a>(
+    baseline: &'a SafetyReport,
+    report: &'a SafetyReport,
+) -> Vec<(Option<&'a ReportEntry>, &'a ReportEntry)> {
+    let mut by_name =
+        BTreeMap::<&str, (Vec<&ReportEntry>, Vec<&ReportEntry>)>::new();
+    for entry in baseline.packages.values() {
+        by_name
+            .entry(entry.package.id.name.as_str())
+            .or_default()
+            .0
+            .push(entry);
+    }
+    for entry in report.packages.values() {
+        by_name
+            .entry(entry.package.id.name.as_str())
+            .or_default()
+            .1
+            .push(entry);
+    }
+
+    let mut pairs = vec![];
+    for (mut baseline_entries, mut entries) in by_name.into_values() {
+        baseline_entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+        entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+        let mut unpaired = vec![];
+        for entry in entries {
+            match baseline_entries.iter().position(|baseline_entry| {
+                baseline_entry.package.id.version == entry.package.id.version
+            }) {
+                Some(index) => {
+                    pairs.push((Some(baseline_entries.remove(index)), entry))
+                }
+                None => unpaired.push(entry),
+            }
+        }
+        let mut baseline_entries = baseline_entries.into_iter();
+        pairs.extend(
+            unpaired
+                .into_iter()
+                .map(|entry| (baseline_entries.next(), entry)),
+        );
+    }
+    pairs.sort_by(|a, b| a.1.package.id.cmp(&b.1.package.id));
+    pairs
+}
+
+fn find_regressions(
+    baseline_entry: &ReportEntry,
+    entry: &ReportEntry,
+) -> Vec<BaselineRegressionKind> {
+    let baseline_used = &baseline_entry.unsafety.used;
+    let used = &entry.unsafety.used;
+    let counts = [
+        ("functions", &baseline_used.functions, &used.functions),
+        ("exprs", &baseline_used.exprs, &used.exprs),
+        ("item-impls", &baseline_used.item_impls, &used.item_impls),
+        ("item-traits", &baseline_used.item_traits, &used.item_traits),
+        ("methods", &baseline_used.methods, &used.methods),
+    ];
+
+    let mut regressions = counts
+        .iter()
+        .filter(|(_, baseline_count, count)| {
+            count.unsafe_ > baseline_count.unsafe_
+        })
+        .map(|(kind, baseline_count, count)| {
+            BaselineRegressionKind::UnsafeIncreased {
+                kind,
+                baseline: baseline_count.unsafe_,
+                current: count.unsafe_,
+            }
+        })
+        .collect::<Vec<_>>();
+    if baseline_entry.unsafety.forbids_unsafe && !entry.unsafety.forbids_unsafe
+    {
+        regressions.push(BaselineRegressionKind::ForbidUnsafeRemoved);
+    }
+    regressions
+}
+
+/// The baseline entry of the current package with each unsafe count lowered
+/// to the current one, if it went down
+fn lower_counts(
+    baseline_entry: &ReportEntry,
+    entry: &ReportEntry,
+) -> ReportEntry {
+    let mut lowered = baseline_entry.clone();
+    lowered.package = entry.package.clone();
+    let used = &mut lowered.unsafety.used;
+    let current = &entry.unsafety.used;
+    for (count, current_count) in [
+        (&mut used.functions, &current.functions),
+        (&mut used.exprs, &current.exprs),
+        (&mut used.item_impls, &current.item_impls),
+        (&mut used.item_traits, &current.item_traits),
+        (&mut used.methods, &current.methods),
+    ]
+    .iter_mut()
+    {
+        count.unsafe_ = count.unsafe_.min(current_count.unsafe_);
+    }
+    lowered
+}
+
+#[cfg(test)]
+mod baseline_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::Source;
+    use rstest::*;
+    use url::Url;
+
+    #[rstest]
+    fn compare_with_baseline_test() {
+        let mut baseline = safety_report(vec![
+            report_entry(path_package_id("grown", "/old"), 1, false),
+            report_entry(path_package_id("lowered", "/old"), 5, false),
+            report_entry(path_package_id("removed", "/old"), 1, false),
+            report_entry(path_package_id("unforbidden", "/old"), 0, true),
+            report_entry(path_package_id("unchanged", "/old"), 2, false),
+            report_entry(package_id("bumped", "1.0.0"), 1, false),
+            report_entry(package_id("bumped_lowered", "1.0.0"), 2, false),
+        ]);
+        for entry in baseline.packages.values_mut() {
+            entry.unsafety.used.functions.unsafe_ = 3;
+        }
+        let report = safety_report(vec![
+            report_entry(path_package_id("grown", "/new"), 2, false),
+            report_entry(path_package_id("lowered", "/new"), 3, false),
+            report_entry(path_package_id("unforbidden", "/new"), 0, false),
+            report_entry(path_package_id("unchanged", "/new"), 2, false),
+            report_entry(path_package_id("new_safe", "/new"), 0, true),
+            report_entry(path_package_id("new_unsafe", "/new"), 1, false),
+            report_entry(package_id("bumped", "2.0.0"), 2, false),
+            report_entry(package_id("bumped_lowered", "1.1.0"), 1, false),
+        ]);
+
+        let comparison = compare_with_baseline(&baseline, &report);
+
+        assert_eq!(
+            comparison
+                .regressions
+                .iter()
+                .map(|regression| regression.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "bumped 2.0.0: unsafe exprs increased from 1 to 2",
+                "grown 1.0.0: unsafe exprs increased from 1 to 2",
+                "new_unsafe 1.0.0: new package using unsafe code",
+                "unforbidden 1.0.0: no longer declares #![forbid(unsafe_code)]",
+            ]
+        );
+        assert!(comparison.improved);
+
+        let mut ratcheted = comparison
+            .ratcheted
+            .packages
+            .values()
+            .map(|entry| {
+                (
+                    entry.package.id.name.as_str(),
+                    entry.package.id.version.to_string(),
+                    entry.unsafety.used.functions.unsafe_,
+                    entry.unsafety.used.exprs.unsafe_,
+                    entry.unsafety.forbids_unsafe,
+                )
+            })
+            .collect::<Vec<_>>();
+        ratcheted.sort();
+        assert_eq!(
+            ratcheted,
+            vec![
+                ("bumped", String::from("2.0.0"), 0, 1, false),
+                ("bumped_lowered", String::from("1.1.0"), 0, 1, false),
+                ("grown", String::from("1.0.0"), 0, 1, false),
+                ("lowered", String::from("1.0.0"), 0, 3, false),
+                ("new_safe", String::from("1.0.0"), 0, 0, true),
+                ("unchanged", String::from("1.0.0"), 0, 2, false),
+                ("unforbidden", String::from("1.0.0"), 0, 0, true),
+            ]
+        );
+
+        let comparison = compare_with_baseline(&baseline, &baseline);
+        assert!(comparison.regressions.is_empty());
+        assert!(!comparison.improved);
+    }
+
+    fn path_package_id(name: &str, path: &str) -> PackageId {
+        PackageId {
+            source: Source::Path(
+                Url::from_file_path(format!("{}/{}", path, name)).unwrap(),
+            ),
+            ..package_id(name, "1.0.0")
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use flate2::read::GzDecoder;
+use geiger::{find_unsafe_in_string, IncludeTests, RsFileMetrics};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tar::Archive;
+
+/// Scan result for a single `.crate` archive, written as one JSON line
+#[derive(Debug, Default, Serialize)]
+pub struct CrateScan {
+    /// File name of the `.crate` archive
+    pub archive: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// Whether every entry point of the crate declares
+    /// `#![forbid(unsafe_code)]`
+    pub forbids_unsafe: bool,
+    pub files: Vec<FileScan>,
+    /// Set when the archive itself could not be read, in which case `files`
+    /// holds the files read before the failure, or when its `Cargo.toml`
+    /// could not be parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Scan result for a single `.rs` file inside a `.crate` archive
+#[derive(Debug, Serialize)]
+pub struct FileScan {
+    /// Path of the file relative to the root of the crate
+    pub path: String,
+    pub is_entry_point: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<RsFileMetrics>,
+    /// Set when the file could not be decoded or parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Totals reported once every archive has been scanned
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct DatasetSummary {
+    pub crates: usize,
+    pub failed_crates: usize,
+    pub files: usize,
+    pub failed_files: usize,
+}
+
+/// Scan every `.crate` archive in `crates_dir` in parallel and write one JSON
+/// line per crate to `output`, in the order the scans finish.
+pub fn scan_crate_archives<W: Write + Send>(
+    crates_dir: &Path,
+    include_tests: IncludeTests,
+    output: W,
+) -> io::Result<DatasetSummary> {
+    let mut archive_paths = fs::read_dir(crates_dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "crate"))
+        .collect::<Vec<PathBuf>>();
+    archive_paths.sort();
+
+    let output = Mutex::new(output);
+    let failed_crates = AtomicUsize::new(0);
+    let files = AtomicUsize::new(0);
+    let failed_files = AtomicUsize::new(0);
+
+    archive_paths.par_iter().try_for_each(|archive_path| {
+        let crate_scan = scan_crate_archive(archive_path, include_tests);
+
+        failed_crates
+            .fetch_add(crate_scan.error.is_some() as usize, Ordering::Relaxed);
+        files.fetch_add(crate_scan.files.len(), Ordering::Relaxed);
+        failed_files.fetch_add(
+            crate_scan
+                .files
+                .iter()
+                .filter(|file| file.error.is_some())
+                .count(),
+            Ordering::Relaxed,
+        );
+
+        let line = serde_json::to_string(&crate_scan).unwrap();
+        writeln!(output.lock().unwrap(), "{}", line)
+    })?;
+
+    output.into_inner().unwrap().flush()?;
+
+    Ok(DatasetSummary {
+        crates: archive_paths.len(),
+        failed_crates: failed_crates.into_inner(),
+        files: files.into_inner(),
+        failed_files: failed_files.into_inner(),
+    })
+}
+
+/// Stream the `.rs` files of a `.crate` archive through the scanner, without
+/// extracting the archive to disk
+pub fn scan_crate_archive(
+    archive_path: &Path,
+    include_tests: IncludeTests,
+) -> CrateScan {
+    let mut crate_scan = CrateScan {
+        archive: archive_path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let mut manifest = None;
+    if let Err(error) = File::open(archive_path).and_then(|file| {
+        read_crate_archive(file, include_tests, &mut crate_scan, &mut manifest)
+    }) {
+        crate_scan.error = Some(error.to_string());
+    }
+
+    let manifest = manifest.and_then(|manifest| {
+        toml::from_str::<toml::Value>(&manifest)
+            .map_err(|error| {
+                crate_scan.error.get_or_insert_with(|| {
+                    format!("Failed to parse Cargo.toml: {}", error)
+                });
+            })
+            .ok()
+    });
+
+    if let Some(package) = manifest.as_ref().and_then(|m| m.get("package")) {
+        crate_scan.name = package
+            .get("name")
+            .and_then(toml::Value::as_str)
+            .map(String::from);
+        crate_scan.version = package
+            .get("version")
+            .and_then(toml::Value::as_str)
+            .map(String::from);
+    }
+
+    let entry_points = entry_point_paths(
+        manifest.as_ref(),
+        crate_scan.files.iter().map(|file| file.path.as_str()),
+    );
+    for file in &mut crate_scan.files {
+        file.is_entry_point = entry_points.contains(&file.path);
+    }
+
+    let mut entry_point_files = crate_scan
+        .files
+        .iter()
+        .filter(|file| file.is_entry_point)
+        .peekable();
+    crate_scan.forbids_unsafe = entry_point_files.peek().is_some()
+        && entry_point_files.all(|file| {
+            file.metrics
+                .as_ref()
+                .is_some_and(|metrics| metrics.forbids_unsafe)
+        });
+
+    crate_scan
+}
+
+fn read_crate_archive<R: Read>(
+    reader: R,
+    include_tests: IncludeTests,
+    crate_scan: &mut CrateScan,
+    manifest: &mut Option<String>,
+) -> io::Result<()> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        // Every path in a `.crate` archive starts with `<name>-<version>/`
+        let path = entry
+            .path()?
+            .components()
+            .skip(1)
+            .collect::<PathBuf>()
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if path == "Cargo.toml" {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            *manifest = Some(contents);
+            continue;
+        }
+
+        if !path.ends_with(".rs") {
+            continue;
+        }
+
+        let mut bytes = vec![];
+        entry.read_to_end(&mut bytes)?;
+
+        let (metrics, error) = match String::from_utf8(bytes) {
+            Ok(src) => match find_unsafe_in_string(&src, include_tests) {
+                Ok(metrics) => (Some(metrics), None),
+                Err(error) => (None, Some(error.to_string())),
+            },
+            Err(error) => (None, Some(error.to_string())),
+        };
+
+        crate_scan.files.push(FileScan {
+            path,
+            is_entry_point: false,
+            metrics,
+            error,
+        });
+    }
+
+    Ok(())
+}
+
+/// The library and binary entry points of a crate, as declared in its
+/// manifest or found at the locations cargo discovers them at. Like cargo,
+/// the discovered binaries are overridden by the declared ones of the same
+/// name, and nothing is discovered when `autolib` or `autobins` is false.
+fn entry_point_paths<'a>(
+    manifest: Option<&toml::Value>,
+    file_paths: impl Iterator<Item = &'a str>,
+) -> HashSet<String> {
+    let package = manifest.and_then(|m| m.get("package"));
+    let package_name = package
+        .and_then(|p| p.get("name"))
+        .and_then(toml::Value::as_str)
+        .unwrap_or_default();
+    let is_auto_discovered = |key: &str| {
+        package
+            .and_then(|p| p.get(key))
+            .and_then(toml::Value::as_bool)
+            .unwrap_or(true)
+    };
+    let file_paths = file_paths.collect::<Vec<&str>>();
+    let discovered_bins = discovered_bin_paths(package_name, &file_paths);
+
+    let mut entry_points = HashSet::new();
+
+    match manifest.and_then(|m| m.get("lib")) {
+        Some(lib) => {
+            entry_points.insert(
+                target_path(lib).unwrap_or_else(|| String::from("src/lib.rs")),
+            );
+        }
+        None if is_auto_discovered("autolib")
+            && file_paths.contains(&"src/lib.rs") =>
+        {
+            entry_points.insert(String::from("src/lib.rs"));
+        }
+        None => {}
+    }
+
+    let declared_bins = match manifest.and_then(|m| m.get("bin")) {
+        Some(toml::Value::Array(bins)) => bins.iter().collect(),
+        Some(bin) => vec![bin],
+        None => vec![],
+    };
+    let mut declared_bin_names = HashSet::new();
+    for bin in declared_bins {
+        let name = bin.get("name").and_then(toml::Value::as_str);
+        declared_bin_names.extend(name);
+        let path = target_path(bin).or_else(|| {
+            discovered_bins
+                .iter()
+                .find(|(discovered_name, _)| Some(*discovered_name) == name)
+                .map(|(_, path)| String::from(*path))
+        });
+        entry_points.extend(path);
+    }
+
+    if is_auto_discovered("autobins") {
+        entry_points.extend(
+            discovered_bins
+                .iter()
+                .filter(|(name, _)| !declared_bin_names.contains(name))
+                .map(|(_, path)| String::from(*path)),
+        );
+    }
+
+    entry_points
+}
+
+/// The path of a `[lib]` or `[[bin]]` target, if it declares one
+fn target_path(target: &toml::Value) -> Option<String> {
+    target
+        .get("path")?
+        .as_str()
+        .map(|path| path.trim_start_matches("./").replace('\\', "/"))
+}
+
+/// The binaries cargo discovers, by name: `src/main.rs`, named after the
+/// package, `src/bin/<name>.rs` and `src/bin/<name>/main.rs`
+fn discovered_bin_paths<'a>(
+    package_name: &'a str,
+    file_paths: &[&'a str],
+) -> Vec<(&'a str, &'a str)> {
+    file_paths
+        .iter()
+        .filter_map(|&path| {
+            if path == "src/main.rs" {
+                return Some((package_name, path));
+            }
+            let bin_path = path.strip_prefix("src/bin/")?;
+            match bin_path.split('/').collect::<Vec<&str>>()[..] {
+                [file_name] => Some((file_name.strip_suffix(".rs")?, path)),
+                [directory, "main.rs"] => Some((directory, path)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod dataset_tests {
+    use super::*;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn scan_crate_archive_test() {
+        let crates_dir = tempdir().unwrap();
+        let archive_path = crates_dir.path().join("demo-0.1.0.crate");
+        write_crate_archive(
+            &archive_path,
+            &[
+                (
+                    "demo-0.1.0/Cargo.toml",
+                    "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+                ),
+                ("demo-0.1.0/src/lib.rs", "#![forbid(unsafe_code)]\nmod a;"),
+                ("demo-0.1.0/src/a.rs", "pub unsafe fn f() {}"),
+                ("demo-0.1.0/src/broken.rs", "fn ("),
+                ("demo-0.1.0/README.md", "not scanned"),
+            ],
+        );
+
+        let crate_scan = scan_crate_archive(&archive_path, IncludeTests::No);
+
+        assert_eq!(crate_scan.archive, "demo-0.1.0.crate");
+        assert_eq!(crate_scan.name.as_deref(), Some("demo"));
+        assert_eq!(crate_scan.version.as_deref(), Some("0.1.0"));
+        assert!(crate_scan.forbids_unsafe);
+        assert_eq!(crate_scan.error, None);
+        assert_eq!(
+            crate_scan
+                .files
+                .iter()
+                .map(|file| (
+                    file.path.as_str(),
+                    file.is_entry_point,
+                    file.metrics.is_some(),
+                    file.error.is_some()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("src/lib.rs", true, true, false),
+                ("src/a.rs", false, true, false),
+                ("src/broken.rs", false, false, true),
+            ]
+        );
+        assert_eq!(
+            crate_scan.files[1]
+                .metrics
+                .as_ref()
+                .unwrap()
+                .counters
+                .functions
+                .unsafe_,
+            1
+        );
+    }
+
+    #[rstest]
+    fn scan_crate_archives_test() {
+        let crates_dir = tempdir().unwrap();
+        write_crate_archive(
+            &crates_dir.path().join("a-1.0.0.crate"),
+            &[("a-1.0.0/src/main.rs", "fn main() {}")],
+        );
+        fs::write(crates_dir.path().join("b-1.0.0.crate"), "not gzip").unwrap();
+        fs::write(crates_dir.path().join("ignored.txt"), "").unwrap();
+
+        let mut output = vec![];
+        let summary = scan_crate_archives(
+            crates_dir.path(),
+            IncludeTests::No,
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary,
+            DatasetSummary {
+                crates: 2,
+                failed_crates: 1,
+                files: 1,
+                failed_files: 0,
+            }
+        );
+        let lines = String::from_utf8(output).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        for line in lines.lines() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+    }
+
+    #[rstest(
+        input_manifest,
+        expected_entry_points,
+        case(None, vec!["src/bin/tool.rs", "src/bin/x/main.rs", "src/lib.rs"]),
+        case(
+            Some("[lib]\npath = \"lib/custom.rs\"\n[[bin]]\nname = \"b\"\npath = \"./tools/b.rs\"\n"),
+            vec![
+                "lib/custom.rs",
+                "src/bin/tool.rs",
+                "src/bin/x/main.rs",
+                "tools/b.rs"
+            ]
+        ),
+        case(
+            Some("[[bin]]\nname = \"tool\"\npath = \"tools/tool.rs\"\n"),
+            vec!["src/bin/x/main.rs", "src/lib.rs", "tools/tool.rs"]
+        ),
+        case(
+            Some("[package]\nautobins = false\n"),
+            vec!["src/lib.rs"]
+        ),
+        case(
+            Some("[package]\nautobins = false\n[[bin]]\nname = \"x\"\n"),
+            vec!["src/bin/x/main.rs", "src/lib.rs"]
+        ),
+        case(
+            Some("[package]\nautolib = false\n"),
+            vec!["src/bin/tool.rs", "src/bin/x/main.rs"]
+        ),
+        case(
+            Some("[package]\nautolib = false\n[lib]\n"),
+            vec!["src/bin/tool.rs", "src/bin/x/main.rs", "src/lib.rs"]
+        )
+    )]
+    fn entry_point_paths_test(
+        input_manifest: Option<&str>,
+        expected_entry_points: Vec<&str>,
+    ) {
+        let manifest =
+            input_manifest.map(|m| toml::from_str::<toml::Value>(m).unwrap());
+        let file_paths = [
+            "src/lib.rs",
+            "src/a.rs",
+            "src/bin/tool.rs",
+            "src/bin/x/main.rs",
+            "src/bin/x/helper.rs",
+            "tests/it.rs",
+        ];
+
+        let mut entry_points =
+            entry_point_paths(manifest.as_ref(), file_paths.iter().copied())
+                .into_iter()
+                .collect::<Vec<String>>();
+        entry_points.sort();
+
+        assert_eq!(entry_points, expected_entry_points);
+    }
+
+    fn write_crate_archive(path: &Path, files: &[(&str, &str)]) {
+        let encoder =
+            GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (file_path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, file_path, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+}
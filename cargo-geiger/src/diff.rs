@@ -0,0 +1,348 @@
+use crate::format::print_config::OutputFormat;
+use crate::format::table::{table_row_delta, UNSAFE_COUNTERS_HEADER};
+
+use anyhow::anyhow;
+use cargo::CargoResult;
+use cargo_geiger_serde::{
+    CounterBlockDelta, PackageChange, PackageId, QuickReportEntry,
+    QuickSafetyReport, SafetyReport, SafetyReportDiff,
+};
+use std::fs;
+use std::path::Path;
+
+/// A JSON report, as written with `--output-format Json`
+enum Report {
+    Full(SafetyReport),
+    Quick(QuickSafetyReport),
+}
+
+impl Report {
+    fn into_quick(self) -> QuickSafetyReport {
+        match self {
+            Report::Full(report) => QuickSafetyReport {
+                packages: report
+                    .packages
+                    .into_iter()
+                    .map(|(package_id, entry)| {
+                        (
+                            package_id,
+                            QuickReportEntry {
+                                package: entry.package,
+                                forbids_unsafe: entry.unsafety.forbids_unsafe,
+                            },
+                        )
+                    })
+                    .collect(),
+                packages_without_metrics: report.packages_without_metrics,
+            },
+            Report::Quick(report) => report,
+        }
+    }
+}
+
+/// Read two JSON reports and compare them. When one of them was written with
+/// `--forbid-only`, only the packages and their forbid status are compared.
+pub fn read_report_diff(
+    old_report_path: &Path,
+    new_report_path: &Path,
+) -> CargoResult<SafetyReportDiff> {
+    match (read_report(old_report_path)?, read_report(new_report_path)?) {
+        (Report::Full(old), Report::Full(new)) => {
+            Ok(SafetyReportDiff::new(&old, &new))
+        }
+        (old, new) => Ok(SafetyReportDiff::from_quick_reports(
+            &old.into_quick(),
+            &new.into_quick(),
+        )),
+    }
+}
+
+/// The lines printed for the differences in the given output format
+pub fn report_diff_to_lines(
+    diff: &SafetyReportDiff,
+    output_format: OutputFormat,
+) -> Vec<String> {
+    match output_format {
+        OutputFormat::Json => vec![serde_json::to_string(diff).unwrap()],
+        _ if diff.is_empty() => vec![String::from("No differences found")],
+        OutputFormat::GitHubMarkdown => report_diff_to_markdown_lines(diff),
+        _ => report_diff_to_text_lines(diff),
+    }
+}
+
+fn read_report(path: &Path) -> CargoResult<Report> {
+    let report = fs::read_to_string(path).map_err(|e| {
+        anyhow!("failed to read report `{}`: {}", path.display(), e)
+    })?;
+    serde_json::from_str(&report)
+        .map(Report::Full)
+        .or_else(|_| serde_json::from_str(&report).map(Report::Quick))
+        .map_err(|e| {
+            anyhow!("failed to parse report `{}`: {}", path.display(), e)
+        })
+}
+
+fn report_diff_to_text_lines(diff: &SafetyReportDiff) -> Vec<String> {
+    let mut lines = vec![];
+    let mut section = |title: &str, section_lines: Vec<String>| {
+        if !section_lines.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("{}:", title));
+            lines.push(String::new());
+            lines.extend(section_lines);
+        }
+    };
+
+    section(
+        "Added packages",
+        diff.added_packages
+            .iter()
+            .map(package_id_to_string)
+            .collect(),
+    );
+    section(
+        "Removed packages",
+        diff.removed_packages
+            .iter()
+            .map(package_id_to_string)
+            .collect(),
+    );
+
+    let mut changed_lines = vec![];
+    if diff
+        .changed_packages
+        .iter()
+        .any(|change| change.used.is_some())
+    {
+        changed_lines.push(format!(
+            "{}Package",
+            UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                .join(" ")
+        ));
+    }
+    for change in &diff.changed_packages {
+        changed_lines.push(match &change.used {
+            Some(used) => {
+                format!(
+                    "{} {}",
+                    table_row_delta(used),
+                    change_to_string(change)
+                )
+            }
+            None => change_to_string(change),
+        });
+    }
+    section(
+        "Changed packages, with the change of used unsafe code",
+        changed_lines,
+    );
+
+    section(
+        "Forbid status changes",
+        diff.forbid_changes
+            .iter()
+            .map(|forbid_change| {
+                format!(
+                    "{}: {}",
+                    package_id_to_string(&forbid_change.new),
+                    forbid_status(forbid_change.forbids_unsafe)
+                )
+            })
+            .collect(),
+    );
+    section(
+        "New unscanned files",
+        diff.new_unscanned_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+    );
+
+    lines.push(String::new());
+    lines
+}
+
+fn report_diff_to_markdown_lines(diff: &SafetyReportDiff) -> Vec<String> {
+    let mut lines = vec![];
+    let mut section = |title: &str, section_lines: Vec<String>| {
+        if !section_lines.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("### {}", title));
+            lines.push(String::new());
+            lines.extend(section_lines);
+        }
+    };
+    let bullet = |package_id: &PackageId| {
+        format!("- `{}` {}", package_id.name, package_id.version)
+    };
+
+    section(
+        "Added packages",
+        diff.added_packages.iter().map(bullet).collect(),
+    );
+    section(
+        "Removed packages",
+        diff.removed_packages.iter().map(bullet).collect(),
+    );
+
+    let mut changed_lines = vec![];
+    if !diff.changed_packages.is_empty() {
+        changed_lines.push(String::from(
+            "| Package | Old version | New version | Functions | Expressions | Impls | Traits | Methods |",
+        ));
+        changed_lines.push(String::from(
+            "| --- | --- | --- | ---: | ---: | ---: | ---: | ---: |",
+        ));
+    }
+    for change in &diff.changed_packages {
+        let counts = match &change.used {
+            Some(used) => delta_columns(used).join(" | "),
+            None => ["?"; 5].join(" | "),
+        };
+        changed_lines.push(format!(
+            "| `{}` | {} | {} | {} |",
+            change.new.name, change.old.version, change.new.version, counts
+        ));
+    }
+    section(
+        "Changed packages, with the change of used unsafe code",
+        changed_lines,
+    );
+
+    section(
+        "Forbid status changes",
+        diff.forbid_changes
+            .iter()
+            .map(|forbid_change| {
+                format!(
+                    "{}: {}",
+                    bullet(&forbid_change.new),
+                    forbid_status(forbid_change.forbids_unsafe)
+                )
+            })
+            .collect(),
+    );
+    section(
+        "New unscanned files",
+        diff.new_unscanned_files
+            .iter()
+            .map(|path| format!("- `{}`", path.display()))
+            .collect(),
+    );
+
+    lines
+}
+
+fn change_to_string(change: &PackageChange) -> String {
+    if change.old.version == change.new.version {
+        package_id_to_string(&change.new)
+    } else {
+        format!(
+            "{} {} -> {}",
+            change.new.name, change.old.version, change.new.version
+        )
+    }
+}
+
+fn delta_columns(delta: &CounterBlockDelta) -> Vec<String> {
+    [
+        delta.functions.unsafe_,
+        delta.exprs.unsafe_,
+        delta.item_impls.unsafe_,
+        delta.item_traits.unsafe_,
+        delta.methods.unsafe_,
+    ]
+    .iter()
+    .map(|change| format!("{:+}", change))
+    .collect()
+}
+
+fn forbid_status(forbids_unsafe: bool) -> &'static str {
+    if forbids_unsafe {
+        "now forbids unsafe code"
+    } else {
+        "no longer forbids unsafe code"
+    }
+}
+
+fn package_id_to_string(package_id: &PackageId) -> String {
+    format!("{} {}", package_id.name, package_id.version)
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+
+    #[rstest]
+    fn report_diff_to_lines_test() {
+        let old = safety_report(vec![
+            report_entry(package_id("bumped", "1.0.0"), 2, false),
+            report_entry(package_id("removed", "1.0.0"), 0, false),
+            report_entry(package_id("unforbidden", "1.0.0"), 0, true),
+        ]);
+        let new = safety_report(vec![
+            report_entry(package_id("added", "1.0.0"), 0, false),
+            report_entry(package_id("bumped", "1.1.0"), 5, false),
+            report_entry(package_id("unforbidden", "1.0.0"), 0, false),
+        ]);
+        let diff = SafetyReportDiff::new(&old, &new);
+
+        assert_eq!(
+            report_diff_to_lines(&diff, OutputFormat::Ascii),
+            vec![
+                "",
+                "Added packages:",
+                "",
+                "added 1.0.0",
+                "",
+                "Removed packages:",
+                "",
+                "removed 1.0.0",
+                "",
+                "Changed packages, with the change of used unsafe code:",
+                "",
+                "Functions  Expressions  Impls  Traits  Methods Package",
+                "+0         +3           +0     +0      +0      bumped 1.0.0 -> 1.1.0",
+                "",
+                "Forbid status changes:",
+                "",
+                "unforbidden 1.0.0: no longer forbids unsafe code",
+                "",
+            ]
+        );
+        assert_eq!(
+            report_diff_to_lines(&diff, OutputFormat::GitHubMarkdown),
+            vec![
+                "### Added packages",
+                "",
+                "- `added` 1.0.0",
+                "",
+                "### Removed packages",
+                "",
+                "- `removed` 1.0.0",
+                "",
+                "### Changed packages, with the change of used unsafe code",
+                "",
+                "| Package | Old version | New version | Functions | Expressions | Impls | Traits | Methods |",
+                "| --- | --- | --- | ---: | ---: | ---: | ---: | ---: |",
+                "| `bumped` | 1.0.0 | 1.1.0 | +0 | +3 | +0 | +0 | +0 |",
+                "",
+                "### Forbid status changes",
+                "",
+                "- `unforbidden` 1.0.0: no longer forbids unsafe code",
+            ]
+        );
+        assert_eq!(
+            report_diff_to_lines(
+                &SafetyReportDiff::new(&old, &old),
+                OutputFormat::Utf8
+            ),
+            vec!["No differences found"]
+        );
+    }
+}
@@ -1,11 +1,23 @@
+pub mod code_quality;
+pub mod csv;
+pub mod cyclonedx;
+pub mod dependency_graph;
 pub mod emoji_symbols;
+pub mod github_annotations;
+pub mod html;
+pub mod junit;
+pub mod openmetrics;
 pub mod pattern;
 pub mod print_config;
+pub mod purl;
+pub mod sarif;
+pub mod spdx;
 pub mod table;
 
 mod display;
 mod parse;
 
+use cargo_geiger_serde::{Source, UnsafeInfo};
 use krates::cm::DependencyKind;
 use std::fmt;
 use strum_macros::EnumIter;
@@ -25,6 +37,16 @@ pub enum CrateDetectionStatus {
     UnsafeDetected,
 }
 
+impl From<&UnsafeInfo> for CrateDetectionStatus {
+    fn from(unsafe_info: &UnsafeInfo) -> Self {
+        match (unsafe_info.forbids_unsafe, unsafe_info.used.has_unsafe()) {
+            (_, true) => CrateDetectionStatus::UnsafeDetected,
+            (true, false) => CrateDetectionStatus::NoneDetectedForbidsUnsafe,
+            (false, false) => CrateDetectionStatus::NoneDetectedAllowsUnsafe,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum RawChunk<'a> {
     Argument(&'a str),
@@ -64,6 +86,17 @@ pub fn get_kind_group_name(dep_kind: DependencyKind) -> Option<&'static str> {
     }
 }
 
+/// The kind of source a package comes from
+pub fn source_kind(source: &Source) -> &'static str {
+    match source {
+        Source::Git { .. } => "git",
+        Source::Registry { .. } => "registry",
+        Source::Path(_) => "path",
+        Source::Directory(_) => "directory",
+        Source::LocalRegistry(_) => "local-registry",
+    }
+}
+
 #[cfg(test)]
 mod format_tests {
     use super::*;
@@ -84,4 +117,30 @@ mod format_tests {
 
         assert_eq!(get_kind_group_name(DependencyKind::Normal), None);
     }
+
+    #[rstest(
+        input_forbids_unsafe,
+        input_unsafe_exprs,
+        expected_crate_detection_status,
+        case(true, 0, CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+        case(false, 0, CrateDetectionStatus::NoneDetectedAllowsUnsafe),
+        case(true, 1, CrateDetectionStatus::UnsafeDetected),
+        case(false, 1, CrateDetectionStatus::UnsafeDetected)
+    )]
+    fn crate_detection_status_from_unsafe_info_test(
+        input_forbids_unsafe: bool,
+        input_unsafe_exprs: u64,
+        expected_crate_detection_status: CrateDetectionStatus,
+    ) {
+        let mut unsafe_info = UnsafeInfo {
+            forbids_unsafe: input_forbids_unsafe,
+            ..Default::default()
+        };
+        unsafe_info.used.exprs.unsafe_ = input_unsafe_exprs;
+
+        assert_eq!(
+            CrateDetectionStatus::from(&unsafe_info),
+            expected_crate_detection_status
+        );
+    }
 }
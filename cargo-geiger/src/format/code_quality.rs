@@ -0,0 +1,403 @@
+use super::sarif::{rule_id, rule_name};
+use crate::scan::UsedUnsafeSite;
+
+use cargo_geiger_serde::PackageId;
+use geiger::{unsafe_site_tokens, UnsafeKind, UnsafeSite};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// The file the issues of dependencies are located in, relative to the
+/// workspace root
+const LOCKFILE_NAME: &str = "Cargo.lock";
+
+/// An issue of a GitLab Code Quality report
+#[derive(Debug, Serialize)]
+pub struct CodeQualityIssue {
+    description: String,
+    check_name: &'static str,
+    fingerprint: String,
+    /// One of `info`, `minor`, `major`, `critical` and `blocker`
+    severity: &'static str,
+    location: Location,
+}
+
+#[derive(Debug, Serialize)]
+struct Location {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Debug, Serialize)]
+struct Lines {
+    begin: usize,
+    end: usize,
+}
+
+/// Build a GitLab Code Quality report from the unsafe sites used by the
+/// build, with one issue per unsafe function, block, impl and method of the
+/// workspace members and one per dependency using unsafe code.
+///
+/// The fingerprint of an issue in a workspace member is a hash of the path of
+/// its file relative to `workspace_root` and of its tokens, so it stays the
+/// same when lines are added above it or it is reformatted. The tokens are
+/// read from the files of the workspace members. If they can not be read,
+/// a warning is printed and the kind and position of the item or block are
+/// hashed instead.
+///
+/// The issue of a dependency is located at its `[[package]]` entry in the
+/// `Cargo.lock` of `workspace_root`, or at the first line of that file if
+/// the entry is not found.
+pub fn unsafe_sites_to_code_quality(
+    unsafe_sites: &[UsedUnsafeSite],
+    workspace_root: &Path,
+) -> Vec<CodeQualityIssue> {
+    let mut issues = vec![];
+    let mut occurrences = HashMap::new();
+    let mut sources = HashMap::<&Path, Option<String>>::new();
+    let mut dependencies = BTreeMap::<&PackageId, BTreeMap<_, usize>>::new();
+    for used_unsafe_site in unsafe_sites {
+        let site = &used_unsafe_site.site;
+        if !used_unsafe_site.is_workspace_member {
+            *dependencies
+                .entry(&used_unsafe_site.package)
+                .or_default()
+                .entry(site.kind)
+                .or_default() += 1;
+            continue;
+        }
+        // Declaring an unsafe trait uses no unsafe code itself, its impls do
+        if site.kind == UnsafeKind::Trait {
+            continue;
+        }
+        let path = used_unsafe_site
+            .path
+            .strip_prefix(workspace_root)
+            .unwrap_or(&used_unsafe_site.path)
+            .display()
+            .to_string();
+        let source = sources
+            .entry(&used_unsafe_site.path)
+            .or_insert_with(|| read_source(&used_unsafe_site.path))
+            .as_deref();
+        let tokens = source
+            .and_then(|source| unsafe_site_tokens(source, site))
+            .unwrap_or_else(|| {
+                if source.is_some() {
+                    eprintln!(
+                        "WARNING: Failed to read the tokens of the unsafe code at {}:{}",
+                        used_unsafe_site.path.display(),
+                        site.line
+                    );
+                }
+                site_position(site)
+            });
+        // Identical items in the same file are told apart by their order
+        let occurrence = occurrences
+            .entry((path.clone(), tokens.clone()))
+            .and_modify(|occurrence| *occurrence += 1)
+            .or_insert(0_usize);
+        issues.push(CodeQualityIssue {
+            description: format!(
+                "{} in {} {}",
+                rule_name(site.kind),
+                used_unsafe_site.package.name,
+                used_unsafe_site.package.version
+            ),
+            check_name: rule_id(site.kind),
+            fingerprint: fingerprint(&[
+                &path,
+                &tokens,
+                &occurrence.to_string(),
+            ]),
+            severity: severity(site.kind),
+            location: Location {
+                path,
+                lines: Lines {
+                    begin: site.line,
+                    end: site.end_line,
+                },
+            },
+        });
+    }
+
+    let lockfile = match dependencies.is_empty() {
+        true => None,
+        false => fs::read_to_string(workspace_root.join(LOCKFILE_NAME)).ok(),
+    };
+    let lockfile_package_lines = lockfile
+        .as_deref()
+        .map(lockfile_package_lines)
+        .unwrap_or_default();
+    for (package_id, kind_counts) in dependencies {
+        let counts = kind_counts
+            .iter()
+            .map(|(kind, count)| {
+                format!("{}: {}", rule_name(*kind).to_lowercase(), count)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        issues.push(CodeQualityIssue {
+            description: format!(
+                "Dependency {} {} uses unsafe code in the build: {}",
+                package_id.name, package_id.version, counts
+            ),
+            check_name: "unsafe-dependency",
+            fingerprint: fingerprint(&[
+                "dependency",
+                &package_id.name,
+                &package_id.version.to_string(),
+            ]),
+            severity: "info",
+            location: Location {
+                path: String::from(LOCKFILE_NAME),
+                lines: lockfile_package_lines
+                    .get(&(
+                        package_id.name.as_str(),
+                        package_id.version.to_string(),
+                    ))
+                    .map(|&(begin, end)| Lines { begin, end })
+                    .unwrap_or(Lines { begin: 1, end: 1 }),
+            },
+        });
+    }
+    issues
+}
+
+fn read_source(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .map_err(|error| {
+            eprintln!(
+                "WARNING: Failed to read {}, the fingerprints of its unsafe code \
+                 change when lines are added above it: {}",
+                path.display(),
+                error
+            )
+        })
+        .ok()
+}
+
+/// The kind and span of an unsafe site, identifying it when its tokens are
+/// not known
+fn site_position(site: &UnsafeSite) -> String {
+    format!(
+        "{} {}:{}-{}:{}",
+        rule_id(site.kind),
+        site.line,
+        site.column,
+        site.end_line,
+        site.end_column
+    )
+}
+
+/// The lines from the `[[package]]` header to the `version` of each package
+/// in a `Cargo.lock` file, by name and version
+fn lockfile_package_lines(
+    lockfile: &str,
+) -> HashMap<(&str, String), (usize, usize)> {
+    let mut package_lines = HashMap::new();
+    let mut begin = None;
+    let mut name = None;
+    for (index, line) in lockfile.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            begin = (line == "[[package]]").then_some(index + 1);
+            name = None;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"'));
+        } else if let Some(value) = line.strip_prefix("version = ") {
+            if let (Some(begin), Some(name)) = (begin, name) {
+                package_lines
+                    .entry((name, String::from(value.trim_matches('"'))))
+                    .or_insert((begin, index + 1));
+            }
+        }
+    }
+    package_lines
+}
+
+fn severity(kind: UnsafeKind) -> &'static str {
+    match kind {
+        UnsafeKind::Block | UnsafeKind::Trait => "minor",
+        UnsafeKind::Function | UnsafeKind::Method => "major",
+        UnsafeKind::Impl => "critical",
+    }
+}
+
+/// The hex encoded SHA-256 of the parts, separated by NUL characters
+fn fingerprint(parts: &[&str]) -> String {
+    format!("{:x}", Sha256::digest(parts.join("\0").as_bytes()))
+}
+
+#[cfg(test)]
+mod code_quality_tests {
+    use super::*;
+
+    use crate::lib_tests::used_unsafe_site;
+    use geiger::{find_unsafe_and_sites_in_string, IncludeTests};
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn unsafe_sites_to_code_quality_test() {
+        let workspace_root = tempdir().unwrap();
+        let member_path = workspace_root.path().join("member/src/lib.rs");
+        let member_src = "\
+unsafe trait T {}
+fn a() {
+    unsafe { f() }
+}
+fn b() {
+    unsafe {
+        f()
+    }
+}
+struct S;
+unsafe impl T for S {}
+";
+        fs::create_dir_all(member_path.parent().unwrap()).unwrap();
+        fs::write(&member_path, member_src).unwrap();
+        fs::write(
+            workspace_root.path().join("Cargo.lock"),
+            "\
+version = 3
+
+[[package]]
+name = \"dep\"
+version = \"1.0.0\"
+
+[[package]]
+name = \"dep\"
+version = \"1.2.3\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+",
+        )
+        .unwrap();
+        let mut unsafe_sites =
+            find_unsafe_and_sites_in_string(member_src, IncludeTests::No)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|site| UsedUnsafeSite {
+                    path: member_path.clone(),
+                    site,
+                    ..used_unsafe_site("member", true, "", UnsafeKind::Block, 1)
+                })
+                .collect::<Vec<UsedUnsafeSite>>();
+        unsafe_sites.extend(vec![
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Block,
+                1,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Block,
+                2,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Function,
+                3,
+            ),
+        ]);
+
+        let issues =
+            unsafe_sites_to_code_quality(&unsafe_sites, workspace_root.path());
+        let json = serde_json::to_value(&issues).unwrap();
+
+        assert_eq!(json.as_array().unwrap().len(), 4);
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "description": "Unsafe block expression in member 1.2.3",
+                "check_name": "unsafe-block-expr",
+                "fingerprint": fingerprint(&["member/src/lib.rs", "unsafe { f () }", "0"]),
+                "severity": "minor",
+                "location": {
+                    "path": "member/src/lib.rs",
+                    "lines": { "begin": 3, "end": 3 }
+                }
+            })
+        );
+        assert_eq!(
+            json[1]["fingerprint"],
+            fingerprint(&["member/src/lib.rs", "unsafe { f () }", "1"])
+        );
+        assert_eq!(json[2]["check_name"], "unsafe-impl");
+        assert_eq!(json[2]["severity"], "critical");
+        assert_eq!(
+            json[3]["description"],
+            "Dependency dep 1.2.3 uses unsafe code in the build: unsafe function: 1, unsafe block expression: 2"
+        );
+        assert_eq!(
+            json[3]["location"],
+            serde_json::json!({
+                "path": "Cargo.lock",
+                "lines": { "begin": 7, "end": 9 }
+            })
+        );
+        assert_eq!(json[3]["severity"], "info");
+    }
+
+    #[rstest]
+    fn unsafe_sites_to_code_quality_test_unreadable_file() {
+        let workspace_root = tempdir().unwrap();
+        let unsafe_sites = vec![
+            used_unsafe_site(
+                "member",
+                true,
+                &workspace_root
+                    .path()
+                    .join("src/missing.rs")
+                    .display()
+                    .to_string(),
+                UnsafeKind::Block,
+                3,
+            ),
+            used_unsafe_site(
+                "member",
+                true,
+                &workspace_root
+                    .path()
+                    .join("src/missing.rs")
+                    .display()
+                    .to_string(),
+                UnsafeKind::Block,
+                7,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Block,
+                1,
+            ),
+        ];
+
+        let issues =
+            unsafe_sites_to_code_quality(&unsafe_sites, workspace_root.path());
+        let json = serde_json::to_value(&issues).unwrap();
+
+        assert_eq!(
+            json[0]["fingerprint"],
+            fingerprint(&["src/missing.rs", "unsafe-block-expr 3:5-5:5", "0"])
+        );
+        assert_eq!(
+            json[1]["fingerprint"],
+            fingerprint(&["src/missing.rs", "unsafe-block-expr 7:5-9:5", "0"])
+        );
+        assert_eq!(
+            json[2]["location"]["lines"],
+            serde_json::json!({ "begin": 1, "end": 1 })
+        );
+    }
+}
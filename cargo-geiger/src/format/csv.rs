@@ -0,0 +1,291 @@
+use super::source_kind;
+use crate::scan::PackageFile;
+
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport};
+use std::collections::{HashMap, HashSet};
+
+/// The counters of a `CounterBlock`, in column order
+const COUNTER_NAMES: [&str; 5] =
+    ["functions", "exprs", "item_impls", "item_traits", "methods"];
+
+/// The kinds of dependency, in the order they are listed in
+const DEPENDENCY_KINDS: [&str; 3] = ["normal", "build", "dev"];
+
+/// How the fields of a row are separated
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Separator {
+    /// Comma separated values, quoted as described in RFC 4180
+    Comma,
+    /// Tab separated values, with tabs and line breaks in fields replaced by
+    /// spaces
+    Tab,
+}
+
+/// Render a `SafetyReport` with one row per package, ordered by package id.
+/// The used and unused counters are left empty for packages which were not
+/// scanned.
+pub fn safety_report_to_csv(
+    report: &SafetyReport,
+    separator: Separator,
+) -> String {
+    let mut header = vec![
+        String::from("name"),
+        String::from("version"),
+        String::from("source"),
+        String::from("dependency_kinds"),
+        String::from("forbids_unsafe"),
+    ];
+    header.extend(counter_columns("used_"));
+    header.extend(counter_columns("unused_"));
+
+    let dependency_kinds = dependency_kinds(report);
+    let mut rows = vec![header];
+    for package_id in sorted_package_ids(report) {
+        let entry = report.packages.get(package_id);
+        let mut row = package_columns(package_id);
+        row.push(
+            dependency_kinds
+                .get(package_id)
+                .map(|kinds| {
+                    DEPENDENCY_KINDS
+                        .iter()
+                        .filter(|kind| kinds.contains(*kind))
+                        .copied()
+                        .collect::<Vec<&str>>()
+                        .join(";")
+                })
+                .unwrap_or_default(),
+        );
+        match entry {
+            Some(entry) => {
+                row.push(entry.unsafety.forbids_unsafe.to_string());
+                row.extend(counter_values(&entry.unsafety.used));
+                row.extend(counter_values(&entry.unsafety.unused));
+            }
+            None => {
+                row.extend(vec![String::new(); 1 + 4 * COUNTER_NAMES.len()])
+            }
+        }
+        rows.push(row);
+    }
+
+    join_rows(&rows, separator)
+}
+
+/// Render the scanned files of the packages in a `SafetyReport`, with one row
+/// per file, ordered by package id and path
+pub fn package_files_to_csv(
+    report: &SafetyReport,
+    package_files: &HashMap<PackageId, Vec<PackageFile>>,
+    separator: Separator,
+) -> String {
+    let mut header = vec![
+        String::from("name"),
+        String::from("version"),
+        String::from("source"),
+        String::from("path"),
+        String::from("used"),
+        String::from("forbids_unsafe"),
+    ];
+    header.extend(counter_columns(""));
+
+    let mut rows = vec![header];
+    for package_id in sorted_package_ids(report) {
+        for file in package_files.get(package_id).into_iter().flatten() {
+            let mut row = package_columns(package_id);
+            row.push(file.path.display().to_string());
+            row.push(file.is_used.to_string());
+            row.push(file.metrics.forbids_unsafe.to_string());
+            row.extend(counter_values(&file.metrics.counters));
+            rows.push(row);
+        }
+    }
+
+    join_rows(&rows, separator)
+}
+
+fn sorted_package_ids(report: &SafetyReport) -> Vec<&PackageId> {
+    let mut package_ids = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .collect::<Vec<&PackageId>>();
+    package_ids.sort();
+    package_ids
+}
+
+/// The kinds of dependency each package is depended on as
+fn dependency_kinds(
+    report: &SafetyReport,
+) -> HashMap<&PackageId, HashSet<&'static str>> {
+    let mut dependency_kinds = HashMap::new();
+    for entry in report.packages.values() {
+        for &(dependencies, kind) in &[
+            (&entry.package.dependencies, DEPENDENCY_KINDS[0]),
+            (&entry.package.build_dependencies, DEPENDENCY_KINDS[1]),
+            (&entry.package.dev_dependencies, DEPENDENCY_KINDS[2]),
+        ] {
+            for dependency in dependencies {
+                dependency_kinds
+                    .entry(dependency)
+                    .or_insert_with(HashSet::new)
+                    .insert(kind);
+            }
+        }
+    }
+    dependency_kinds
+}
+
+fn package_columns(package_id: &PackageId) -> Vec<String> {
+    vec![
+        package_id.name.clone(),
+        package_id.version.to_string(),
+        String::from(source_kind(&package_id.source)),
+    ]
+}
+
+fn counter_columns(prefix: &str) -> Vec<String> {
+    COUNTER_NAMES
+        .iter()
+        .flat_map(|name| {
+            vec![
+                format!("{}{}_safe", prefix, name),
+                format!("{}{}_unsafe", prefix, name),
+            ]
+        })
+        .collect()
+}
+
+fn counter_values(counter_block: &CounterBlock) -> Vec<String> {
+    [
+        &counter_block.functions,
+        &counter_block.exprs,
+        &counter_block.item_impls,
+        &counter_block.item_traits,
+        &counter_block.methods,
+    ]
+    .iter()
+    .flat_map(|count| vec![count.safe.to_string(), count.unsafe_.to_string()])
+    .collect()
+}
+
+fn join_rows(rows: &[Vec<String>], separator: Separator) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|field| escape_field(field, separator))
+                .collect::<Vec<String>>()
+                .join(match separator {
+                    Separator::Comma => ",",
+                    Separator::Tab => "\t",
+                })
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn escape_field(field: &str, separator: Separator) -> String {
+    match separator {
+        Separator::Comma if field.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        }
+        Separator::Comma => String::from(field),
+        Separator::Tab => field.replace(['\t', '\n', '\r'], " "),
+    }
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::{Count, DependencyKind};
+    use geiger::RsFileMetrics;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    fn csv_report() -> SafetyReport {
+        let mut root = report_entry(package_id("root", "0.1.0"), 0, true);
+        root.package
+            .add_dependency(package_id("dep", "0.1.0"), DependencyKind::Normal);
+        root.package
+            .add_dependency(package_id("dep", "0.1.0"), DependencyKind::Build);
+        root.package.add_dependency(
+            package_id("unscanned", "0.1.0"),
+            DependencyKind::Development,
+        );
+        let mut dep = report_entry(package_id("dep", "0.1.0"), 3, false);
+        dep.unsafety.used.exprs.safe = 1;
+        dep.unsafety.unused.methods.unsafe_ = 2;
+
+        let mut report = safety_report(vec![root, dep]);
+        report
+            .packages_without_metrics
+            .insert(package_id("unscanned", "0.1.0"));
+        report
+    }
+
+    #[rstest]
+    fn safety_report_to_csv_test() {
+        let csv = safety_report_to_csv(&csv_report(), Separator::Comma);
+
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                "name,version,source,dependency_kinds,forbids_unsafe,\
+                used_functions_safe,used_functions_unsafe,used_exprs_safe,used_exprs_unsafe,\
+                used_item_impls_safe,used_item_impls_unsafe,used_item_traits_safe,used_item_traits_unsafe,\
+                used_methods_safe,used_methods_unsafe,\
+                unused_functions_safe,unused_functions_unsafe,unused_exprs_safe,unused_exprs_unsafe,\
+                unused_item_impls_safe,unused_item_impls_unsafe,unused_item_traits_safe,unused_item_traits_unsafe,\
+                unused_methods_safe,unused_methods_unsafe",
+                "dep,0.1.0,registry,normal;build,false,0,0,1,3,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2",
+                "root,0.1.0,registry,,true,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0",
+                "unscanned,0.1.0,registry,dev,,,,,,,,,,,,,,,,,,,,,",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn package_files_to_csv_test() {
+        let package_files = vec![(
+            package_id("dep", "0.1.0"),
+            vec![PackageFile {
+                path: PathBuf::from("src/a\tb.rs"),
+                is_used: true,
+                metrics: RsFileMetrics {
+                    counters: CounterBlock {
+                        functions: Count {
+                            safe: 4,
+                            unsafe_: 1,
+                        },
+                        ..Default::default()
+                    },
+                    forbids_unsafe: false,
+                },
+            }],
+        )]
+        .into_iter()
+        .collect();
+
+        let tsv =
+            package_files_to_csv(&csv_report(), &package_files, Separator::Tab);
+
+        assert_eq!(
+            tsv.lines().nth(1),
+            Some("dep\t0.1.0\tregistry\tsrc/a b.rs\ttrue\tfalse\t4\t1\t0\t0\t0\t0\t0\t0\t0\t0")
+        );
+        assert_eq!(tsv.lines().count(), 2);
+    }
+
+    #[rstest(
+        input_field,
+        expected_field,
+        case("plain", "plain"),
+        case("a,b", "\"a,b\""),
+        case("say \"hi\"", "\"say \"\"hi\"\"\"")
+    )]
+    fn escape_field_test(input_field: &str, expected_field: &str) {
+        assert_eq!(escape_field(input_field, Separator::Comma), expected_field);
+    }
+}
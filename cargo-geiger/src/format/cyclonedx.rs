@@ -0,0 +1,254 @@
+use super::purl::package_url;
+
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport, UnsafeInfo};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+const BOM_FORMAT: &str = "CycloneDX";
+const SPEC_VERSION: &str = "1.5";
+
+/// A `CycloneDX` 1.5 BOM of the packages in the dependency graph, with the
+/// unsafe usage of each package in its `cargo-geiger:*` properties
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: BomMetadata,
+    components: Vec<Component>,
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct BomMetadata {
+    tools: Tools,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<Component>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Component {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: String,
+    version: String,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Serialize)]
+struct Property {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Dependency {
+    #[serde(rename = "ref")]
+    ref_: String,
+    depends_on: Vec<String>,
+}
+
+/// Build a `CycloneDX` BOM from a `SafetyReport`. The root package is described
+/// by the BOM metadata, and every other package of the report is listed as a
+/// component, including the ones which could not be scanned.
+pub fn safety_report_to_cyclonedx(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+) -> Bom {
+    let mut package_ids = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .collect::<Vec<&PackageId>>();
+    package_ids.sort();
+
+    let component = |package_id: &PackageId| Component {
+        type_: "library",
+        bom_ref: package_url(package_id),
+        name: package_id.name.clone(),
+        version: package_id.version.to_string(),
+        purl: package_url(package_id),
+        properties: report
+            .packages
+            .get(package_id)
+            .map(|entry| unsafety_properties(&entry.unsafety))
+            .unwrap_or_default(),
+    };
+
+    let components = package_ids
+        .iter()
+        .filter(|package_id| Some(**package_id) != root_package_id)
+        .map(|package_id| component(package_id))
+        .collect();
+
+    let dependencies = package_ids
+        .iter()
+        .map(|package_id| {
+            let depends_on = report
+                .packages
+                .get(*package_id)
+                .map(|entry| {
+                    entry
+                        .package
+                        .dependencies
+                        .iter()
+                        .chain(&entry.package.build_dependencies)
+                        .chain(&entry.package.dev_dependencies)
+                        .map(package_url)
+                        .collect::<BTreeSet<String>>()
+                })
+                .unwrap_or_default();
+            Dependency {
+                ref_: package_url(package_id),
+                depends_on: depends_on.into_iter().collect(),
+            }
+        })
+        .collect();
+
+    Bom {
+        bom_format: BOM_FORMAT,
+        spec_version: SPEC_VERSION,
+        version: 1,
+        metadata: BomMetadata {
+            tools: Tools {
+                components: vec![Tool {
+                    type_: "application",
+                    name: "cargo-geiger",
+                    version: env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            component: root_package_id.map(component),
+        },
+        components,
+        dependencies,
+    }
+}
+
+fn unsafety_properties(unsafe_info: &UnsafeInfo) -> Vec<Property> {
+    let mut properties = counter_block_properties("used", &unsafe_info.used);
+    properties.extend(counter_block_properties("unused", &unsafe_info.unused));
+    properties.push(Property {
+        name: String::from("cargo-geiger:forbids-unsafe"),
+        value: unsafe_info.forbids_unsafe.to_string(),
+    });
+    properties
+}
+
+fn counter_block_properties(
+    usage: &str,
+    counter_block: &CounterBlock,
+) -> Vec<Property> {
+    let counts = [
+        ("functions", &counter_block.functions),
+        ("exprs", &counter_block.exprs),
+        ("item-impls", &counter_block.item_impls),
+        ("item-traits", &counter_block.item_traits),
+        ("methods", &counter_block.methods),
+    ];
+    counts
+        .iter()
+        .flat_map(|(kind, count)| {
+            [("safe", count.safe), ("unsafe", count.unsafe_)].map(
+                |(safety, value)| Property {
+                    name: format!("cargo-geiger:{}:{}:{}", usage, kind, safety),
+                    value: value.to_string(),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod cyclonedx_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::DependencyKind;
+    use rstest::*;
+
+    #[rstest]
+    fn safety_report_to_cyclonedx_test() {
+        let mut root = report_entry(package_id("root", "0.1.0"), 2, false);
+        root.package
+            .add_dependency(package_id("dep", "0.1.0"), DependencyKind::Normal);
+        root.package.add_dependency(
+            package_id("unscanned", "0.1.0"),
+            DependencyKind::Build,
+        );
+        let mut report = safety_report(vec![
+            root,
+            report_entry(package_id("dep", "0.1.0"), 2, false),
+        ]);
+        report
+            .packages_without_metrics
+            .insert(package_id("unscanned", "0.1.0"));
+
+        let bom = serde_json::to_value(safety_report_to_cyclonedx(
+            &report,
+            Some(&package_id("root", "0.1.0")),
+        ))
+        .unwrap();
+
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["specVersion"], "1.5");
+        assert_eq!(
+            bom["metadata"]["component"]["bom-ref"],
+            "pkg:cargo/root@0.1.0"
+        );
+        let components = bom["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0]["purl"], "pkg:cargo/dep@0.1.0");
+        assert!(components[1].get("properties").is_none());
+
+        let properties = components[0]["properties"].as_array().unwrap();
+        assert_eq!(properties.len(), 21);
+        assert_eq!(
+            properties[3],
+            serde_json::json!({
+                "name": "cargo-geiger:used:exprs:unsafe",
+                "value": "2"
+            })
+        );
+        assert_eq!(
+            properties[20],
+            serde_json::json!({
+                "name": "cargo-geiger:forbids-unsafe",
+                "value": "false"
+            })
+        );
+
+        assert_eq!(
+            bom["dependencies"],
+            serde_json::json!([
+                { "ref": "pkg:cargo/dep@0.1.0", "dependsOn": [] },
+                {
+                    "ref": "pkg:cargo/root@0.1.0",
+                    "dependsOn": [
+                        "pkg:cargo/dep@0.1.0",
+                        "pkg:cargo/unscanned@0.1.0"
+                    ]
+                },
+                { "ref": "pkg:cargo/unscanned@0.1.0", "dependsOn": [] }
+            ])
+        );
+    }
+}
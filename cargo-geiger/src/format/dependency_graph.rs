@@ -0,0 +1,342 @@
+use super::CrateDetectionStatus;
+
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// A node of the exported graph, either a single package or all packages
+/// which forbid unsafe code merged into one
+#[derive(Debug)]
+struct Node {
+    label: Vec<String>,
+    /// `None` for packages which were not scanned
+    status: Option<CrateDetectionStatus>,
+}
+
+/// The dependency graph of a `SafetyReport`, with the edges ordered by their
+/// endpoints and kind so the output is stable
+#[derive(Debug)]
+struct ExportGraph {
+    nodes: Vec<Node>,
+    edges: BTreeSet<(usize, usize, EdgeKind)>,
+}
+
+/// The dependency kind of an edge
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum EdgeKind {
+    Normal,
+    Build,
+    Development,
+}
+
+/// Render the dependency graph of a `SafetyReport` in the Graphviz DOT
+/// language. The nodes are coloured by their `CrateDetectionStatus` and the
+/// edges of build and dev dependencies are dashed and dotted.
+pub fn safety_report_to_dot(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+    collapse_forbidding: bool,
+) -> String {
+    let graph = ExportGraph::new(report, root_package_id, collapse_forbidding);
+    let mut dot = String::from("digraph geiger {\n");
+    dot.push_str("    node [shape=box, style=filled];\n");
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let label = node
+            .label
+            .iter()
+            .map(|line| escape_dot(line))
+            .collect::<Vec<String>>()
+            .join("\\n");
+        writeln!(
+            dot,
+            "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+            index,
+            label,
+            fill_colour(node.status.as_ref())
+        )
+        .unwrap();
+    }
+    for (from, to, kind) in &graph.edges {
+        let style = match kind {
+            EdgeKind::Normal => "",
+            EdgeKind::Build => " [style=dashed, label=\"build\"]",
+            EdgeKind::Development => " [style=dotted, label=\"dev\"]",
+        };
+        writeln!(dot, "    n{} -> n{}{};", from, to, style).unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Render the dependency graph of a `SafetyReport` as a Mermaid flowchart,
+/// with the same colours as `safety_report_to_dot`. The edges of build
+/// dependencies are thick and the ones of dev dependencies dotted.
+pub fn safety_report_to_mermaid(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+    collapse_forbidding: bool,
+) -> String {
+    let graph = ExportGraph::new(report, root_package_id, collapse_forbidding);
+    let mut mermaid = String::from("graph TD\n");
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let label = node
+            .label
+            .iter()
+            .map(|line| escape_mermaid(line))
+            .collect::<Vec<String>>()
+            .join("<br/>");
+        writeln!(
+            mermaid,
+            "    n{}[\"{}\"]:::{}",
+            index,
+            label,
+            status_class(node.status.as_ref())
+        )
+        .unwrap();
+    }
+    for (from, to, kind) in &graph.edges {
+        let arrow = match kind {
+            EdgeKind::Normal => "-->",
+            EdgeKind::Build => "==>|build|",
+            EdgeKind::Development => "-.->|dev|",
+        };
+        writeln!(mermaid, "    n{} {} n{}", from, arrow, to).unwrap();
+    }
+    for status in &[
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe),
+        Some(CrateDetectionStatus::UnsafeDetected),
+        None,
+    ] {
+        writeln!(
+            mermaid,
+            "    classDef {} fill:{}",
+            status_class(status.as_ref()),
+            fill_colour(status.as_ref())
+        )
+        .unwrap();
+    }
+    mermaid
+}
+
+impl ExportGraph {
+    fn new(
+        report: &SafetyReport,
+        root_package_id: Option<&PackageId>,
+        collapse_forbidding: bool,
+    ) -> Self {
+        let mut package_ids = report
+            .packages
+            .keys()
+            .chain(&report.packages_without_metrics)
+            .collect::<Vec<&PackageId>>();
+        package_ids.sort();
+
+        let mut nodes = Vec::new();
+        let mut collapsed = Vec::new();
+        let mut indices = HashMap::new();
+        for package_id in &package_ids {
+            let entry = report.packages.get(*package_id);
+            let status = entry.map(|e| CrateDetectionStatus::from(&e.unsafety));
+            if collapse_forbidding
+                && status
+                    == Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe)
+                && Some(*package_id) != root_package_id
+            {
+                collapsed.push(*package_id);
+                continue;
+            }
+            let mut label =
+                vec![format!("{} {}", package_id.name, package_id.version)];
+            if let Some(entry) = entry {
+                label.push(used_unsafe_counts(&entry.unsafety.used));
+            }
+            indices.insert(*package_id, nodes.len());
+            nodes.push(Node { label, status });
+        }
+        if !collapsed.is_empty() {
+            for package_id in &collapsed {
+                indices.insert(*package_id, nodes.len());
+            }
+            nodes.push(Node {
+                label: vec![format!(
+                    "{} {} forbidding unsafe",
+                    collapsed.len(),
+                    if collapsed.len() == 1 {
+                        "package"
+                    } else {
+                        "packages"
+                    }
+                )],
+                status: Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+            });
+        }
+
+        let mut edges = BTreeSet::new();
+        for (package_id, entry) in &report.packages {
+            let from = indices[package_id];
+            for &(dependencies, kind) in &[
+                (&entry.package.dependencies, EdgeKind::Normal),
+                (&entry.package.build_dependencies, EdgeKind::Build),
+                (&entry.package.dev_dependencies, EdgeKind::Development),
+            ] {
+                for dependency in dependencies {
+                    match indices.get(dependency) {
+                        // Edges between collapsed packages would loop on the
+                        // merged node
+                        Some(to) if *to != from => {
+                            edges.insert((from, *to, kind));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        ExportGraph { nodes, edges }
+    }
+}
+
+fn used_unsafe_counts(used: &CounterBlock) -> String {
+    format!(
+        "unsafe functions: {}, exprs: {}, item-impls: {}, item-traits: {}, methods: {}",
+        used.functions.unsafe_,
+        used.exprs.unsafe_,
+        used.item_impls.unsafe_,
+        used.item_traits.unsafe_,
+        used.methods.unsafe_
+    )
+}
+
+fn fill_colour(status: Option<&CrateDetectionStatus>) -> &'static str {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => "#b7e1a1",
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "#fce8a4",
+        Some(CrateDetectionStatus::UnsafeDetected) => "#f4a6a6",
+        None => "#d9d9d9",
+    }
+}
+
+fn status_class(status: Option<&CrateDetectionStatus>) -> &'static str {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => "forbids",
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "none",
+        Some(CrateDetectionStatus::UnsafeDetected) => "unsafe",
+        None => "unscanned",
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod dependency_graph_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::DependencyKind;
+    use rstest::*;
+
+    /// root -> a (forbids) -> c (unsafe), root -build-> b (forbids),
+    /// root -dev-> d (not scanned)
+    fn dependency_graph_report() -> SafetyReport {
+        let mut root = report_entry(package_id("root", "0.1.0"), 0, false);
+        root.package
+            .add_dependency(package_id("a", "0.1.0"), DependencyKind::Normal);
+        root.package
+            .add_dependency(package_id("b", "0.1.0"), DependencyKind::Build);
+        root.package.add_dependency(
+            package_id("d", "0.1.0"),
+            DependencyKind::Development,
+        );
+        let mut a = report_entry(package_id("a", "0.1.0"), 0, true);
+        a.package
+            .add_dependency(package_id("b", "0.1.0"), DependencyKind::Normal);
+        a.package
+            .add_dependency(package_id("c", "0.1.0"), DependencyKind::Normal);
+
+        let mut report = safety_report(vec![
+            root,
+            a,
+            report_entry(package_id("b", "0.1.0"), 0, true),
+            report_entry(package_id("c", "0.1.0"), 2, false),
+        ]);
+        report
+            .packages_without_metrics
+            .insert(package_id("d", "0.1.0"));
+        report
+    }
+
+    #[rstest]
+    fn safety_report_to_dot_test() {
+        let dot = safety_report_to_dot(
+            &dependency_graph_report(),
+            Some(&package_id("root", "0.1.0")),
+            false,
+        );
+
+        assert!(dot.starts_with("digraph geiger {\n"));
+        assert!(dot.contains(
+            "    n2 [label=\"c 0.1.0\\nunsafe functions: 0, exprs: 2, item-impls: 0, item-traits: 0, methods: 0\", fillcolor=\"#f4a6a6\"];\n"
+        ));
+        assert!(dot
+            .contains("    n3 [label=\"d 0.1.0\", fillcolor=\"#d9d9d9\"];\n"));
+        assert!(dot.contains("    n4 -> n0;\n"));
+        assert!(dot.contains("    n4 -> n1 [style=dashed, label=\"build\"];\n"));
+        assert!(dot.contains("    n4 -> n3 [style=dotted, label=\"dev\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[rstest]
+    fn safety_report_to_dot_collapse_forbidding_test() {
+        let dot = safety_report_to_dot(
+            &dependency_graph_report(),
+            Some(&package_id("root", "0.1.0")),
+            true,
+        );
+
+        assert!(dot.contains(
+            "    n3 [label=\"2 packages forbidding unsafe\", fillcolor=\"#b7e1a1\"];\n"
+        ));
+        assert_eq!(
+            dot.lines()
+                .filter(|line| line.contains("->"))
+                .collect::<Vec<_>>(),
+            vec![
+                "    n2 -> n1 [style=dotted, label=\"dev\"];",
+                "    n2 -> n3;",
+                "    n2 -> n3 [style=dashed, label=\"build\"];",
+                "    n3 -> n0;",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn safety_report_to_mermaid_test() {
+        let mermaid = safety_report_to_mermaid(
+            &dependency_graph_report(),
+            Some(&package_id("root", "0.1.0")),
+            false,
+        );
+
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains("    n3[\"d 0.1.0\"]:::unscanned\n"));
+        assert!(mermaid.contains("    n1[\"b 0.1.0<br/>unsafe functions: 0, exprs: 0, item-impls: 0, item-traits: 0, methods: 0\"]:::forbids\n"));
+        assert!(mermaid.contains("    n4 --> n0\n"));
+        assert!(mermaid.contains("    n4 ==>|build| n1\n"));
+        assert!(mermaid.contains("    n4 -.->|dev| n3\n"));
+        assert!(mermaid.contains("    classDef unsafe fill:#f4a6a6\n"));
+    }
+
+    #[rstest]
+    fn escape_mermaid_test() {
+        assert_eq!(escape_mermaid("a \"<b>\""), "a #quot;#lt;b#gt;#quot;");
+    }
+}
@@ -0,0 +1,155 @@
+use super::sarif::rule_name;
+use crate::scan::UsedUnsafeSite;
+
+use cargo_geiger_serde::PackageId;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Build GitHub Actions workflow commands from the unsafe sites used by the
+/// build: a `::warning` for each unsafe site of the workspace members, located
+/// relative to `workspace_root`, and a single `::notice` summarizing the
+/// dependencies using unsafe code.
+pub fn unsafe_sites_to_github_annotations(
+    unsafe_sites: &[UsedUnsafeSite],
+    workspace_root: &Path,
+) -> Vec<String> {
+    let mut annotations = vec![];
+    let mut dependencies = BTreeMap::<&PackageId, usize>::new();
+    for used_unsafe_site in unsafe_sites {
+        if !used_unsafe_site.is_workspace_member {
+            *dependencies.entry(&used_unsafe_site.package).or_default() += 1;
+            continue;
+        }
+        let site = &used_unsafe_site.site;
+        let path = used_unsafe_site
+            .path
+            .strip_prefix(workspace_root)
+            .unwrap_or(&used_unsafe_site.path);
+        annotations.push(format!(
+            "::warning file={},line={},endLine={},col={},title={}::{}",
+            escape_property(&path.display().to_string()),
+            site.line,
+            site.end_line,
+            site.column,
+            escape_property(rule_name(site.kind)),
+            escape_data(&format!(
+                "{} in {} {}",
+                rule_name(site.kind),
+                used_unsafe_site.package.name,
+                used_unsafe_site.package.version
+            ))
+        ));
+    }
+
+    if !dependencies.is_empty() {
+        let packages = dependencies
+            .iter()
+            .map(|(package_id, count)| {
+                format!(
+                    "{} {} ({} unsafe {})",
+                    package_id.name,
+                    package_id.version,
+                    count,
+                    if *count == 1 { "site" } else { "sites" }
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        annotations.push(format!(
+            "::notice title={}::{}",
+            escape_property("cargo-geiger"),
+            escape_data(&format!(
+                "{} {} unsafe code in the build: {}",
+                dependencies.len(),
+                if dependencies.len() == 1 {
+                    "dependency uses"
+                } else {
+                    "dependencies use"
+                },
+                packages
+            ))
+        ));
+    }
+    annotations
+}
+
+/// Escape the message of a workflow command
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod github_annotations_tests {
+    use super::*;
+
+    use crate::lib_tests::used_unsafe_site;
+    use geiger::UnsafeKind;
+    use rstest::*;
+
+    #[rstest]
+    fn unsafe_sites_to_github_annotations_test() {
+        let unsafe_sites = vec![
+            used_unsafe_site(
+                "member",
+                true,
+                "/workspace/src/a,b.rs",
+                UnsafeKind::Block,
+                7,
+            ),
+            used_unsafe_site(
+                "member",
+                true,
+                "/workspace/src/lib.rs",
+                UnsafeKind::Impl,
+                7,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Block,
+                7,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep/src/lib.rs",
+                UnsafeKind::Function,
+                7,
+            ),
+            used_unsafe_site(
+                "libc",
+                false,
+                "/registry/libc/src/lib.rs",
+                UnsafeKind::Function,
+                7,
+            ),
+        ];
+
+        assert_eq!(
+            unsafe_sites_to_github_annotations(
+                &unsafe_sites,
+                Path::new("/workspace")
+            ),
+            vec![
+                "::warning file=src/a%2Cb.rs,line=7,endLine=9,col=5,title=Unsafe block expression::Unsafe block expression in member 1.2.3",
+                "::warning file=src/lib.rs,line=7,endLine=9,col=5,title=Unsafe impl::Unsafe impl in member 1.2.3",
+                "::notice title=cargo-geiger::2 dependencies use unsafe code in the build: dep 1.2.3 (2 unsafe sites), libc 1.2.3 (1 unsafe site)",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn escape_property_test() {
+        assert_eq!(escape_property("a:b,c%\n"), "a%3Ab%2Cc%25%0A");
+    }
+}
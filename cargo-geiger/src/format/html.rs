@@ -0,0 +1,255 @@
+use super::{source_kind, CrateDetectionStatus};
+use crate::scan::PackageFile;
+
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// The page the report data is embedded in, with the scripts and styles
+/// which render it
+const TEMPLATE: &str = include_str!("html/report.html");
+
+#[derive(Debug, Serialize)]
+struct HtmlReport<'a> {
+    /// Index of the root package in `packages`
+    root: Option<usize>,
+    packages: Vec<HtmlPackage<'a>>,
+    used_but_not_scanned_files: Vec<&'a PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct HtmlPackage<'a> {
+    name: &'a str,
+    version: String,
+    source: &'static str,
+    /// One of `forbids`, `none`, `unsafe` and `unscanned`
+    status: &'static str,
+    used: Option<&'a CounterBlock>,
+    unused: Option<&'a CounterBlock>,
+    /// Indices of the dependencies in `packages`
+    dependencies: Vec<usize>,
+    build_dependencies: Vec<usize>,
+    dev_dependencies: Vec<usize>,
+    files: Vec<HtmlFile>,
+}
+
+#[derive(Debug, Serialize)]
+struct HtmlFile {
+    path: String,
+    used: bool,
+    forbids_unsafe: bool,
+    counters: CounterBlock,
+}
+
+/// Render a `SafetyReport` as a self-contained HTML page, with a dependency
+/// tree, a sortable table of the packages, a treemap of the used unsafe
+/// expressions and the files of each package found in `package_files`.
+pub fn safety_report_to_html(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+    package_files: &HashMap<PackageId, Vec<PackageFile>>,
+) -> String {
+    let mut package_ids = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .collect::<Vec<&PackageId>>();
+    package_ids.sort();
+    let indices = package_ids
+        .iter()
+        .enumerate()
+        .map(|(index, package_id)| (*package_id, index))
+        .collect::<HashMap<&PackageId, usize>>();
+
+    let packages = package_ids
+        .iter()
+        .map(|package_id| {
+            let entry = report.packages.get(*package_id);
+            let status = match entry
+                .map(|e| CrateDetectionStatus::from(&e.unsafety))
+            {
+                Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => {
+                    "forbids"
+                }
+                Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "none",
+                Some(CrateDetectionStatus::UnsafeDetected) => "unsafe",
+                None => "unscanned",
+            };
+            HtmlPackage {
+                name: &package_id.name,
+                version: package_id.version.to_string(),
+                source: source_kind(&package_id.source),
+                status,
+                used: entry.map(|e| &e.unsafety.used),
+                unused: entry.map(|e| &e.unsafety.unused),
+                dependencies: entry
+                    .map(|e| {
+                        dependency_indices(&e.package.dependencies, &indices)
+                    })
+                    .unwrap_or_default(),
+                build_dependencies: entry
+                    .map(|e| {
+                        dependency_indices(
+                            &e.package.build_dependencies,
+                            &indices,
+                        )
+                    })
+                    .unwrap_or_default(),
+                dev_dependencies: entry
+                    .map(|e| {
+                        dependency_indices(
+                            &e.package.dev_dependencies,
+                            &indices,
+                        )
+                    })
+                    .unwrap_or_default(),
+                files: package_files
+                    .get(*package_id)
+                    .map(|files| {
+                        files
+                            .iter()
+                            .map(|file| HtmlFile {
+                                path: file.path.display().to_string(),
+                                used: file.is_used,
+                                forbids_unsafe: file.metrics.forbids_unsafe,
+                                counters: file.metrics.counters.clone(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let mut used_but_not_scanned_files =
+        report.used_but_not_scanned_files.iter().collect::<Vec<_>>();
+    used_but_not_scanned_files.sort();
+
+    let html_report = HtmlReport {
+        root: root_package_id
+            .and_then(|root_package_id| indices.get(root_package_id))
+            .copied(),
+        packages,
+        used_but_not_scanned_files,
+    };
+
+    let title = match root_package_id {
+        Some(root_package_id) => format!(
+            "cargo-geiger report: {} {}",
+            root_package_id.name, root_package_id.version
+        ),
+        None => String::from("cargo-geiger report"),
+    };
+    // The data is embedded in a script element, which must not be closed by
+    // a string inside of it. JSON allows `<` to be escaped in strings, and it
+    // appears nowhere else.
+    let data = serde_json::to_string(&html_report)
+        .unwrap()
+        .replace('<', "\\u003c");
+
+    TEMPLATE
+        .replace("{{title}}", &escape_html(&title))
+        .replace("{{data}}", &data)
+}
+
+fn dependency_indices(
+    dependencies: &HashSet<PackageId>,
+    indices: &HashMap<&PackageId, usize>,
+) -> Vec<usize> {
+    let mut dependency_indices = dependencies
+        .iter()
+        .filter_map(|dependency| indices.get(dependency).copied())
+        .collect::<Vec<usize>>();
+    dependency_indices.sort_unstable();
+    dependency_indices
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::DependencyKind;
+    use geiger::RsFileMetrics;
+    use rstest::*;
+
+    fn embedded_data(html: &str) -> serde_json::Value {
+        let start = html.find("id=\"geiger-data\">").unwrap()
+            + "id=\"geiger-data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        serde_json::from_str(&html[start..end]).unwrap()
+    }
+
+    #[rstest]
+    fn safety_report_to_html_test() {
+        let mut root = report_entry(package_id("root", "0.1.0"), 0, true);
+        root.package
+            .add_dependency(package_id("dep", "0.1.0"), DependencyKind::Normal);
+        root.package.add_dependency(
+            package_id("unscanned", "0.1.0"),
+            DependencyKind::Development,
+        );
+        let mut report = safety_report(vec![
+            root,
+            report_entry(package_id("dep", "0.1.0"), 3, false),
+        ]);
+        report
+            .packages_without_metrics
+            .insert(package_id("unscanned", "0.1.0"));
+        report
+            .used_but_not_scanned_files
+            .insert(PathBuf::from("</script><b>.rs"));
+        let package_files = vec![(
+            package_id("dep", "0.1.0"),
+            vec![PackageFile {
+                path: PathBuf::from("src/lib.rs"),
+                is_used: true,
+                metrics: RsFileMetrics::default(),
+            }],
+        )]
+        .into_iter()
+        .collect();
+
+        let html = safety_report_to_html(
+            &report,
+            Some(&package_id("root", "0.1.0")),
+            &package_files,
+        );
+
+        assert!(html.contains("<title>cargo-geiger report: root 0.1.0</title>"));
+        assert!(!html.contains("</script><b>"));
+        let data = embedded_data(&html);
+        assert_eq!(data["root"], 1);
+        let packages = data["packages"].as_array().unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| (
+                    p["name"].as_str().unwrap(),
+                    p["status"].as_str().unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("dep", "unsafe"),
+                ("root", "forbids"),
+                ("unscanned", "unscanned")
+            ]
+        );
+        assert_eq!(packages[1]["dependencies"], serde_json::json!([0]));
+        assert_eq!(packages[1]["dev_dependencies"], serde_json::json!([2]));
+        assert_eq!(packages[0]["files"][0]["path"], "src/lib.rs");
+        assert!(packages[2]["used"].is_null());
+        assert_eq!(
+            data["used_but_not_scanned_files"],
+            serde_json::json!(["</script><b>.rs"])
+        );
+    }
+}
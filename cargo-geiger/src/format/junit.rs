@@ -0,0 +1,220 @@
+use crate::policy::PolicyViolation;
+
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport};
+use std::fmt::Write;
+
+/// A test case, which fails when it has any failure messages
+struct TestCase {
+    name: String,
+    failures: Vec<String>,
+    skipped: bool,
+}
+
+/// Render a `SafetyReport` as a `JUnit` XML report, with a test suite of one
+/// test case per package and one of the files used by the build but not
+/// scanned.
+///
+/// A package fails when unsafe code is used by the build or it breaks a rule
+/// of the policy, and is skipped when it was not scanned.
+pub fn safety_report_to_junit(
+    report: &SafetyReport,
+    violations: &[PolicyViolation],
+) -> String {
+    let mut package_ids = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .collect::<Vec<&PackageId>>();
+    package_ids.sort();
+
+    let packages = package_ids
+        .into_iter()
+        .map(|package_id| {
+            let entry = report.packages.get(package_id);
+            let mut failures = vec![];
+            if let Some(entry) = entry {
+                if entry.unsafety.used.has_unsafe() {
+                    failures.push(format!(
+                        "unsafe code is used by the build: {}",
+                        used_unsafe_counts(&entry.unsafety.used)
+                    ));
+                }
+            }
+            failures.extend(
+                violations
+                    .iter()
+                    .filter(|violation| violation.package == *package_id)
+                    .map(|violation| {
+                        format!(
+                            "{} (rule: `{}`)",
+                            violation.message, violation.rule
+                        )
+                    }),
+            );
+            TestCase {
+                name: format!("{} {}", package_id.name, package_id.version),
+                failures,
+                skipped: entry.is_none(),
+            }
+        })
+        .collect::<Vec<TestCase>>();
+
+    let mut used_but_not_scanned_files =
+        report.used_but_not_scanned_files.iter().collect::<Vec<_>>();
+    used_but_not_scanned_files.sort();
+    let files = used_but_not_scanned_files
+        .into_iter()
+        .map(|path| TestCase {
+            name: path.display().to_string(),
+            failures: vec![String::from(
+                "file is used by the build but was not scanned",
+            )],
+            skipped: false,
+        })
+        .collect::<Vec<TestCase>>();
+
+    let suites = [
+        ("cargo-geiger.packages", &packages),
+        ("cargo-geiger.used-but-not-scanned-files", &files),
+    ];
+    let tests = packages.len() + files.len();
+    let failures = packages
+        .iter()
+        .chain(&files)
+        .filter(|test_case| !test_case.failures.is_empty())
+        .count();
+
+    let mut junit =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        junit,
+        "<testsuites name=\"cargo-geiger\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    )
+    .unwrap();
+    for (suite_name, test_cases) in suites.iter() {
+        write_test_suite(&mut junit, suite_name, test_cases);
+    }
+    junit.push_str("</testsuites>");
+    junit
+}
+
+fn write_test_suite(junit: &mut String, name: &str, test_cases: &[TestCase]) {
+    writeln!(
+        junit,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        name,
+        test_cases.len(),
+        test_cases
+            .iter()
+            .filter(|test_case| !test_case.failures.is_empty())
+            .count(),
+        test_cases
+            .iter()
+            .filter(|test_case| test_case.skipped)
+            .count()
+    )
+    .unwrap();
+    for test_case in test_cases {
+        write!(
+            junit,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            name,
+            escape_xml(&test_case.name)
+        )
+        .unwrap();
+        if test_case.skipped {
+            junit.push_str(">\n      <skipped message=\"not scanned\"/>\n    </testcase>\n");
+        } else if test_case.failures.is_empty() {
+            junit.push_str("/>\n");
+        } else {
+            writeln!(
+                junit,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape_xml(&test_case.failures[0]),
+                escape_xml(&test_case.failures.join("\n"))
+            )
+            .unwrap();
+        }
+    }
+    junit.push_str("  </testsuite>\n");
+}
+
+fn used_unsafe_counts(used: &CounterBlock) -> String {
+    format!(
+        "functions: {}, exprs: {}, item-impls: {}, item-traits: {}, methods: {}",
+        used.functions.unsafe_,
+        used.exprs.unsafe_,
+        used.item_impls.unsafe_,
+        used.item_traits.unsafe_,
+        used.methods.unsafe_
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod junit_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn safety_report_to_junit_test() {
+        let mut report = safety_report(vec![
+            report_entry(package_id("forbids", "0.1.0"), 0, true),
+            report_entry(package_id("member", "0.1.0"), 0, false),
+            report_entry(package_id("unsafe", "0.1.0"), 3, false),
+        ]);
+        report
+            .packages_without_metrics
+            .insert(package_id("unscanned", "0.1.0"));
+        report
+            .used_but_not_scanned_files
+            .insert(PathBuf::from("src/<generated>.rs"));
+        let violations = vec![PolicyViolation {
+            package: package_id("member", "0.1.0"),
+            rule: String::from("members-forbid-unsafe"),
+            message: String::from(
+                "workspace member does not declare #![forbid(unsafe_code)]",
+            ),
+        }];
+
+        let junit = safety_report_to_junit(&report, &violations);
+
+        assert_eq!(
+            junit,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"cargo-geiger\" tests=\"5\" failures=\"3\">\n\
+             \x20 <testsuite name=\"cargo-geiger.packages\" tests=\"4\" failures=\"2\" skipped=\"1\">\n\
+             \x20   <testcase classname=\"cargo-geiger.packages\" name=\"forbids 0.1.0\"/>\n\
+             \x20   <testcase classname=\"cargo-geiger.packages\" name=\"member 0.1.0\">\n\
+             \x20     <failure message=\"workspace member does not declare #![forbid(unsafe_code)] (rule: `members-forbid-unsafe`)\">\
+             workspace member does not declare #![forbid(unsafe_code)] (rule: `members-forbid-unsafe`)</failure>\n\
+             \x20   </testcase>\n\
+             \x20   <testcase classname=\"cargo-geiger.packages\" name=\"unsafe 0.1.0\">\n\
+             \x20     <failure message=\"unsafe code is used by the build: functions: 0, exprs: 3, item-impls: 0, item-traits: 0, methods: 0\">\
+             unsafe code is used by the build: functions: 0, exprs: 3, item-impls: 0, item-traits: 0, methods: 0</failure>\n\
+             \x20   </testcase>\n\
+             \x20   <testcase classname=\"cargo-geiger.packages\" name=\"unscanned 0.1.0\">\n\
+             \x20     <skipped message=\"not scanned\"/>\n\
+             \x20   </testcase>\n\
+             \x20 </testsuite>\n\
+             \x20 <testsuite name=\"cargo-geiger.used-but-not-scanned-files\" tests=\"1\" failures=\"1\" skipped=\"0\">\n\
+             \x20   <testcase classname=\"cargo-geiger.used-but-not-scanned-files\" name=\"src/&lt;generated&gt;.rs\">\n\
+             \x20     <failure message=\"file is used by the build but was not scanned\">\
+             file is used by the build but was not scanned</failure>\n\
+             \x20   </testcase>\n\
+             \x20 </testsuite>\n\
+             </testsuites>"
+        );
+    }
+}
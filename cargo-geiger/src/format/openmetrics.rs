@@ -0,0 +1,182 @@
+use super::{source_kind, CrateDetectionStatus};
+
+use cargo_geiger_serde::{Count, CounterBlock, SafetyReport};
+use std::fmt::Write;
+
+/// The statuses `geiger_packages_total` is reported for, all of them always
+/// being written so the series do not come and go
+const STATUSES: [&str; 4] = ["forbids", "none", "unsafe", "unscanned"];
+
+/// Render a `SafetyReport` in the `OpenMetrics` text format, with the gauges
+///
+/// - `geiger_unsafe_total{package,version,source,kind,used}`, the unsafe
+///   items of each kind used and not used by the build
+/// - `geiger_forbids_unsafe{package,version,source}`, 1 if the package declares
+///   `#![forbid(unsafe_code)]`
+/// - `geiger_packages_total{status}`, the number of packages by their
+///   `CrateDetectionStatus`, or `unscanned`
+///
+/// The packages which were not scanned are only counted in
+/// `geiger_packages_total`.
+pub fn safety_report_to_openmetrics(report: &SafetyReport) -> String {
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+
+    let mut openmetrics = String::new();
+    write_metric_family(
+        &mut openmetrics,
+        "geiger_unsafe_total",
+        "Unsafe items found in the scanned .rs files of a package.",
+    );
+    for entry in &entries {
+        for &(used, counter_block) in &[
+            ("true", &entry.unsafety.used),
+            ("false", &entry.unsafety.unused),
+        ] {
+            for (kind, count) in counts(counter_block) {
+                write_sample(
+                    &mut openmetrics,
+                    "geiger_unsafe_total",
+                    &[
+                        ("package", &entry.package.id.name),
+                        ("version", &entry.package.id.version.to_string()),
+                        ("source", source_kind(&entry.package.id.source)),
+                        ("kind", kind),
+                        ("used", used),
+                    ],
+                    count.unsafe_,
+                );
+            }
+        }
+    }
+
+    write_metric_family(
+        &mut openmetrics,
+        "geiger_forbids_unsafe",
+        "Whether a package declares #![forbid(unsafe_code)].",
+    );
+    for entry in &entries {
+        write_sample(
+            &mut openmetrics,
+            "geiger_forbids_unsafe",
+            &[
+                ("package", &entry.package.id.name),
+                ("version", &entry.package.id.version.to_string()),
+                ("source", source_kind(&entry.package.id.source)),
+            ],
+            entry.unsafety.forbids_unsafe as u64,
+        );
+    }
+
+    write_metric_family(
+        &mut openmetrics,
+        "geiger_packages_total",
+        "Packages in the dependency graph by unsafe usage.",
+    );
+    let mut status_counts = [0_u64; 4];
+    for entry in &entries {
+        let index = match CrateDetectionStatus::from(&entry.unsafety) {
+            CrateDetectionStatus::NoneDetectedForbidsUnsafe => 0,
+            CrateDetectionStatus::NoneDetectedAllowsUnsafe => 1,
+            CrateDetectionStatus::UnsafeDetected => 2,
+        };
+        status_counts[index] += 1;
+    }
+    status_counts[3] = report.packages_without_metrics.len() as u64;
+    for (status, count) in STATUSES.iter().zip(status_counts.iter()) {
+        write_sample(
+            &mut openmetrics,
+            "geiger_packages_total",
+            &[("status", status)],
+            *count,
+        );
+    }
+
+    openmetrics.push_str("# EOF");
+    openmetrics
+}
+
+fn write_metric_family(openmetrics: &mut String, name: &str, help: &str) {
+    writeln!(openmetrics, "# TYPE {} gauge", name).unwrap();
+    writeln!(openmetrics, "# HELP {} {}", name, help).unwrap();
+}
+
+fn write_sample(
+    openmetrics: &mut String,
+    name: &str,
+    labels: &[(&str, &str)],
+    value: u64,
+) {
+    let labels = labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+        .collect::<Vec<String>>()
+        .join(",");
+    writeln!(openmetrics, "{}{{{}}} {}", name, labels, value).unwrap();
+}
+
+fn counts(counter_block: &CounterBlock) -> [(&'static str, &Count); 5] {
+    [
+        ("functions", &counter_block.functions),
+        ("exprs", &counter_block.exprs),
+        ("item-impls", &counter_block.item_impls),
+        ("item-traits", &counter_block.item_traits),
+        ("methods", &counter_block.methods),
+    ]
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod openmetrics_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+
+    #[rstest]
+    fn safety_report_to_openmetrics_test() {
+        let mut dep = report_entry(package_id("dep", "0.1.0"), 3, true);
+        dep.unsafety.used.exprs.safe = 1;
+        dep.unsafety.unused.methods.unsafe_ = 2;
+        let mut report = safety_report(vec![dep]);
+        report
+            .packages_without_metrics
+            .insert(package_id("unscanned", "0.1.0"));
+
+        let openmetrics = safety_report_to_openmetrics(&report);
+        let lines = openmetrics.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2 + 10 + 2 + 1 + 2 + 4 + 1);
+        assert_eq!(lines[0], "# TYPE geiger_unsafe_total gauge");
+        assert!(lines.contains(
+            &"geiger_unsafe_total{package=\"dep\",version=\"0.1.0\",source=\"registry\",kind=\"exprs\",used=\"true\"} 3"
+        ));
+        assert!(lines.contains(
+            &"geiger_unsafe_total{package=\"dep\",version=\"0.1.0\",source=\"registry\",kind=\"methods\",used=\"false\"} 2"
+        ));
+        assert!(lines.contains(
+            &"geiger_forbids_unsafe{package=\"dep\",version=\"0.1.0\",source=\"registry\"} 1"
+        ));
+        assert_eq!(
+            lines[lines.len() - 5..],
+            [
+                "geiger_packages_total{status=\"forbids\"} 0",
+                "geiger_packages_total{status=\"none\"} 0",
+                "geiger_packages_total{status=\"unsafe\"} 1",
+                "geiger_packages_total{status=\"unscanned\"} 1",
+                "# EOF",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn escape_label_test() {
+        assert_eq!(escape_label("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+}
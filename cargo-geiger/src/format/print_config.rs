@@ -18,9 +18,39 @@ pub enum Prefix {
 #[derive(Clone, Copy, Debug, EnumString, Eq, PartialEq)]
 pub enum OutputFormat {
     Ascii,
+    /// Comma separated values, with one row per package or scanned file
+    Csv,
+    /// `CycloneDX` 1.5 JSON BOM, with the unsafe usage of each package in its
+    /// properties
+    CycloneDx,
+    /// Graphviz DOT graph of the dependencies, coloured by unsafe usage
+    Dot,
     Json,
+    /// GitHub Actions workflow commands, annotating each unsafe site of the
+    /// workspace members
+    GitHubAnnotations,
     GitHubMarkdown,
+    /// GitLab Code Quality report, with one issue per unsafe item or block of
+    /// the workspace members and one per dependency using unsafe code
+    GitLabCodeQuality,
+    /// Self-contained HTML page with a dependency tree, a package table, a
+    /// treemap and the files of each package
+    Html,
+    /// `JUnit` XML report, with one test case per package
+    JUnit,
+    /// Mermaid flowchart of the dependencies, coloured by unsafe usage
+    Mermaid,
+    /// `OpenMetrics` text format, with gauges of the unsafe usage of each
+    /// package
+    OpenMetrics,
     Ratio,
+    /// SARIF 2.1.0, with one result per unsafe site used by the build
+    Sarif,
+    /// SPDX 2.3 JSON document, with the unsafe usage of each package in an
+    /// annotation
+    Spdx,
+    /// Tab separated values, with one row per package or scanned file
+    Tsv,
     Utf8,
 }
 
@@ -30,6 +60,19 @@ impl Default for OutputFormat {
     }
 }
 
+impl OutputFormat {
+    /// Whether the output lists the unsafe sites of the scanned files, which
+    /// are only collected when needed
+    pub fn uses_unsafe_sites(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::GitHubAnnotations
+                | OutputFormat::GitLabCodeQuality
+                | OutputFormat::Sarif
+        )
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct PrintConfig {
     /// Don't truncate dependencies that have already been displayed.
@@ -243,8 +286,20 @@ mod print_config_tests {
         input_raw_str,
         expected_output_format_result,
         case("Ascii", Ok(OutputFormat::Ascii)),
+        case("Csv", Ok(OutputFormat::Csv)),
+        case("CycloneDx", Ok(OutputFormat::CycloneDx)),
+        case("Dot", Ok(OutputFormat::Dot)),
         case("Json", Ok(OutputFormat::Json)),
+        case("GitHubAnnotations", Ok(OutputFormat::GitHubAnnotations)),
         case("GitHubMarkdown", Ok(OutputFormat::GitHubMarkdown)),
+        case("GitLabCodeQuality", Ok(OutputFormat::GitLabCodeQuality)),
+        case("Html", Ok(OutputFormat::Html)),
+        case("JUnit", Ok(OutputFormat::JUnit)),
+        case("Mermaid", Ok(OutputFormat::Mermaid)),
+        case("OpenMetrics", Ok(OutputFormat::OpenMetrics)),
+        case("Sarif", Ok(OutputFormat::Sarif)),
+        case("Spdx", Ok(OutputFormat::Spdx)),
+        case("Tsv", Ok(OutputFormat::Tsv)),
         case("Utf8", Ok(OutputFormat::Utf8)),
         case("unknown_variant", Err(strum::ParseError::VariantNotFound))
     )]
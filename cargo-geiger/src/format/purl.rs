@@ -0,0 +1,97 @@
+use cargo_geiger_serde::{PackageId, Source};
+use url::form_urlencoded::byte_serialize;
+use url::Url;
+
+/// The index URLs of crates.io, whose packages need no `repository_url`
+const CRATES_IO_INDEX_URLS: [&str; 2] = [
+    "https://github.com/rust-lang/crates.io-index",
+    "https://index.crates.io/",
+];
+
+/// The package URL of a package, as specified by
+/// <https://github.com/package-url/purl-spec>, qualified with where the
+/// package comes from unless it is published to crates.io
+pub fn package_url(package_id: &PackageId) -> String {
+    let purl = format!(
+        "pkg:cargo/{}@{}",
+        encode(&package_id.name),
+        encode(&package_id.version.to_string())
+    );
+    let qualifier = match &package_id.source {
+        Source::Registry { url, .. } if is_crates_io(url) => None,
+        Source::Registry { url, .. }
+        | Source::Directory(url)
+        | Source::LocalRegistry(url) => {
+            Some(("repository_url", url.to_string()))
+        }
+        Source::Git { url, rev } if rev.is_empty() => {
+            Some(("vcs_url", format!("git+{}", url)))
+        }
+        Source::Git { url, rev } => {
+            Some(("vcs_url", format!("git+{}@{}", url, rev)))
+        }
+        Source::Path(url) => Some(("download_url", url.to_string())),
+    };
+    match qualifier {
+        Some((key, value)) => format!("{}?{}={}", purl, key, encode(&value)),
+        None => purl,
+    }
+}
+
+/// Whether a registry index URL is the one of crates.io
+pub fn is_crates_io(url: &Url) -> bool {
+    CRATES_IO_INDEX_URLS.contains(&url.as_str())
+}
+
+fn encode(value: &str) -> String {
+    byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+#[cfg(test)]
+mod purl_tests {
+    use super::*;
+
+    use rstest::*;
+    use semver::Version;
+
+    #[rstest(
+        input_source,
+        expected_package_url,
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://index.crates.io/").unwrap(),
+            },
+            "pkg:cargo/my-crate@1.0.0-rc.1"
+        ),
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://example.com/index").unwrap(),
+            },
+            "pkg:cargo/my-crate@1.0.0-rc.1?repository_url=https%3A%2F%2Fexample.com%2Findex"
+        ),
+        case(
+            Source::Git {
+                url: Url::parse("https://github.com/owner/repo").unwrap(),
+                rev: String::from("abc123"),
+            },
+            "pkg:cargo/my-crate@1.0.0-rc.1?vcs_url=git%2Bhttps%3A%2F%2Fgithub.com%2Fowner%2Frepo%40abc123"
+        ),
+        case(
+            Source::Path(Url::parse("file:///my%20crate").unwrap()),
+            "pkg:cargo/my-crate@1.0.0-rc.1?download_url=file%3A%2F%2F%2Fmy%2520crate"
+        )
+    )]
+    fn package_url_test(input_source: Source, expected_package_url: &str) {
+        let package_id = PackageId {
+            name: String::from("my-crate"),
+            version: Version::parse("1.0.0-rc.1").unwrap(),
+            source: input_source,
+        };
+
+        assert_eq!(package_url(&package_id), expected_package_url);
+    }
+}
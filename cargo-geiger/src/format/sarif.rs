@@ -0,0 +1,364 @@
+use crate::scan::UsedUnsafeSite;
+
+use geiger::UnsafeKind;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use url::Url;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The columns of `UnsafeSite` are counted in characters, while SARIF
+/// defaults to UTF-16 code units
+const COLUMN_KIND: &str = "unicodeCodePoints";
+
+/// The base id of the URIs relative to the workspace root
+const SRCROOT: &str = "%SRCROOT%";
+
+/// The rules reported, in the order of their `ruleIndex`
+const UNSAFE_KINDS: [UnsafeKind; 5] = [
+    UnsafeKind::Function,
+    UnsafeKind::Block,
+    UnsafeKind::Impl,
+    UnsafeKind::Trait,
+    UnsafeKind::Method,
+];
+
+/// A SARIF 2.1.0 log with a single run, holding one result per unsafe site
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    column_kind: &'static str,
+    original_uri_base_ids: BTreeMap<&'static str, ArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: ToolComponent,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<ReportingDescriptor>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    id: &'static str,
+    short_description: Message,
+    full_description: Message,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    properties: ResultProperties,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+}
+
+/// Tells the unsafe code of the workspace apart from the one of its
+/// dependencies
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultProperties {
+    /// Either `workspace` or `dependency`
+    origin: &'static str,
+    package: String,
+    package_version: String,
+}
+
+/// Build a SARIF log from the unsafe sites used by the build. Files below
+/// `workspace_root` are located relative to it, the others by their absolute
+/// path.
+pub fn unsafe_sites_to_sarif(
+    unsafe_sites: &[UsedUnsafeSite],
+    workspace_root: &Path,
+) -> SarifLog {
+    let workspace_root_url = Url::from_directory_path(workspace_root).ok();
+
+    let results = unsafe_sites
+        .iter()
+        .map(|used_unsafe_site| {
+            let kind = used_unsafe_site.site.kind;
+            let (origin, level) = match used_unsafe_site.is_workspace_member {
+                true => ("workspace", "warning"),
+                false => ("dependency", "note"),
+            };
+            SarifResult {
+                rule_id: rule_id(kind),
+                rule_index: UNSAFE_KINDS
+                    .iter()
+                    .position(|k| *k == kind)
+                    .unwrap(),
+                level,
+                message: Message {
+                    text: format!(
+                        "{} in {} {}",
+                        rule_name(kind),
+                        used_unsafe_site.package.name,
+                        used_unsafe_site.package.version
+                    ),
+                },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: artifact_location(
+                            &used_unsafe_site.path,
+                            workspace_root,
+                            workspace_root_url.as_ref(),
+                        ),
+                        region: Region {
+                            start_line: used_unsafe_site.site.line,
+                            start_column: used_unsafe_site.site.column,
+                        },
+                    },
+                }],
+                properties: ResultProperties {
+                    origin,
+                    package: used_unsafe_site.package.name.clone(),
+                    package_version: used_unsafe_site
+                        .package
+                        .version
+                        .to_string(),
+                },
+            }
+        })
+        .collect();
+
+    let original_uri_base_ids = workspace_root_url
+        .iter()
+        .map(|url| {
+            (
+                SRCROOT,
+                ArtifactLocation {
+                    uri: url.to_string(),
+                    uri_base_id: None,
+                },
+            )
+        })
+        .collect();
+
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: ToolComponent {
+                    name: "cargo-geiger",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri:
+                        "https://github.com/geiger-rs/cargo-geiger",
+                    rules: UNSAFE_KINDS
+                        .iter()
+                        .map(|kind| ReportingDescriptor {
+                            id: rule_id(*kind),
+                            short_description: Message {
+                                text: rule_name(*kind).to_string(),
+                            },
+                            full_description: Message {
+                                text: rule_description(*kind).to_string(),
+                            },
+                        })
+                        .collect(),
+                },
+            },
+            column_kind: COLUMN_KIND,
+            original_uri_base_ids,
+            results,
+        }],
+    }
+}
+
+fn artifact_location(
+    path: &Path,
+    workspace_root: &Path,
+    workspace_root_url: Option<&Url>,
+) -> ArtifactLocation {
+    let file_url = Url::from_file_path(path).ok();
+    let relative_uri = match (workspace_root_url, &file_url) {
+        (Some(root_url), Some(file_url))
+            if path.starts_with(workspace_root) =>
+        {
+            root_url.make_relative(file_url)
+        }
+        _ => None,
+    };
+    match (relative_uri, file_url) {
+        (Some(uri), _) => ArtifactLocation {
+            uri,
+            uri_base_id: Some(SRCROOT),
+        },
+        (None, Some(file_url)) => ArtifactLocation {
+            uri: file_url.to_string(),
+            uri_base_id: None,
+        },
+        (None, None) => ArtifactLocation {
+            uri: path.display().to_string(),
+            uri_base_id: None,
+        },
+    }
+}
+
+/// The id of the rule reporting an unsafe kind
+pub fn rule_id(kind: UnsafeKind) -> &'static str {
+    match kind {
+        UnsafeKind::Function => "unsafe-fn",
+        UnsafeKind::Block => "unsafe-block-expr",
+        UnsafeKind::Impl => "unsafe-impl",
+        UnsafeKind::Trait => "unsafe-trait",
+        UnsafeKind::Method => "unsafe-method",
+    }
+}
+
+/// The name of the rule reporting an unsafe kind
+pub fn rule_name(kind: UnsafeKind) -> &'static str {
+    match kind {
+        UnsafeKind::Function => "Unsafe function",
+        UnsafeKind::Block => "Unsafe block expression",
+        UnsafeKind::Impl => "Unsafe impl",
+        UnsafeKind::Trait => "Unsafe trait",
+        UnsafeKind::Method => "Unsafe method",
+    }
+}
+
+fn rule_description(kind: UnsafeKind) -> &'static str {
+    match kind {
+        UnsafeKind::Function => "An `unsafe fn`, or a function exported with `#[no_mangle]` or `#[export_name]`",
+        UnsafeKind::Block => "An `unsafe { .. }` block expression",
+        UnsafeKind::Impl => "An `unsafe impl` of an unsafe trait",
+        UnsafeKind::Trait => "An `unsafe trait` declaration",
+        UnsafeKind::Method => "An `unsafe fn` in an impl block",
+    }
+}
+
+#[cfg(test)]
+mod sarif_tests {
+    use super::*;
+
+    use crate::lib_tests::used_unsafe_site;
+    use rstest::*;
+
+    #[rstest]
+    fn unsafe_sites_to_sarif_test() {
+        let unsafe_sites = vec![
+            used_unsafe_site(
+                "member",
+                true,
+                "/workspace/src/my file.rs",
+                UnsafeKind::Block,
+                7,
+            ),
+            used_unsafe_site(
+                "dep",
+                false,
+                "/registry/dep-1.2.3/src/lib.rs",
+                UnsafeKind::Impl,
+                7,
+            ),
+        ];
+
+        let sarif = serde_json::to_value(unsafe_sites_to_sarif(
+            &unsafe_sites,
+            Path::new("/workspace"),
+        ))
+        .unwrap();
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        assert_eq!(
+            run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+            "file:///workspace/"
+        );
+        assert_eq!(run["tool"]["driver"]["rules"][2]["id"], "unsafe-impl");
+
+        let results = &run["results"];
+        assert_eq!(
+            results[0],
+            serde_json::json!({
+                "ruleId": "unsafe-block-expr",
+                "ruleIndex": 1,
+                "level": "warning",
+                "message": {
+                    "text": "Unsafe block expression in member 1.2.3"
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": "src/my%20file.rs",
+                            "uriBaseId": "%SRCROOT%"
+                        },
+                        "region": { "startLine": 7, "startColumn": 5 }
+                    }
+                }],
+                "properties": {
+                    "origin": "workspace",
+                    "package": "member",
+                    "packageVersion": "1.2.3"
+                }
+            })
+        );
+        assert_eq!(results[1]["ruleId"], "unsafe-impl");
+        assert_eq!(results[1]["ruleIndex"], 2);
+        assert_eq!(results[1]["level"], "note");
+        assert_eq!(results[1]["properties"]["origin"], "dependency");
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["artifactLocation"],
+            serde_json::json!({
+                "uri": "file:///registry/dep-1.2.3/src/lib.rs"
+            })
+        );
+    }
+}
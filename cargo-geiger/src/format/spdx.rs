@@ -0,0 +1,438 @@
+use super::purl::{is_crates_io, package_url};
+
+use cargo_geiger_serde::{PackageId, SafetyReport, Source};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SPDX_VERSION: &str = "SPDX-2.3";
+const DATA_LICENSE: &str = "CC0-1.0";
+const DOCUMENT_SPDX_ID: &str = "SPDXRef-DOCUMENT";
+const NOASSERTION: &str = "NOASSERTION";
+
+/// An SPDX 2.3 document of the packages in the dependency graph, with the
+/// unsafe usage of each package in an annotation
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpdxDocument {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: CreationInfo,
+    packages: Vec<Package>,
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package {
+    name: String,
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    version_info: String,
+    download_location: String,
+    files_analyzed: bool,
+    license_concluded: &'static str,
+    license_declared: &'static str,
+    copyright_text: &'static str,
+    external_refs: Vec<ExternalRef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Annotation {
+    annotation_date: String,
+    annotation_type: &'static str,
+    annotator: String,
+    comment: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+/// Build an SPDX document from a `SafetyReport`, created at `created`. The
+/// root package is the one the document describes. The unsafe usage of each
+/// scanned package is attached as an annotation whose comment is the JSON of
+/// its `UnsafeInfo`. Dependencies which are not in the report, as in a
+/// partial one, are left out of the relationships.
+pub fn safety_report_to_spdx(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+    created: SystemTime,
+) -> SpdxDocument {
+    let created_timestamp = utc_timestamp(created);
+    let tool = format!("Tool: cargo-geiger-{}", env!("CARGO_PKG_VERSION"));
+
+    let mut package_ids = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .collect::<Vec<&PackageId>>();
+    package_ids.sort();
+
+    let spdx_ids = package_spdx_ids(&package_ids);
+
+    let packages = package_ids
+        .iter()
+        .map(|package_id| Package {
+            name: package_id.name.clone(),
+            spdx_id: spdx_ids[*package_id].clone(),
+            version_info: package_id.version.to_string(),
+            download_location: download_location(package_id),
+            files_analyzed: false,
+            license_concluded: NOASSERTION,
+            license_declared: NOASSERTION,
+            copyright_text: NOASSERTION,
+            external_refs: vec![ExternalRef {
+                reference_category: "PACKAGE-MANAGER",
+                reference_type: "purl",
+                reference_locator: package_url(package_id),
+            }],
+            annotations: report
+                .packages
+                .get(*package_id)
+                .map(|entry| Annotation {
+                    annotation_date: created_timestamp.clone(),
+                    annotation_type: "OTHER",
+                    annotator: tool.clone(),
+                    comment: serde_json::to_string(&entry.unsafety).unwrap(),
+                })
+                .into_iter()
+                .collect(),
+        })
+        .collect();
+
+    let mut relationships = root_package_id
+        .and_then(|root_package_id| spdx_ids.get(root_package_id))
+        .map(|root_spdx_id| Relationship {
+            spdx_element_id: String::from(DOCUMENT_SPDX_ID),
+            relationship_type: "DESCRIBES",
+            related_spdx_element: root_spdx_id.clone(),
+        })
+        .into_iter()
+        .collect::<Vec<Relationship>>();
+    for package_id in &package_ids {
+        let entry = match report.packages.get(*package_id) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let package_spdx_id = &spdx_ids[*package_id];
+        let mut dependency_relationships = vec![];
+        for dependency_spdx_id in entry
+            .package
+            .dependencies
+            .iter()
+            .filter_map(|dependency| spdx_ids.get(dependency))
+        {
+            dependency_relationships.push(Relationship {
+                spdx_element_id: package_spdx_id.clone(),
+                relationship_type: "DEPENDS_ON",
+                related_spdx_element: dependency_spdx_id.clone(),
+            });
+        }
+        for (relationship_type, dependencies) in [
+            ("BUILD_DEPENDENCY_OF", &entry.package.build_dependencies),
+            ("DEV_DEPENDENCY_OF", &entry.package.dev_dependencies),
+        ] {
+            for dependency_spdx_id in dependencies
+                .iter()
+                .filter_map(|dependency| spdx_ids.get(dependency))
+            {
+                dependency_relationships.push(Relationship {
+                    spdx_element_id: dependency_spdx_id.clone(),
+                    relationship_type,
+                    related_spdx_element: package_spdx_id.clone(),
+                });
+            }
+        }
+        dependency_relationships.sort_by(|a, b| {
+            (
+                a.relationship_type,
+                &a.spdx_element_id,
+                &a.related_spdx_element,
+            )
+                .cmp(&(
+                    b.relationship_type,
+                    &b.spdx_element_id,
+                    &b.related_spdx_element,
+                ))
+        });
+        relationships.extend(dependency_relationships);
+    }
+
+    let name = match root_package_id {
+        Some(root_package_id) => {
+            format!("{}-{}", root_package_id.name, root_package_id.version)
+        }
+        None => String::from("cargo-geiger"),
+    };
+    let created_nanos = created
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    SpdxDocument {
+        spdx_version: SPDX_VERSION,
+        data_license: DATA_LICENSE,
+        spdx_id: DOCUMENT_SPDX_ID,
+        document_namespace: format!(
+            "https://github.com/geiger-rs/cargo-geiger/spdxdocs/{}-{}",
+            name, created_nanos
+        ),
+        name,
+        creation_info: CreationInfo {
+            created: created_timestamp,
+            creators: vec![tool],
+        },
+        packages,
+        relationships,
+    }
+}
+
+/// Where the package can be downloaded from, which is not asserted for
+/// packages only found on the local file system
+fn download_location(package_id: &PackageId) -> String {
+    match &package_id.source {
+        Source::Registry { url, .. } if is_crates_io(url) => {
+            format!(
+                "https://crates.io/api/v1/crates/{}/{}/download",
+                package_id.name, package_id.version
+            )
+        }
+        Source::Git { url, rev } if rev.is_empty() => format!("git+{}", url),
+        Source::Git { url, rev } => format!("git+{}@{}", url, rev),
+        Source::Registry { .. }
+        | Source::Path(_)
+        | Source::Directory(_)
+        | Source::LocalRegistry(_) => String::from(NOASSERTION),
+    }
+}
+
+/// Unique SPDX identifiers made of the name and version of each package,
+/// which may only contain letters, digits, `.` and `-`
+fn package_spdx_ids<'a>(
+    package_ids: &[&'a PackageId],
+) -> HashMap<&'a PackageId, String> {
+    let mut spdx_ids = HashMap::new();
+    let mut seen = HashMap::<String, usize>::new();
+    for package_id in package_ids {
+        let spdx_id = format!(
+            "SPDXRef-Package-{}-{}",
+            package_id.name, package_id.version
+        )
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                true => c,
+                false => '-',
+            },
+        )
+        .collect::<String>();
+        let count = seen.entry(spdx_id.clone()).or_default();
+        *count += 1;
+        let spdx_id = match *count {
+            1 => spdx_id,
+            count => format!("{}-{}", spdx_id, count),
+        };
+        spdx_ids.insert(*package_id, spdx_id);
+    }
+    spdx_ids
+}
+
+/// Format a time as `YYYY-MM-DDThh:mm:ssZ`
+fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Convert the days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod spdx_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use cargo_geiger_serde::DependencyKind;
+    use rstest::*;
+    use std::time::Duration;
+    use url::Url;
+
+    #[rstest]
+    fn safety_report_to_spdx_test() {
+        let root_id = PackageId {
+            source: Source::Path(Url::parse("file:///r").unwrap()),
+            ..package_id("root", "0.1.0")
+        };
+        let dev_dep_id = PackageId {
+            source: Source::Git {
+                url: Url::parse("https://github.com/owner/repo").unwrap(),
+                rev: String::from("abc123"),
+            },
+            ..package_id("dev_dep", "0.1.0")
+        };
+        let mut root = report_entry(root_id.clone(), 0, true);
+        root.package.add_dependency(
+            package_id("normal_dep", "0.1.0"),
+            DependencyKind::Normal,
+        );
+        root.package.add_dependency(
+            package_id("build_dep", "0.1.0"),
+            DependencyKind::Build,
+        );
+        root.package
+            .add_dependency(dev_dep_id.clone(), DependencyKind::Development);
+        let mut report = safety_report(vec![root]);
+        report.packages_without_metrics.extend(vec![
+            package_id("normal_dep", "0.1.0"),
+            package_id("build_dep", "0.1.0"),
+            dev_dep_id,
+        ]);
+
+        let document = serde_json::to_value(safety_report_to_spdx(
+            &report,
+            Some(&root_id),
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ))
+        .unwrap();
+
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(document["name"], "root-0.1.0");
+        assert_eq!(document["creationInfo"]["created"], "2023-11-14T22:13:20Z");
+
+        let packages = document["packages"].as_array().unwrap();
+        let package = |name: &str| {
+            packages
+                .iter()
+                .find(|package| package["name"] == name)
+                .unwrap()
+        };
+        assert_eq!(
+            package("build_dep")["downloadLocation"],
+            "https://crates.io/api/v1/crates/build_dep/0.1.0/download"
+        );
+        assert_eq!(
+            package("dev_dep")["downloadLocation"],
+            "git+https://github.com/owner/repo@abc123"
+        );
+        assert_eq!(package("root")["downloadLocation"], "NOASSERTION");
+        assert!(package("build_dep").get("annotations").is_none());
+        assert_eq!(
+            package("root")["annotations"][0]["comment"],
+            serde_json::to_string(&report.packages[&root_id].unsafety).unwrap()
+        );
+
+        let relationships = document["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|relationship| {
+                format!(
+                    "{} {} {}",
+                    relationship["spdxElementId"].as_str().unwrap(),
+                    relationship["relationshipType"].as_str().unwrap(),
+                    relationship["relatedSpdxElement"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            relationships,
+            vec![
+                "SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package-root-0.1.0",
+                "SPDXRef-Package-build-dep-0.1.0 BUILD_DEPENDENCY_OF SPDXRef-Package-root-0.1.0",
+                "SPDXRef-Package-root-0.1.0 DEPENDS_ON SPDXRef-Package-normal-dep-0.1.0",
+                "SPDXRef-Package-dev-dep-0.1.0 DEV_DEPENDENCY_OF SPDXRef-Package-root-0.1.0",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn safety_report_to_spdx_test_skips_missing_dependencies() {
+        let mut root = report_entry(package_id("root", "0.1.0"), 0, true);
+        root.package.add_dependency(
+            package_id("missing_dep", "0.1.0"),
+            DependencyKind::Normal,
+        );
+        root.package.add_dependency(
+            package_id("missing_build_dep", "0.1.0"),
+            DependencyKind::Build,
+        );
+
+        let document = serde_json::to_value(safety_report_to_spdx(
+            &safety_report(vec![root]),
+            None,
+            UNIX_EPOCH,
+        ))
+        .unwrap();
+
+        assert_eq!(document["packages"].as_array().unwrap().len(), 1);
+        assert_eq!(document["relationships"], serde_json::json!([]));
+    }
+
+    #[rstest(
+        input_seconds,
+        expected_timestamp,
+        case(0, "1970-01-01T00:00:00Z"),
+        case(951_782_400, "2000-02-29T00:00:00Z"),
+        case(1_792_281_599, "2026-10-17T23:59:59Z")
+    )]
+    fn utc_timestamp_test(input_seconds: u64, expected_timestamp: &str) {
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(input_seconds)),
+            expected_timestamp
+        );
+    }
+}
@@ -5,18 +5,21 @@ use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::{colorize, OutputFormat, PrintConfig};
 use crate::format::CrateDetectionStatus;
 use crate::mapping::CargoMetadataParameters;
-use crate::scan::{GeigerContext, ScanResult};
-use crate::tree::TextTreeLine;
+use crate::scan::{GeigerContext, RustStd, ScanResult, RUST_STD_GROUP_NAME};
+use crate::tree::{construct_tree_vines_string, TextTreeLine};
 
 use handle_text_tree_line::{
+    rust_std_package_to_table_line_string,
     text_tree_line_extra_deps_group_to_table_line_string,
     text_tree_line_package_to_table_line_string, HandlePackageParameters,
 };
 use total_package_counts::TotalPackageCounts;
 
-use cargo_geiger_serde::{Count, CounterBlock};
+use cargo_geiger_serde::{Count, CounterBlock, CounterBlockDelta};
 use colored::ColoredString;
+use krates::cm::PackageId;
 use std::collections::HashSet;
+use std::fmt::Display;
 use std::path::PathBuf;
 
 // TODO: use a table library, or factor the tableness out in a smarter way. This
@@ -72,6 +75,27 @@ pub fn create_table_from_text_tree_lines(
         table_lines.push(table_line);
     }
 
+    if let Some(rust_std) = table_parameters.rust_std {
+        // The standard library is left out of the totals unless asked for
+        let mut rust_std_package_counts = TotalPackageCounts::new();
+        let mut rust_std_package_parameters = HandlePackageParameters {
+            total_package_counts: if table_parameters.include_std_in_totals {
+                &mut *handle_package_parameters.total_package_counts
+            } else {
+                &mut rust_std_package_counts
+            },
+            visited_package_ids: &mut *handle_package_parameters
+                .visited_package_ids,
+            warning_count: &mut *handle_package_parameters.warning_count,
+        };
+        table_lines.append(&mut rust_std_table_lines(
+            &emoji_symbols,
+            &mut rust_std_package_parameters,
+            rust_std,
+            table_parameters,
+        ));
+    }
+
     table_lines.push(String::new());
     let total_detection_status =
         total_package_counts.get_total_detection_status();
@@ -96,8 +120,45 @@ pub fn create_table_from_text_tree_lines(
 
 pub struct TableParameters<'a> {
     pub geiger_context: &'a GeigerContext,
+    /// Whether the standard library is counted in the totals
+    pub include_std_in_totals: bool,
+    /// Packages which are labelled as patched or replaced in the tree
+    pub patched_package_ids: &'a HashSet<PackageId>,
     pub print_config: &'a PrintConfig,
     pub rs_files_used: &'a HashSet<PathBuf>,
+    /// The standard library, listed under its own group after the tree
+    pub rust_std: Option<&'a RustStd>,
+}
+
+/// The lines of the group listing the standard library packages
+fn rust_std_table_lines(
+    emoji_symbols: &EmojiSymbols,
+    handle_package_parameters: &mut HandlePackageParameters,
+    rust_std: &RustStd,
+    table_parameters: &TableParameters,
+) -> Vec<String> {
+    let print_config = table_parameters.print_config;
+    let mut table_lines = vec![
+        String::new(),
+        format!(
+            "{}{}{}",
+            table_row_empty(),
+            construct_tree_vines_string(&mut [], print_config),
+            RUST_STD_GROUP_NAME
+        ),
+    ];
+    for (index, rust_std_package) in rust_std.packages.iter().enumerate() {
+        let is_last = index + 1 == rust_std.packages.len();
+        table_lines.push(rust_std_package_to_table_line_string(
+            emoji_symbols,
+            handle_package_parameters,
+            &rust_std_package.package,
+            rust_std,
+            table_parameters,
+            construct_tree_vines_string(&mut [!is_last], print_config),
+        ));
+    }
+    table_lines
 }
 
 fn table_footer_unsafe_counts(
@@ -168,7 +229,7 @@ fn table_footer(
     }
 }
 
-fn table_row(
+pub fn table_row(
     used: &CounterBlock,
     not_used: &CounterBlock,
     output_format: OutputFormat,
@@ -226,6 +287,31 @@ fn table_row(
     }
 }
 
+/// A table row with one column per unsafe counter, laid out like the rows
+/// returned by `table_row` for the non ratio output formats
+pub fn table_row_columns<T: Display>(columns: [T; 5]) -> String {
+    let [functions, exprs, item_impls, item_traits, methods] =
+        columns.map(|column| column.to_string());
+    format!(
+        "{: <10} {: <12} {: <6} {: <7} {: <7}",
+        functions, exprs, item_impls, item_traits, methods
+    )
+}
+
+/// A table row with the signed change of every unsafe counter
+pub fn table_row_delta(delta: &CounterBlockDelta) -> String {
+    table_row_columns(
+        [
+            delta.functions.unsafe_,
+            delta.exprs.unsafe_,
+            delta.item_impls.unsafe_,
+            delta.item_traits.unsafe_,
+            delta.methods.unsafe_,
+        ]
+        .map(|change| format!("{:+}", change)),
+    )
+}
+
 fn table_row_empty() -> String {
     let headers_but_last =
         &UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1];
@@ -332,6 +418,18 @@ mod table_tests {
         assert_eq!(table_row, "4/6        8/12         12/18  16/24   20/30  ");
     }
 
+    #[rstest]
+    fn table_row_delta_test() {
+        let mut delta = CounterBlockDelta::default();
+        delta.functions.unsafe_ = 2;
+        delta.exprs.unsafe_ = -3;
+
+        assert_eq!(
+            table_row_delta(&delta),
+            String::from("+2         -3           +0     +0      +0     ")
+        );
+    }
+
     #[rstest]
     fn table_row_empty_test() {
         let empty_table_row = table_row_empty();
@@ -377,6 +475,7 @@ mod table_tests {
                 forbids_unsafe,
             },
             is_crate_entry_point,
+            unsafe_sites: vec![],
         }
     }
 
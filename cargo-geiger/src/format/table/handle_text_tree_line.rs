@@ -2,14 +2,15 @@ use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::{colorize, OutputFormat};
 use crate::format::{get_kind_group_name, CrateDetectionStatus, SymbolKind};
 use crate::mapping::CargoMetadataParameters;
-use crate::scan::unsafe_stats;
+use crate::scan::{unsafe_stats, RustStd};
 
 use super::total_package_counts::TotalPackageCounts;
 use super::TableParameters;
 use super::{table_row, table_row_empty};
 
+use cargo_geiger_serde::UnsafeInfo;
 use colored::ColoredString;
-use krates::cm::{DependencyKind, PackageId};
+use krates::cm::{DependencyKind, Package, PackageId};
 use std::collections::HashSet;
 use std::fmt::Display;
 
@@ -53,6 +54,71 @@ pub fn text_tree_line_package_to_table_line_string(
     };
     let unsafe_info =
         unsafe_stats(package_metrics, table_parameters.rs_files_used);
+    let package_name = format!(
+        "{}{}",
+        table_parameters
+            .print_config
+            .format
+            .display(cargo_metadata_parameters, &package_id),
+        if table_parameters.patched_package_ids.contains(&package_id) {
+            " (patched)"
+        } else {
+            ""
+        }
+    );
+
+    Some(package_to_table_line_string(
+        emoji_symbols,
+        handle_package_parameters,
+        package_is_new,
+        package_name,
+        table_parameters,
+        tree_vines,
+        unsafe_info,
+    ))
+}
+
+/// The table line of a package of the standard library, which is not part of
+/// the dependency graph and has no metrics when it has no `.rs` files
+pub fn rust_std_package_to_table_line_string(
+    emoji_symbols: &EmojiSymbols,
+    handle_package_parameters: &mut HandlePackageParameters,
+    package: &Package,
+    rust_std: &RustStd,
+    table_parameters: &TableParameters,
+    tree_vines: String,
+) -> String {
+    let package_metrics = rust_std
+        .geiger_context
+        .package_id_to_metrics
+        .get(&package.id)
+        .cloned()
+        .unwrap_or_default();
+    let unsafe_info = unsafe_stats(&package_metrics, &rust_std.rs_files_used);
+    let package_is_new = handle_package_parameters
+        .visited_package_ids
+        .insert(package.id.clone());
+
+    package_to_table_line_string(
+        emoji_symbols,
+        handle_package_parameters,
+        package_is_new,
+        format!("{} {}", package.name, package.version),
+        table_parameters,
+        tree_vines,
+        unsafe_info,
+    )
+}
+
+fn package_to_table_line_string(
+    emoji_symbols: &EmojiSymbols,
+    handle_package_parameters: &mut HandlePackageParameters,
+    package_is_new: bool,
+    package_name: String,
+    table_parameters: &TableParameters,
+    tree_vines: String,
+    unsafe_info: UnsafeInfo,
+) -> String {
     if package_is_new {
         handle_package_parameters
             .total_package_counts
@@ -87,13 +153,7 @@ pub fn text_tree_line_package_to_table_line_string(
     let package_name = colorize(
         &crate_detection_status,
         table_parameters.print_config.output_format,
-        format!(
-            "{}",
-            table_parameters
-                .print_config
-                .format
-                .display(cargo_metadata_parameters, &package_id)
-        ),
+        package_name,
     );
     let unsafe_info = colorize(
         &crate_detection_status,
@@ -105,7 +165,7 @@ pub fn text_tree_line_package_to_table_line_string(
         ),
     );
 
-    Some(construct_package_text_tree_line(
+    construct_package_text_tree_line(
         crate_detection_status,
         emoji_symbols,
         icon,
@@ -113,7 +173,7 @@ pub fn text_tree_line_package_to_table_line_string(
         table_parameters,
         tree_vines,
         unsafe_info,
-    ))
+    )
 }
 
 fn construct_package_text_tree_line(
@@ -255,11 +315,14 @@ mod handle_text_tree_line_tests {
         let package_name = String::from("package_name").normal();
         let table_parameters = TableParameters {
             geiger_context: &Default::default(),
+            include_std_in_totals: false,
+            patched_package_ids: &Default::default(),
             print_config: &PrintConfig {
                 output_format: input_output_format,
                 ..Default::default()
             },
             rs_files_used: &Default::default(),
+            rust_std: None,
         };
         let tree_vines = String::from("tree_vines");
         let unsafe_info = ColoredString::from("unsafe_info").normal();
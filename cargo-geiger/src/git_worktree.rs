@@ -0,0 +1,166 @@
+use crate::args::Args;
+
+use anyhow::anyhow;
+use cargo::util::important_paths;
+use cargo::{CargoResult, GlobalContext};
+use cargo_util::ProcessBuilder;
+use std::path::{Path, PathBuf};
+use std::str;
+use tempfile::TempDir;
+
+/// A git revision of the workspace checked out into a temporary worktree of
+/// the local repository. The worktree is removed when this is dropped.
+pub struct TemporaryWorktree {
+    /// Path of the manifest to scan, inside the worktree
+    pub manifest_path: PathBuf,
+    repository_root: PathBuf,
+    worktree_path: PathBuf,
+    // Removed after the worktree has been unregistered from the repository
+    _dir: TempDir,
+}
+
+impl TemporaryWorktree {
+    /// The arguments to scan the checked out revision with
+    pub fn scan_args(&self, args: &Args) -> Args {
+        let mut scan_args = args.clone();
+        scan_args.manifest_path = Some(self.manifest_path.clone());
+        scan_args
+    }
+}
+
+impl Drop for TemporaryWorktree {
+    fn drop(&mut self) {
+        let _ = git(&self.repository_root)
+            .arg("worktree")
+            .arg("remove")
+            .arg("--force")
+            .arg(&self.worktree_path)
+            .exec_with_output();
+    }
+}
+
+/// Check out the revision given with `--rev` into a temporary worktree of the
+/// repository containing the manifest, leaving the working copy untouched
+pub fn create_temporary_worktree(
+    rev: &str,
+    args: &Args,
+    gctx: &GlobalContext,
+) -> CargoResult<TemporaryWorktree> {
+    let manifest_path = match &args.manifest_path {
+        Some(manifest_path) => manifest_path.clone(),
+        None => important_paths::find_root_manifest_for_wd(gctx.cwd())?,
+    }
+    .canonicalize()?;
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+    let repository_root = PathBuf::from(git_output(
+        git(manifest_dir).arg("rev-parse").arg("--show-toplevel"),
+    )?)
+    .canonicalize()?;
+    let commit = git_output(
+        git(&repository_root)
+            .arg("rev-parse")
+            .arg("--verify")
+            .arg(format!("{}^{{commit}}", rev)),
+    )
+    .map_err(|e| {
+        anyhow!(
+            "`{}` is not a revision of `{}`: {}",
+            rev,
+            repository_root.display(),
+            e
+        )
+    })?;
+    let relative_manifest_path =
+        manifest_path.strip_prefix(&repository_root)?.to_path_buf();
+
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-geiger-rev-")
+        .tempdir()?;
+    let worktree_path = dir.path().join("worktree");
+    git(&repository_root)
+        .arg("worktree")
+        .arg("add")
+        .arg("--detach")
+        .arg(&worktree_path)
+        .arg(&commit)
+        .exec_with_output()?;
+
+    let temporary_worktree = TemporaryWorktree {
+        manifest_path: worktree_path.join(&relative_manifest_path),
+        repository_root,
+        worktree_path,
+        _dir: dir,
+    };
+    if !temporary_worktree.manifest_path.exists() {
+        return Err(anyhow!(
+            "`{}` does not exist at revision `{}`",
+            relative_manifest_path.display(),
+            rev
+        ));
+    }
+    Ok(temporary_worktree)
+}
+
+fn git(dir: &Path) -> ProcessBuilder {
+    let mut process = ProcessBuilder::new("git");
+    process.arg("-C").arg(dir);
+    process
+}
+
+fn git_output(process: &mut ProcessBuilder) -> CargoResult<String> {
+    let output = process.exec_with_output()?;
+    Ok(str::from_utf8(&output.stdout)?.trim().to_string())
+}
+
+#[cfg(test)]
+mod git_worktree_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn create_temporary_worktree_test() {
+        let repository = tempdir().unwrap();
+        let git_in_repository = |arguments: &[&str]| {
+            let mut process = git(repository.path());
+            process
+                .arg("-c")
+                .arg("user.name=test")
+                .arg("-c")
+                .arg("user.email=test@example.com")
+                .args(arguments);
+            process.exec_with_output().unwrap();
+        };
+        fs::write(repository.path().join("Cargo.toml"), "old").unwrap();
+        git_in_repository(&["init", "--quiet"]);
+        git_in_repository(&["add", "Cargo.toml"]);
+        git_in_repository(&["commit", "--quiet", "-m", "old"]);
+        fs::write(repository.path().join("Cargo.toml"), "new").unwrap();
+
+        let args = Args {
+            manifest_path: Some(repository.path().join("Cargo.toml")),
+            ..Default::default()
+        };
+        let gctx = GlobalContext::default().unwrap();
+        let temporary_worktree =
+            create_temporary_worktree("HEAD", &args, &gctx).unwrap();
+        let manifest_path = temporary_worktree.manifest_path.clone();
+
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), "old");
+        assert_eq!(
+            temporary_worktree.scan_args(&args).manifest_path,
+            Some(manifest_path.clone())
+        );
+        assert!(create_temporary_worktree("no-such-rev", &args, &gctx).is_err());
+
+        drop(temporary_worktree);
+        assert!(!manifest_path.exists());
+        assert_eq!(
+            fs::read_to_string(repository.path().join("Cargo.toml")).unwrap(),
+            "new"
+        );
+    }
+}
@@ -38,7 +38,10 @@ pub fn build_graph<'a>(
         &args.deps_args,
         &args.target_args,
     );
-    let cfgs = get_cfgs(global_rustc_path, &args.target_args.target)?;
+    let cfgs = get_cfgs(
+        global_rustc_path,
+        &args.target_args.target().map(String::from),
+    )?;
 
     let mut graph = Graph {
         graph: petgraph::Graph::new(),
@@ -161,7 +164,7 @@ fn build_graph_prerequisites<'a>(
     let target = if target_args.all_targets {
         None
     } else {
-        Some(target_args.target.as_deref().unwrap_or(config_host))
+        Some(target_args.target().unwrap_or(config_host))
     };
 
     (extra_deps, target)
@@ -263,20 +266,20 @@ mod graph_tests {
         case(
             TargetArgs {
                 all_targets: true,
-                target: None
+                targets: vec![]
             },
             None
         ),
         case(
             TargetArgs {
                 all_targets: false,
-                target: None
+                targets: vec![]
             },
             Some("default_config_host")),
         case(
             TargetArgs {
                 all_targets: false,
-                target: Some(String::from("provided_config_host")),
+                targets: vec![String::from("provided_config_host")],
             },
             Some("provided_config_host")
         )
@@ -8,14 +8,28 @@
 
 /// Argument parsing
 pub mod args;
+/// Comparison of the scan results with a baseline
+pub mod baseline;
 /// Bootstrapping functions for structs required by the CLI
 pub mod cli;
+/// Bulk scanning of `.crate` archives
+pub mod dataset;
+/// Comparison of two JSON reports
+pub mod diff;
+/// Checking out a git revision of the workspace into a temporary worktree
+pub mod git_worktree;
 /// Construction of the dependency graph
 pub mod graph;
+/// Reading the packages pinned by a `Cargo.lock` file from the local cache
+pub mod lockfile;
 /// Mapping functionality from `cargo::core` to `cargo_metadata`
 pub mod mapping;
+/// Rules checked against the scan results with `--check`
+pub mod policy;
 /// Interaction with README.md files
 pub mod readme;
+/// Scanning of a single crate from the local registry cache
+pub mod registry_crate;
 /// Functions for scanning projects for unsafe code
 pub mod scan;
 
@@ -26,9 +40,18 @@ mod tree;
 
 #[cfg(test)]
 mod lib_tests {
+    use crate::scan::UsedUnsafeSite;
+
+    use cargo_geiger_serde::{
+        PackageId, PackageInfo, ReportEntry, SafetyReport, Source, UnsafeInfo,
+    };
+    use geiger::{UnsafeKind, UnsafeSite};
     use krates::cm::{CargoOpt, Metadata, MetadataCommand};
     use krates::Builder as KratesBuilder;
     use krates::Krates;
+    use semver::Version;
+    use std::path::PathBuf;
+    use url::Url;
 
     pub fn construct_krates_and_metadata() -> (Krates, Metadata) {
         let metadata = MetadataCommand::new()
@@ -43,4 +66,68 @@ mod lib_tests {
 
         (krates, metadata)
     }
+
+    /// A package from crates.io
+    pub fn package_id(name: &str, version: &str) -> PackageId {
+        PackageId {
+            name: String::from(name),
+            version: Version::parse(version).unwrap(),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        }
+    }
+
+    /// A package with no dependencies, using `unsafe_exprs` unsafe
+    /// expressions and nothing else
+    pub fn report_entry(
+        package_id: PackageId,
+        unsafe_exprs: u64,
+        forbids_unsafe: bool,
+    ) -> ReportEntry {
+        let mut unsafety = UnsafeInfo {
+            forbids_unsafe,
+            ..Default::default()
+        };
+        unsafety.used.exprs.unsafe_ = unsafe_exprs;
+        ReportEntry {
+            package: PackageInfo::new(package_id),
+            unsafety,
+        }
+    }
+
+    /// An unsafe site of version 1.2.3 of a package from crates.io, starting
+    /// in column 5 of `line` and ending in column 5 two lines below it
+    pub fn used_unsafe_site(
+        name: &str,
+        is_workspace_member: bool,
+        path: &str,
+        kind: UnsafeKind,
+        line: usize,
+    ) -> UsedUnsafeSite {
+        UsedUnsafeSite {
+            package: package_id(name, "1.2.3"),
+            is_workspace_member,
+            path: PathBuf::from(path),
+            site: UnsafeSite {
+                kind,
+                line,
+                column: 5,
+                end_line: line + 2,
+                end_column: 5,
+            },
+        }
+    }
+
+    pub fn safety_report(entries: Vec<ReportEntry>) -> SafetyReport {
+        SafetyReport {
+            packages: entries
+                .into_iter()
+                .map(|entry| (entry.package.id.clone(), entry))
+                .collect(),
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,1251 @@
+use crate::scan::registry_index_dir_name;
+
+use anyhow::{anyhow, bail};
+use cargo::core::SourceId;
+use cargo::{CargoResult, GlobalContext};
+use krates::cm::{Dependency, DependencyKind, Metadata, Package};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A package entry of a `Cargo.lock` file
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    fn is_path(&self) -> bool {
+        self.source.is_none()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+/// Construct the `cargo metadata` of the packages pinned by a `Cargo.lock`
+/// file, without resolving any dependencies or accessing the network.
+///
+/// The sources of registry and git packages are looked up in `$CARGO_HOME`,
+/// the sources of path packages below the directory of the lockfile. The
+/// package given with `--package`, the package next to the lockfile or the
+/// only path package is used as the root. A lockfile does not record
+/// features, see `resolve_features` for how they are approximated.
+pub fn get_lockfile_metadata(
+    lockfile_path: &Path,
+    root_package_name: Option<&str>,
+    gctx: &GlobalContext,
+) -> CargoResult<Metadata> {
+    let workspace_root = lockfile_path
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    get_lockfile_metadata_in_workspace(
+        lockfile_path,
+        &workspace_root,
+        root_package_name,
+        gctx,
+    )
+}
+
+/// Like `get_lockfile_metadata`, but with the sources of path packages looked
+/// up below `workspace_root`, for lockfiles which are not stored in their
+/// workspace, such as an older version of it.
+pub fn get_lockfile_metadata_in_workspace(
+    lockfile_path: &Path,
+    workspace_root: &Path,
+    root_package_name: Option<&str>,
+    gctx: &GlobalContext,
+) -> CargoResult<Metadata> {
+    let lockfile_contents = fs::read_to_string(lockfile_path).map_err(|e| {
+        anyhow!("failed to read `{}`: {}", lockfile_path.display(), e)
+    })?;
+    let locked_packages = toml::from_str::<Lockfile>(&lockfile_contents)
+        .map_err(|e| {
+            anyhow!("failed to parse `{}`: {}", lockfile_path.display(), e)
+        })?
+        .package;
+
+    let workspace_root = workspace_root.to_path_buf();
+    let cargo_home = gctx.home().as_path_unlocked();
+    let path_manifests = find_path_manifests(&workspace_root);
+
+    let mut manifest_paths = vec![];
+    let mut not_found = vec![];
+    for locked_package in &locked_packages {
+        match find_manifest(gctx, cargo_home, &path_manifests, locked_package) {
+            Some(manifest_path) => manifest_paths.push(manifest_path),
+            None => not_found.push(format!(
+                "  {} {}{}",
+                locked_package.name,
+                locked_package.version,
+                locked_package
+                    .source
+                    .as_ref()
+                    .map(|source| format!(" ({})", source))
+                    .unwrap_or_default()
+            )),
+        }
+    }
+    if !not_found.is_empty() {
+        bail!(
+            "the sources of {} package(s) pinned by `{}` were not found in \
+             `{}`, fetch them with `cargo fetch` first:\n{}",
+            not_found.len(),
+            lockfile_path.display(),
+            cargo_home.display(),
+            not_found.join("\n")
+        );
+    }
+
+    let packages = locked_packages
+        .iter()
+        .zip(&manifest_paths)
+        .map(|(locked_package, manifest_path)| {
+            read_package(locked_package, manifest_path, gctx)
+        })
+        .collect::<CargoResult<Vec<Package>>>()?;
+
+    let root_index = find_root_package_index(
+        &locked_packages,
+        &manifest_paths,
+        &workspace_root,
+        root_package_name,
+    )
+    .ok_or_else(|| {
+        anyhow!(
+            "no root package found for `{}`, choose one of the workspace \
+             members with `--package`",
+            lockfile_path.display()
+        )
+    })?;
+
+    let dependency_indices = locked_packages
+        .iter()
+        .map(|locked_package| {
+            resolve_dependencies(&locked_packages, locked_package)
+        })
+        .collect::<CargoResult<Vec<Vec<usize>>>>()?;
+    let member_indices = locked_packages
+        .iter()
+        .enumerate()
+        .filter(|(_, locked_package)| locked_package.is_path())
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+    let features =
+        resolve_features(&packages, &dependency_indices, &member_indices);
+
+    // Optional dependencies are pinned by the lockfile whether or not any
+    // feature enables them, but only the enabled ones are part of the resolve
+    // graph.
+    let enabled_dependency_indices = packages
+        .iter()
+        .zip(&dependency_indices)
+        .zip(&features)
+        .map(|((package, dependency_indices), features)| {
+            let (enabled_dependencies, _) =
+                dependency_feature_requests(package, features);
+            dependency_indices
+                .iter()
+                .copied()
+                .filter(|&index| {
+                    declared_dependencies(package, &packages[index]).iter().any(
+                        |dependency| {
+                            is_dependency_enabled(
+                                dependency,
+                                &enabled_dependencies,
+                            )
+                        },
+                    )
+                })
+                .collect::<Vec<usize>>()
+        })
+        .collect::<Vec<Vec<usize>>>();
+    let reachable =
+        reachable_indices(&enabled_dependency_indices, &member_indices);
+
+    let nodes = reachable
+        .iter()
+        .map(|&index| {
+            let package = &packages[index];
+            let dependency_packages = enabled_dependency_indices[index]
+                .iter()
+                .map(|&dependency_index| &packages[dependency_index])
+                .collect::<Vec<&Package>>();
+            json!({
+                "id": package.id,
+                "dependencies": dependency_packages
+                    .iter()
+                    .map(|dependency_package| &dependency_package.id)
+                    .collect::<Vec<_>>(),
+                "deps": dependency_packages
+                    .iter()
+                    .map(|dependency_package| {
+                        node_dep(package, dependency_package)
+                    })
+                    .collect::<Vec<serde_json::Value>>(),
+                "features": features[index],
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    let workspace_members = member_indices
+        .iter()
+        .map(|&index| &packages[index].id)
+        .collect::<Vec<_>>();
+    let root = &packages[root_index].id;
+    let packages = reachable
+        .iter()
+        .map(|&index| &packages[index])
+        .collect::<Vec<&Package>>();
+
+    // The `[workspace.metadata]` table of the manifest next to the lockfile
+    let workspace_metadata = read_manifest(&workspace_root.join("Cargo.toml"))
+        .and_then(|manifest| {
+            manifest.get("workspace")?.get("metadata").cloned()
+        });
+
+    Ok(serde_json::from_value(json!({
+        "packages": packages,
+        "workspace_members": workspace_members,
+        "resolve": {
+            "nodes": nodes,
+            "root": root,
+        },
+        "workspace_root": workspace_root,
+        "target_directory": workspace_root.join("target"),
+        "metadata": workspace_metadata,
+        "version": 1,
+    }))?)
+}
+
+/// The packages reachable from the workspace members, in lockfile order
+fn reachable_indices(
+    dependency_indices: &[Vec<usize>],
+    member_indices: &[usize],
+) -> BTreeSet<usize> {
+    let mut reachable = BTreeSet::new();
+    let mut pending = member_indices.to_vec();
+    while let Some(index) = pending.pop() {
+        if reachable.insert(index) {
+            pending.extend(&dependency_indices[index]);
+        }
+    }
+    reachable
+}
+
+/// Read the manifest of a locked package the same way cargo does, and convert
+/// it to its `cargo metadata` representation
+fn read_package(
+    locked_package: &LockedPackage,
+    manifest_path: &Path,
+    gctx: &GlobalContext,
+) -> CargoResult<Package> {
+    let source_id = match &locked_package.source {
+        Some(source) => SourceId::from_url(source)?,
+        None => SourceId::for_path(manifest_path.parent().unwrap())?,
+    };
+    let package = cargo::ops::read_package(manifest_path, source_id, gctx)?;
+    let serialized_package = package.serialized(
+        gctx.cli_unstable(),
+        package.manifest().unstable_features(),
+    );
+    Ok(serde_json::from_value(serde_json::to_value(
+        serialized_package,
+    )?)?)
+}
+
+/// A dependency in `Cargo.lock` is recorded as `name`, `name version` or
+/// `name version (source)`, with as much detail as needed to tell it apart
+/// from the other packages of the lockfile.
+fn resolve_dependencies(
+    locked_packages: &[LockedPackage],
+    locked_package: &LockedPackage,
+) -> CargoResult<Vec<usize>> {
+    locked_package
+        .dependencies
+        .iter()
+        .map(|dependency| {
+            let mut parts = dependency.splitn(3, ' ');
+            let name = parts.next().unwrap_or_default();
+            let version = parts.next();
+            let source = parts
+                .next()
+                .map(|source| source.trim_start_matches('(').trim_end_matches(')'));
+            locked_packages
+                .iter()
+                .position(|candidate| {
+                    candidate.name == name
+                        && version.is_none_or(|v| candidate.version == v)
+                        && source.is_none_or(|s| {
+                            candidate.source.as_deref() == Some(s)
+                        })
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "dependency `{}` of `{} {}` is not pinned by the lockfile",
+                        dependency,
+                        locked_package.name,
+                        locked_package.version
+                    )
+                })
+        })
+        .collect()
+}
+
+/// A dependency edge of the resolve graph, with the dependency kinds declared
+/// in the manifest of the dependent package
+fn node_dep(
+    package: &Package,
+    dependency_package: &Package,
+) -> serde_json::Value {
+    let declared_dependencies =
+        declared_dependencies(package, dependency_package);
+
+    let name = declared_dependencies
+        .iter()
+        .find_map(|dependency| dependency.rename.clone())
+        .unwrap_or_else(|| dependency_package.name.clone())
+        .replace('-', "_");
+    let dep_kinds = declared_dependencies
+        .iter()
+        .map(|dependency| {
+            json!({
+                "kind": match dependency.kind {
+                    DependencyKind::Development => Some("dev"),
+                    DependencyKind::Build => Some("build"),
+                    _ => None,
+                },
+                "target": dependency.target.as_ref().map(ToString::to_string),
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    json!({
+        "name": name,
+        "pkg": dependency_package.id,
+        "dep_kinds": dep_kinds,
+    })
+}
+
+/// The dependencies declared by `package` which resolve to
+/// `dependency_package`, one for every kind and target it is declared for
+fn declared_dependencies<'a>(
+    package: &'a Package,
+    dependency_package: &Package,
+) -> Vec<&'a Dependency> {
+    package
+        .dependencies
+        .iter()
+        .filter(|dependency| {
+            dependency.name == dependency_package.name
+                && dependency.req.matches(&dependency_package.version)
+        })
+        .collect()
+}
+
+/// Approximate the features cargo activates for every package, starting from
+/// the default features of the workspace members and following the features
+/// each dependency is declared with, including the ones enabled through
+/// `dep:`, `pkg/feat` and `pkg?/feat` feature values.
+///
+/// A lockfile does not record features, and this differs from cargo in that
+/// it can only enable more of them:
+///
+/// - the features requested by normal, build and dev-dependencies are
+///   unified, as with resolver 1, where resolver 2 keeps the build
+///   dependencies apart and only enables the ones of dev-dependencies when
+///   building tests, examples or benchmarks
+/// - the dependencies declared for any target are followed, not only the
+///   ones of the target being built
+/// - every path package of the lockfile is a member and gets its default
+///   features, as with `cargo build --workspace`, where `--features` and
+///   `--no-default-features` are not applied and path dependencies outside
+///   the workspace only get the features requested by their dependents
+fn resolve_features(
+    packages: &[Package],
+    dependency_indices: &[Vec<usize>],
+    member_indices: &[usize],
+) -> Vec<BTreeSet<String>> {
+    let mut features = vec![BTreeSet::new(); packages.len()];
+    for &index in member_indices {
+        enable_feature(&packages[index], &mut features[index], "default");
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, package) in packages.iter().enumerate() {
+            let (enabled_dependencies, feature_requests) =
+                dependency_feature_requests(package, &features[index]);
+
+            for &dependency_index in &dependency_indices[index] {
+                let dependency_package = &packages[dependency_index];
+                for dependency in
+                    declared_dependencies(package, dependency_package)
+                {
+                    if !is_dependency_enabled(dependency, &enabled_dependencies)
+                    {
+                        continue;
+                    }
+                    let key =
+                        dependency.rename.as_ref().unwrap_or(&dependency.name);
+                    let requested_features = dependency
+                        .features
+                        .iter()
+                        .map(String::as_str)
+                        .chain(
+                            dependency
+                                .uses_default_features
+                                .then_some("default"),
+                        )
+                        .chain(
+                            feature_requests
+                                .iter()
+                                .filter(|(dependency_key, _)| {
+                                    dependency_key == key
+                                })
+                                .map(|(_, feature)| feature.as_str()),
+                        )
+                        .collect::<Vec<&str>>();
+                    for feature in requested_features {
+                        changed |= enable_feature(
+                            dependency_package,
+                            &mut features[dependency_index],
+                            feature,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    features
+}
+
+fn is_dependency_enabled(
+    dependency: &Dependency,
+    enabled_dependencies: &HashSet<String>,
+) -> bool {
+    !dependency.optional
+        || enabled_dependencies
+            .contains(dependency.rename.as_ref().unwrap_or(&dependency.name))
+}
+
+/// Enable a feature declared by the package, and the features it enables in
+/// turn. Returns whether any feature was not enabled before.
+fn enable_feature(
+    package: &Package,
+    features: &mut BTreeSet<String>,
+    feature: &str,
+) -> bool {
+    let mut changed = false;
+    let mut pending = vec![feature];
+    while let Some(feature) = pending.pop() {
+        let enabled_values = match package.features.get(feature) {
+            Some(values) => values,
+            None => continue,
+        };
+        if !features.insert(String::from(feature)) {
+            continue;
+        }
+        changed = true;
+        for value in enabled_values {
+            // `dep:x` and the weak `x?/y` don't enable the feature `x`
+            let own_feature = value.split('/').next().unwrap_or_default();
+            if !value.starts_with("dep:") && !own_feature.ends_with('?') {
+                pending.push(own_feature);
+            }
+        }
+    }
+    changed
+}
+
+/// The optional dependencies the enabled features of a package turn on, and
+/// the features they request from its dependencies, by dependency name
+fn dependency_feature_requests(
+    package: &Package,
+    features: &BTreeSet<String>,
+) -> (HashSet<String>, Vec<(String, String)>) {
+    let mut enabled_dependencies = HashSet::new();
+    let mut feature_requests = vec![];
+    for value in features
+        .iter()
+        .filter_map(|feature| package.features.get(feature))
+        .flatten()
+    {
+        if let Some(dependency) = value.strip_prefix("dep:") {
+            enabled_dependencies.insert(String::from(dependency));
+        } else if let Some((dependency, feature)) = value.split_once('/') {
+            if !dependency.ends_with('?') {
+                enabled_dependencies.insert(String::from(dependency));
+            }
+            feature_requests.push((
+                String::from(dependency.trim_end_matches('?')),
+                String::from(feature),
+            ));
+        }
+    }
+    (enabled_dependencies, feature_requests)
+}
+
+fn find_root_package_index(
+    locked_packages: &[LockedPackage],
+    manifest_paths: &[PathBuf],
+    workspace_root: &Path,
+    root_package_name: Option<&str>,
+) -> Option<usize> {
+    let path_package_indices = locked_packages
+        .iter()
+        .enumerate()
+        .filter(|(_, locked_package)| locked_package.is_path())
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+
+    if let Some(root_package_name) = root_package_name {
+        let root_package_name =
+            root_package_name.split('@').next().unwrap_or_default();
+        return path_package_indices
+            .into_iter()
+            .find(|&index| locked_packages[index].name == root_package_name);
+    }
+
+    let workspace_root_manifest = workspace_root.join("Cargo.toml");
+    path_package_indices
+        .iter()
+        .copied()
+        .find(|&index| manifest_paths[index] == workspace_root_manifest)
+        .or(match path_package_indices.as_slice() {
+            [index] => Some(*index),
+            _ => None,
+        })
+}
+
+fn find_manifest(
+    gctx: &GlobalContext,
+    cargo_home: &Path,
+    path_manifests: &HashMap<(String, String), PathBuf>,
+    locked_package: &LockedPackage,
+) -> Option<PathBuf> {
+    let source = match &locked_package.source {
+        Some(source) => source,
+        None => {
+            return path_manifests
+                .get(&(
+                    locked_package.name.clone(),
+                    locked_package.version.clone(),
+                ))
+                .cloned()
+        }
+    };
+    let (kind, url) = source.split_once('+')?;
+    match kind {
+        "registry" | "sparse" => find_registry_manifest(
+            &cargo_home.join("registry").join("src").join(
+                registry_index_dir_name(
+                    gctx,
+                    SourceId::from_url(source).ok()?,
+                )?,
+            ),
+            locked_package,
+        ),
+        "git" => {
+            let (_, revision) = url.split_once('#')?;
+            find_git_manifest(
+                &cargo_home.join("git").join("checkouts"),
+                locked_package,
+                revision,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Registry packages are unpacked into
+/// `$CARGO_HOME/registry/src/<index>/<name>-<version>`, where `index_dir` is
+/// the directory of the registry the lockfile entry comes from
+fn find_registry_manifest(
+    index_dir: &Path,
+    locked_package: &LockedPackage,
+) -> Option<PathBuf> {
+    Some(
+        index_dir
+            .join(format!(
+                "{}-{}",
+                locked_package.name, locked_package.version
+            ))
+            .join("Cargo.toml"),
+    )
+    .filter(|manifest_path| manifest_path.exists())
+}
+
+/// Git dependencies are checked out into
+/// `$CARGO_HOME/git/checkouts/<repository>/<short revision>`, where the
+/// package can be in any directory of the repository.
+fn find_git_manifest(
+    git_checkouts: &Path,
+    locked_package: &LockedPackage,
+    revision: &str,
+) -> Option<PathBuf> {
+    let short_revision = revision.get(..7)?;
+    fs::read_dir(git_checkouts)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|repository_dir| repository_dir.path().join(short_revision))
+        .filter(|checkout_dir| checkout_dir.is_dir())
+        .find_map(|checkout_dir| {
+            find_package_manifests(&checkout_dir)
+                .find(|((name, version), _)| {
+                    *name == locked_package.name
+                        && *version == locked_package.version
+                })
+                .map(|(_, manifest_path)| manifest_path)
+        })
+}
+
+/// The manifests of the packages below the directory of the lockfile, and of
+/// the path dependencies they reach outside of it, by package name and
+/// version, so that a package is only matched with a lockfile entry of the
+/// version it declares
+fn find_path_manifests(
+    workspace_root: &Path,
+) -> HashMap<(String, String), PathBuf> {
+    let mut path_manifests =
+        find_package_manifests(workspace_root).collect::<HashMap<_, _>>();
+    let mut pending_manifests =
+        path_manifests.values().cloned().collect::<Vec<PathBuf>>();
+    while let Some(manifest_path) = pending_manifests.pop() {
+        for (name_and_version, dependency_manifest_path) in
+            find_path_dependency_manifests(&manifest_path)
+        {
+            if let Entry::Vacant(entry) = path_manifests.entry(name_and_version)
+            {
+                pending_manifests.push(dependency_manifest_path.clone());
+                entry.insert(dependency_manifest_path);
+            }
+        }
+    }
+    path_manifests
+}
+
+/// The manifests of the dependencies declared with a `path` by a manifest,
+/// including target specific dependencies
+fn find_path_dependency_manifests(
+    manifest_path: &Path,
+) -> Vec<((String, String), PathBuf)> {
+    let manifest = match read_manifest(manifest_path) {
+        Some(manifest) => manifest,
+        None => return vec![],
+    };
+    let manifest_dir = manifest_path.parent().unwrap_or(manifest_path);
+    let dependency_tables = |table: &toml::Value| {
+        ["dependencies", "dev-dependencies", "build-dependencies"]
+            .iter()
+            .filter_map(|key| table.get(key)?.as_table().cloned())
+            .collect::<Vec<toml::value::Table>>()
+    };
+    let mut tables = dependency_tables(&manifest);
+    if let Some(targets) =
+        manifest.get("target").and_then(toml::Value::as_table)
+    {
+        for target in targets.values() {
+            tables.extend(dependency_tables(target));
+        }
+    }
+    tables
+        .iter()
+        .flat_map(|table| table.values())
+        .filter_map(|dependency| dependency.get("path")?.as_str())
+        .filter_map(|path| {
+            let dependency_manifest_path =
+                manifest_dir.join(path).join("Cargo.toml");
+            let name_and_version = package_name_and_version(
+                &read_manifest(&dependency_manifest_path)?,
+                &dependency_manifest_path,
+            )?;
+            Some((name_and_version, dependency_manifest_path))
+        })
+        .collect()
+}
+
+/// Every package manifest below `dir`, skipping hidden and `target`
+/// directories
+fn find_package_manifests(
+    dir: &Path,
+) -> impl Iterator<Item = ((String, String), PathBuf)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !entry.file_name().to_str().is_some_and(|name| {
+                    name.starts_with('.') || name == "target"
+                })
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() == "Cargo.toml")
+        .filter_map(|entry| {
+            let name_and_version = package_name_and_version(
+                &read_manifest(entry.path())?,
+                entry.path(),
+            )?;
+            Some((name_and_version, entry.into_path()))
+        })
+}
+
+fn read_manifest(manifest_path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(manifest_path).ok()?).ok()
+}
+
+/// The name and version of the package of a manifest. The version can be
+/// inherited from the workspace, and defaults to `0.0.0` when it is omitted.
+fn package_name_and_version(
+    manifest: &toml::Value,
+    manifest_path: &Path,
+) -> Option<(String, String)> {
+    let package = manifest.get("package")?;
+    let name = String::from(package.get("name")?.as_str()?);
+    let version = match package.get("version") {
+        None => String::from("0.0.0"),
+        Some(toml::Value::String(version)) => version.clone(),
+        Some(version) => {
+            version
+                .get("workspace")?
+                .as_bool()
+                .filter(|&inherited| inherited)?;
+            workspace_package_version(manifest_path)?
+        }
+    };
+    Some((name, version))
+}
+
+/// The `workspace.package.version` of the closest manifest declaring a
+/// workspace, starting from the directory of `manifest_path`
+fn workspace_package_version(manifest_path: &Path) -> Option<String> {
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .filter_map(|dir| read_manifest(&dir.join("Cargo.toml")))
+        .find_map(|manifest| manifest.get("workspace").cloned())?
+        .get("package")?
+        .get("version")?
+        .as_str()
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod lockfile_tests {
+    use super::*;
+
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest(
+        input_dependency,
+        expected_index,
+        case("itoa", Some(0)),
+        case("syn 1.0.109", Some(1)),
+        case(
+            "syn 2.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+            Some(2)
+        ),
+        case("syn 3.0.0", None),
+        case("serde", None)
+    )]
+    fn resolve_dependencies_test(
+        input_dependency: &str,
+        expected_index: Option<usize>,
+    ) {
+        let locked_packages = [
+            locked_package("itoa", "1.0.18", &[]),
+            locked_package("syn", "1.0.109", &[]),
+            locked_package("syn", "2.0.0", &[]),
+            locked_package("root", "0.1.0", &[input_dependency]),
+        ];
+
+        let resolved =
+            resolve_dependencies(&locked_packages, &locked_packages[3]).ok();
+
+        assert_eq!(resolved, expected_index.map(|index| vec![index]));
+    }
+
+    #[rstest(
+        input_root_package_name,
+        input_path_package_names,
+        expected_root_index,
+        case(None, vec!["next_to_lockfile", "member"], Some(0)),
+        case(Some("member@0.1.0"), vec!["next_to_lockfile", "member"], Some(1)),
+        case(Some("itoa"), vec!["next_to_lockfile", "member"], None),
+        case(None, vec!["member"], Some(0)),
+        case(None, vec!["member", "other_member"], None)
+    )]
+    fn find_root_package_index_test(
+        input_root_package_name: Option<&str>,
+        input_path_package_names: Vec<&str>,
+        expected_root_index: Option<usize>,
+    ) {
+        let workspace_root = PathBuf::from("/workspace");
+        let mut locked_packages = input_path_package_names
+            .iter()
+            .map(|name| LockedPackage {
+                source: None,
+                ..locked_package(name, "0.1.0", &[])
+            })
+            .collect::<Vec<LockedPackage>>();
+        locked_packages.push(locked_package("itoa", "1.0.18", &[]));
+        let manifest_paths = locked_packages
+            .iter()
+            .map(|locked_package| match locked_package.name.as_str() {
+                "next_to_lockfile" => workspace_root.join("Cargo.toml"),
+                name => workspace_root.join(name).join("Cargo.toml"),
+            })
+            .collect::<Vec<PathBuf>>();
+
+        assert_eq!(
+            find_root_package_index(
+                &locked_packages,
+                &manifest_paths,
+                &workspace_root,
+                input_root_package_name
+            ),
+            expected_root_index
+        );
+    }
+
+    #[rstest]
+    fn reachable_indices_test() {
+        let dependency_indices = vec![vec![1], vec![2], vec![1], vec![2]];
+
+        assert_eq!(
+            reachable_indices(&dependency_indices, &[0]),
+            [0, 1, 2].iter().copied().collect::<BTreeSet<usize>>()
+        );
+    }
+
+    #[rstest]
+    fn find_manifest_test() {
+        let gctx = GlobalContext::default().unwrap();
+        let cargo_home = tempdir().unwrap();
+        let registry_package_dir = cargo_home
+            .path()
+            .join("registry/src/index.crates.io-1949cf8c6b5b557f/itoa-1.0.18");
+        let other_registry_package_dir = cargo_home.path().join(format!(
+            "registry/src/{}/itoa-1.0.20",
+            registry_index_dir_name(
+                &gctx,
+                SourceId::from_url("sparse+https://example.com/index/")
+                    .unwrap()
+            )
+            .unwrap()
+        ));
+        let git_package_dir = cargo_home
+            .path()
+            .join("git/checkouts/geiger-0123456789abcdef/abcdef1/geiger");
+        for (package_dir, name, version) in [
+            (&registry_package_dir, "itoa", "1.0.18"),
+            (&other_registry_package_dir, "itoa", "1.0.20"),
+            (&git_package_dir, "geiger", "0.5.0"),
+        ] {
+            fs::create_dir_all(package_dir).unwrap();
+            fs::write(
+                package_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"{}\"\n",
+                    name, version
+                ),
+            )
+            .unwrap();
+        }
+
+        let registry_package = locked_package("itoa", "1.0.18", &[]);
+        let other_registry_package = LockedPackage {
+            source: Some(String::from("sparse+https://example.com/index/")),
+            ..locked_package("itoa", "1.0.20", &[])
+        };
+        let git_package = LockedPackage {
+            source: Some(String::from(
+                "git+https://example.com/geiger?branch=main#abcdef1234567890",
+            )),
+            ..locked_package("geiger", "0.5.0", &[])
+        };
+        let other_git_version_package = LockedPackage {
+            version: String::from("0.6.0"),
+            ..git_package.clone()
+        };
+        let uncached_package = locked_package("itoa", "1.0.19", &[]);
+        // Only cached for the other registry
+        let other_registry_version_package =
+            locked_package("itoa", "1.0.20", &[]);
+
+        for (locked_package, expected_manifest_path) in [
+            (
+                &registry_package,
+                Some(registry_package_dir.join("Cargo.toml")),
+            ),
+            (
+                &other_registry_package,
+                Some(other_registry_package_dir.join("Cargo.toml")),
+            ),
+            (&git_package, Some(git_package_dir.join("Cargo.toml"))),
+            (&other_git_version_package, None),
+            (&uncached_package, None),
+            (&other_registry_version_package, None),
+        ] {
+            assert_eq!(
+                find_manifest(
+                    &gctx,
+                    cargo_home.path(),
+                    &HashMap::new(),
+                    locked_package
+                ),
+                expected_manifest_path
+            );
+        }
+    }
+
+    #[rstest]
+    fn find_path_manifests_test() {
+        let workspace_root = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let manifests = [
+            (
+                workspace_root.path().to_path_buf(),
+                String::from(
+                    "[workspace]\nmembers = [\"inherited\", \"declared\"]\n\
+                     [workspace.package]\nversion = \"0.3.0\"\n",
+                ),
+            ),
+            (
+                workspace_root.path().join("inherited"),
+                format!(
+                    "[package]\nname = \"inherited\"\nversion.workspace = true\n\
+                     [dependencies]\ndeclared = {{ path = \"{}\" }}\n",
+                    outside.path().display()
+                ),
+            ),
+            (
+                workspace_root.path().join("declared"),
+                String::from(
+                    "[package]\nname = \"declared\"\nversion = \"1.0.0\"\n",
+                ),
+            ),
+            (
+                outside.path().to_path_buf(),
+                String::from("[package]\nname = \"declared\"\n"),
+            ),
+        ];
+        for (package_dir, manifest) in &manifests {
+            fs::create_dir_all(package_dir).unwrap();
+            fs::write(package_dir.join("Cargo.toml"), manifest).unwrap();
+        }
+
+        let path_manifests = find_path_manifests(workspace_root.path());
+
+        assert_eq!(
+            path_manifests,
+            [
+                (("inherited", "0.3.0"), &manifests[1].0),
+                (("declared", "1.0.0"), &manifests[2].0),
+                (("declared", "0.0.0"), &manifests[3].0),
+            ]
+            .iter()
+            .map(|((name, version), package_dir)| (
+                (String::from(*name), String::from(*version)),
+                package_dir.join("Cargo.toml")
+            ))
+            .collect::<HashMap<(String, String), PathBuf>>()
+        );
+        assert_eq!(
+            find_manifest(
+                &GlobalContext::default().unwrap(),
+                Path::new("/cargo_home"),
+                &path_manifests,
+                &LockedPackage {
+                    source: None,
+                    ..locked_package("declared", "1.0.0", &[])
+                }
+            ),
+            Some(manifests[2].0.join("Cargo.toml"))
+        );
+    }
+
+    #[rstest(
+        input_feature,
+        expected_features,
+        case("default", vec!["alloc", "default", "std"]),
+        case("serde", vec!["serde"]),
+        case("derive", vec!["derive"]),
+        case("full", vec!["alloc", "full", "log", "std"]),
+        case("undeclared", vec![])
+    )]
+    fn enable_feature_test(input_feature: &str, expected_features: Vec<&str>) {
+        let package = package(
+            "lib",
+            "1.0.0",
+            json!({
+                "default": ["std"],
+                "std": ["alloc"],
+                "alloc": [],
+                "serde": ["dep:serde"],
+                "derive": ["serde?/derive"],
+                "log": ["dep:log"],
+                "full": ["std", "log/std"]
+            }),
+            vec![],
+        );
+        let mut features = BTreeSet::new();
+
+        let changed = enable_feature(&package, &mut features, input_feature);
+
+        assert_eq!(changed, !expected_features.is_empty());
+        assert_eq!(features, string_set(&expected_features));
+        assert!(!enable_feature(&package, &mut features, input_feature));
+    }
+
+    #[rstest(
+        input_features,
+        expected_enabled_dependencies,
+        expected_feature_requests,
+        case(vec!["serde"], vec!["serde"], vec![]),
+        case(vec!["derive"], vec![], vec![("serde", "derive")]),
+        case(vec!["full"], vec!["log"], vec![("log", "std")]),
+        case(vec!["std"], vec![], vec![])
+    )]
+    fn dependency_feature_requests_test(
+        input_features: Vec<&str>,
+        expected_enabled_dependencies: Vec<&str>,
+        expected_feature_requests: Vec<(&str, &str)>,
+    ) {
+        let package = package(
+            "lib",
+            "1.0.0",
+            json!({
+                "std": [],
+                "serde": ["dep:serde"],
+                "derive": ["serde?/derive"],
+                "full": ["std", "log/std"]
+            }),
+            vec![],
+        );
+
+        let (enabled_dependencies, feature_requests) =
+            dependency_feature_requests(&package, &string_set(&input_features));
+
+        assert_eq!(
+            enabled_dependencies,
+            expected_enabled_dependencies
+                .iter()
+                .map(|dependency| String::from(*dependency))
+                .collect::<HashSet<String>>()
+        );
+        assert_eq!(
+            feature_requests,
+            expected_feature_requests
+                .iter()
+                .map(|(dependency, feature)| (
+                    String::from(*dependency),
+                    String::from(*feature)
+                ))
+                .collect::<Vec<(String, String)>>()
+        );
+    }
+
+    #[rstest(
+        input_root_default_features,
+        input_uses_default_features,
+        expected_lib_features,
+        expected_optional_features,
+        case(vec![], true, vec!["default", "std"], vec![]),
+        case(vec![], false, vec![], vec![]),
+        case(vec!["weak"], false, vec![], vec![]),
+        case(
+            vec!["optional", "weak"],
+            false,
+            vec!["extra"],
+            vec!["default", "fast"]
+        ),
+        case(
+            vec!["optional"],
+            true,
+            vec!["default", "extra", "std"],
+            vec!["default"]
+        )
+    )]
+    fn resolve_features_test(
+        input_root_default_features: Vec<&str>,
+        input_uses_default_features: bool,
+        expected_lib_features: Vec<&str>,
+        expected_optional_features: Vec<&str>,
+    ) {
+        let packages = [
+            package(
+                "root",
+                "0.1.0",
+                json!({
+                    "default": input_root_default_features,
+                    "optional": ["dep:optional", "lib/extra"],
+                    "weak": ["optional?/fast"]
+                }),
+                vec![
+                    dependency("lib", false, input_uses_default_features),
+                    dependency("optional", true, true),
+                ],
+            ),
+            package(
+                "lib",
+                "1.0.0",
+                json!({
+                    "default": ["std"],
+                    "std": [],
+                    "extra": []
+                }),
+                vec![],
+            ),
+            package(
+                "optional",
+                "1.0.0",
+                json!({
+                    "default": [],
+                    "fast": []
+                }),
+                vec![],
+            ),
+        ];
+
+        let features =
+            resolve_features(&packages, &[vec![1, 2], vec![], vec![]], &[0]);
+
+        assert_eq!(features[1], string_set(&expected_lib_features));
+        assert_eq!(features[2], string_set(&expected_optional_features));
+    }
+
+    #[rstest(
+        input_kind,
+        input_target,
+        input_member_indices,
+        expected_lib_features,
+        case(DependencyKind::Build, None, vec![0], vec!["extra"]),
+        case(DependencyKind::Development, None, vec![0], vec!["extra"]),
+        case(DependencyKind::Normal, Some("cfg(windows)"), vec![0], vec!["extra"]),
+        case(
+            DependencyKind::Development,
+            None,
+            vec![0, 1],
+            vec!["default", "extra", "std"]
+        )
+    )]
+    fn resolve_features_differences_test(
+        input_kind: DependencyKind,
+        input_target: Option<&str>,
+        input_member_indices: Vec<usize>,
+        expected_lib_features: Vec<&str>,
+    ) {
+        // When building `root` for Linux, cargo's resolver 2 enables none of
+        // the features of `lib` in any of these cases, the last one having
+        // `lib` as a path dependency outside of the workspace
+        let mut extra_dependency = dependency("lib", false, false);
+        extra_dependency.kind = input_kind;
+        extra_dependency.target =
+            serde_json::from_value(json!(input_target)).unwrap();
+        extra_dependency.features = vec![String::from("extra")];
+        let packages = [
+            package(
+                "root",
+                "0.1.0",
+                json!({}),
+                vec![dependency("lib", false, false), extra_dependency],
+            ),
+            package(
+                "lib",
+                "1.0.0",
+                json!({
+                    "default": ["std"],
+                    "std": [],
+                    "extra": []
+                }),
+                vec![],
+            ),
+        ];
+
+        let features = resolve_features(
+            &packages,
+            &[vec![1], vec![]],
+            &input_member_indices,
+        );
+
+        assert_eq!(features[1], string_set(&expected_lib_features));
+    }
+
+    fn dependency(
+        name: &str,
+        optional: bool,
+        uses_default_features: bool,
+    ) -> Dependency {
+        serde_json::from_value(json!({
+            "name": name,
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "req": "^1.0.0",
+            "kind": null,
+            "rename": null,
+            "optional": optional,
+            "uses_default_features": uses_default_features,
+            "features": [],
+            "target": null,
+            "registry": null,
+            "path": null
+        }))
+        .unwrap()
+    }
+
+    fn package(
+        name: &str,
+        version: &str,
+        features: serde_json::Value,
+        dependencies: Vec<Dependency>,
+    ) -> Package {
+        serde_json::from_value(json!({
+            "name": name,
+            "version": version,
+            "id": format!("{} {}", name, version),
+            "dependencies": dependencies,
+            "targets": [],
+            "features": features,
+            "manifest_path": format!("/{}/Cargo.toml", name)
+        }))
+        .unwrap()
+    }
+
+    fn string_set(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|value| String::from(*value)).collect()
+    }
+
+    fn locked_package(
+        name: &str,
+        version: &str,
+        dependencies: &[&str],
+    ) -> LockedPackage {
+        LockedPackage {
+            name: String::from(name),
+            version: String::from(version),
+            source: Some(String::from(
+                "registry+https://github.com/rust-lang/crates.io-index",
+            )),
+            dependencies: dependencies
+                .iter()
+                .map(|d| String::from(*d))
+                .collect(),
+        }
+    }
+}
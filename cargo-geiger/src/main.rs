@@ -12,16 +12,43 @@ extern crate petgraph;
 extern crate strum;
 extern crate strum_macros;
 
-use cargo_geiger::args::{Args, HELP};
+use cargo_geiger::args::{
+    Args, BaselineArgs, DatasetArgs, DiffArgs, Subcommand, UpdateImpactArgs,
+    HELP,
+};
+use cargo_geiger::baseline::{
+    compare_with_baseline, read_baseline, write_baseline,
+    BASELINE_REGRESSION_EXIT_CODE,
+};
 use cargo_geiger::cli::{get_cargo_metadata, get_krates, get_workspace};
+use cargo_geiger::dataset::scan_crate_archives;
+use cargo_geiger::diff::{read_report_diff, report_diff_to_lines};
+use cargo_geiger::git_worktree::create_temporary_worktree;
 use cargo_geiger::graph::build_graph;
+use cargo_geiger::lockfile::{
+    get_lockfile_metadata, get_lockfile_metadata_in_workspace,
+};
 use cargo_geiger::mapping::{CargoMetadataParameters, QueryResolve};
+use cargo_geiger::policy::{
+    check_policy, find_policy, workspace_member_ids, Policy,
+};
 use cargo_geiger::readme::create_or_replace_section_in_readme;
-use cargo_geiger::scan::{scan, FoundWarningsError, ScanResult};
+use cargo_geiger::registry_crate::create_temporary_crate_root;
+use cargo_geiger::scan::{
+    scan, scan_feature_matrix, scan_report, scan_report_without_build,
+    scan_targets, scan_update_impact, scan_without_build,
+    update_impact_to_scan_result, FoundWarningsError, ScanResult,
+};
 
 use cargo::core::shell::Shell;
 use cargo::util::important_paths;
 use cargo::{CliError, CliResult, GlobalContext};
+use cargo_geiger_serde::SafetyReport;
+use geiger::IncludeTests;
+use krates::cm::Metadata;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
@@ -34,11 +61,132 @@ fn cli_result_main(args: &Args) -> CliResult {
         println!("{}", HELP);
         return Ok(());
     }
+    match &args.subcommand {
+        Some(Subcommand::Dataset(dataset_args)) => {
+            return run_dataset(args, dataset_args);
+        }
+        Some(Subcommand::Diff(diff_args)) => return run_diff(args, diff_args),
+        Some(Subcommand::UpdateImpact(update_impact_args)) => {
+            return run_update_impact(args, update_impact_args);
+        }
+        None => {}
+    }
+
+    if args.include_std
+        && (args.forbid_only
+            || args.features_args.feature_matrix
+            || args.target_args.is_multi_target())
+    {
+        eprintln!("--include-std can not be combined with --forbid-only, --feature-matrix or more than one --target");
+        return Err(CliError::code(1));
+    }
+
+    if args.requires_default_scan()
+        && (args.forbid_only
+            || args.features_args.feature_matrix
+            || args.target_args.is_multi_target())
+    {
+        eprintln!("--output-format {:?} can not be combined with --forbid-only, --feature-matrix or more than one --target", args.output_format);
+        return Err(CliError::code(1));
+    }
+
+    if args.check
+        && (args.forbid_only
+            || args.features_args.feature_matrix
+            || args.target_args.is_multi_target())
+    {
+        eprintln!("--check can not be combined with --forbid-only, --feature-matrix or more than one --target");
+        return Err(CliError::code(1));
+    }
+
+    if args.check && args.reports_policy_violations() {
+        eprintln!("--check can not be combined with --output-format {:?}, which reports the policy violations itself", args.output_format);
+        return Err(CliError::code(1));
+    }
+
+    if args.baseline_args.is_baseline()
+        && (args.check
+            || args.forbid_only
+            || args.features_args.feature_matrix
+            || args.target_args.is_multi_target())
+    {
+        eprintln!("--baseline and --write-baseline can not be combined with --check, --forbid-only, --feature-matrix or more than one --target");
+        return Err(CliError::code(1));
+    }
+
+    if args.baseline_args.baseline_path.is_some()
+        && args.baseline_args.write_baseline_path.is_some()
+    {
+        eprintln!("--baseline can not be combined with --write-baseline");
+        return Err(CliError::code(1));
+    }
+
+    if args.baseline_args.update_baseline
+        && args.baseline_args.baseline_path.is_none()
+    {
+        eprintln!("--update-baseline requires --baseline");
+        return Err(CliError::code(1));
+    }
+
+    if args.rev.is_some() && (args.krate.is_some() || args.lockfile.is_some()) {
+        eprintln!("--rev can not be combined with --crate or --lockfile");
+        return Err(CliError::code(1));
+    }
+
+    if args.rev.is_some() && args.readme_args.update_readme {
+        eprintln!("--rev can not be combined with --update-readme");
+        return Err(CliError::code(1));
+    }
 
     let mut gctx = GlobalContext::default()?;
     args.update_config(&mut gctx)?;
 
-    let cargo_metadata = get_cargo_metadata(args, &gctx)?;
+    // With `--crate`, the crate is scanned as the dependency of a temporary
+    // package, which is removed again when it goes out of scope.
+    let temporary_crate_root = args
+        .krate
+        .as_deref()
+        .map(|crate_spec| create_temporary_crate_root(crate_spec, &gctx))
+        .transpose()?;
+    let args = &temporary_crate_root.as_ref().map_or_else(
+        || args.clone(),
+        |temporary_crate_root| temporary_crate_root.scan_args(args),
+    );
+    if temporary_crate_root.is_some() {
+        args.update_config(&mut gctx)?;
+    }
+
+    // With `--rev`, the workspace is scanned as it was at a git revision,
+    // checked out into a temporary worktree, which is removed again when it
+    // goes out of scope.
+    let temporary_worktree = args
+        .rev
+        .as_deref()
+        .map(|rev| create_temporary_worktree(rev, args, &gctx))
+        .transpose()?;
+    let args = &temporary_worktree.as_ref().map_or_else(
+        || args.clone(),
+        |temporary_worktree| temporary_worktree.scan_args(args),
+    );
+
+    // With `--lockfile`, the packages are read from the local cache instead of
+    // being resolved by `cargo metadata`, and nothing is built.
+    let cargo_metadata = match &args.lockfile {
+        Some(lockfile_path) => {
+            if args.features_args.feature_matrix
+                || args.target_args.is_multi_target()
+            {
+                eprintln!("--lockfile can not be combined with --feature-matrix or more than one --target");
+                return Err(CliError::code(1));
+            }
+            get_lockfile_metadata(
+                lockfile_path,
+                args.package.as_deref(),
+                &gctx,
+            )?
+        }
+        None => get_cargo_metadata(args, &gctx)?,
+    };
     let krates = get_krates(&cargo_metadata)?;
 
     let cargo_metadata_parameters = CargoMetadataParameters {
@@ -46,7 +194,10 @@ fn cli_result_main(args: &Args) -> CliResult {
         krates: &krates,
     };
 
-    let workspace = get_workspace(&gctx, args.manifest_path.clone())?;
+    let workspace = match args.lockfile {
+        Some(_) => None,
+        None => Some(get_workspace(&gctx, args.manifest_path.clone())?),
+    };
 
     let cargo_metadata_root_package_id = if let Some(
         cargo_metadata_root_package,
@@ -65,36 +216,115 @@ fn cli_result_main(args: &Args) -> CliResult {
         return Err(CliError::code(1));
     };
 
-    let global_rustc = gctx.load_global_rustc(Some(&workspace))?;
-
-    let graph = build_graph(
-        args,
-        &cargo_metadata_parameters,
-        &global_rustc.host,
-        &global_rustc.path,
-        cargo_metadata_root_package_id.clone(),
-    )?;
+    let global_rustc = gctx.load_global_rustc(workspace.as_ref())?;
 
     let query_resolve_root_package_id = args.package.as_ref().map_or(
         cargo_metadata_root_package_id.clone(),
         |package_query| {
             krates
                 .query_resolve(package_query)
-                .map_or(cargo_metadata_root_package_id, |package_id| package_id)
+                .unwrap_or_else(|| cargo_metadata_root_package_id.clone())
         },
     );
 
+    if args.check || args.baseline_args.is_baseline() {
+        let policy = if args.check {
+            Some(find_policy(&cargo_metadata)?)
+        } else {
+            None
+        };
+        let graph = build_graph(
+            args,
+            &cargo_metadata_parameters,
+            &global_rustc.host,
+            &global_rustc.path,
+            cargo_metadata_root_package_id,
+        )?;
+        let report = match &workspace {
+            Some(workspace) => scan_report(
+                args,
+                &cargo_metadata_parameters,
+                &gctx,
+                &graph,
+                query_resolve_root_package_id,
+                workspace,
+            )?,
+            None => scan_report_without_build(
+                args,
+                &cargo_metadata_parameters,
+                &gctx,
+                &graph,
+                query_resolve_root_package_id,
+            )?,
+        };
+        return match policy {
+            Some(policy) => run_check(&policy, &report, &cargo_metadata),
+            None => run_baseline(&args.baseline_args, &report),
+        };
+    }
+
     let ScanResult {
         scan_output_lines,
         warning_count,
-    } = scan(
-        args,
-        &cargo_metadata_parameters,
-        &gctx,
-        &graph,
-        query_resolve_root_package_id,
-        &workspace,
-    )?;
+    } = match &workspace {
+        None => {
+            let graph = build_graph(
+                args,
+                &cargo_metadata_parameters,
+                &global_rustc.host,
+                &global_rustc.path,
+                cargo_metadata_root_package_id,
+            )?;
+
+            scan_without_build(
+                args,
+                &cargo_metadata_parameters,
+                &gctx,
+                &graph,
+                query_resolve_root_package_id,
+            )?
+        }
+        Some(workspace) if args.features_args.feature_matrix => {
+            scan_feature_matrix(
+                args,
+                &cargo_metadata_parameters,
+                &global_rustc.host,
+                &gctx,
+                &global_rustc.path,
+                cargo_metadata_root_package_id,
+                query_resolve_root_package_id,
+                workspace,
+            )?
+        }
+        Some(workspace) if args.target_args.is_multi_target() => scan_targets(
+            args,
+            &cargo_metadata_parameters,
+            &global_rustc.host,
+            &gctx,
+            &global_rustc.path,
+            cargo_metadata_root_package_id,
+            query_resolve_root_package_id,
+            workspace,
+        )?,
+        Some(workspace) => {
+            let graph = build_graph(
+                args,
+                &cargo_metadata_parameters,
+                &global_rustc.host,
+                &global_rustc.path,
+                cargo_metadata_root_package_id,
+            )?;
+
+            scan(
+                args,
+                &cargo_metadata_parameters,
+                &gctx,
+                &graph,
+                query_resolve_root_package_id,
+                workspace,
+            )?
+        }
+    };
 
     if args.readme_args.update_readme {
         create_or_replace_section_in_readme(
@@ -117,6 +347,137 @@ fn cli_result_main(args: &Args) -> CliResult {
     Ok(())
 }
 
+fn run_check(
+    policy: &Policy,
+    report: &SafetyReport,
+    cargo_metadata: &Metadata,
+) -> CliResult {
+    let violations =
+        check_policy(policy, report, &workspace_member_ids(cargo_metadata));
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    if violations.is_empty() {
+        println!("No policy violations found");
+        Ok(())
+    } else {
+        println!("{} policy violation(s) found", violations.len());
+        Err(CliError::code(1))
+    }
+}
+
+fn run_baseline(
+    baseline_args: &BaselineArgs,
+    report: &SafetyReport,
+) -> CliResult {
+    let baseline_path = match (
+        &baseline_args.baseline_path,
+        &baseline_args.write_baseline_path,
+    ) {
+        (Some(baseline_path), _) => baseline_path,
+        (None, Some(write_baseline_path)) => {
+            write_baseline(write_baseline_path, report)?;
+            println!("Wrote baseline to {}", write_baseline_path.display());
+            return Ok(());
+        }
+        (None, None) => return Ok(()),
+    };
+
+    let baseline = read_baseline(baseline_path)?;
+    let comparison = compare_with_baseline(&baseline, report);
+    for regression in &comparison.regressions {
+        println!("{}", regression);
+    }
+    if baseline_args.update_baseline && comparison.improved {
+        write_baseline(baseline_path, &comparison.ratcheted)?;
+        println!("Updated baseline {}", baseline_path.display());
+    }
+    if comparison.regressions.is_empty() {
+        println!("No unsafe usage grew compared with the baseline");
+        Ok(())
+    } else {
+        println!(
+            "{} regression(s) found compared with the baseline",
+            comparison.regressions.len()
+        );
+        Err(CliError::code(BASELINE_REGRESSION_EXIT_CODE))
+    }
+}
+
+fn run_dataset(args: &Args, dataset_args: &DatasetArgs) -> CliResult {
+    let include_tests = if args.include_tests {
+        IncludeTests::Yes
+    } else {
+        IncludeTests::No
+    };
+    let output: Box<dyn Write + Send> = match &dataset_args.output_path {
+        Some(output_path) => Box::new(BufWriter::new(
+            File::create(output_path)
+                .map_err(|e| CliError::new(anyhow::Error::new(e), 1))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    let summary =
+        scan_crate_archives(&dataset_args.crates_dir, include_tests, output)
+            .map_err(|e| CliError::new(anyhow::Error::new(e), 1))?;
+
+    eprintln!(
+        "Scanned {} crates ({} could not be read), {} of {} files failed to parse",
+        summary.crates,
+        summary.failed_crates,
+        summary.failed_files,
+        summary.files
+    );
+
+    Ok(())
+}
+
+fn run_diff(args: &Args, diff_args: &DiffArgs) -> CliResult {
+    let diff = read_report_diff(
+        &diff_args.old_report_path,
+        &diff_args.new_report_path,
+    )?;
+    for line in report_diff_to_lines(&diff, args.output_format) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn run_update_impact(
+    args: &Args,
+    update_impact_args: &UpdateImpactArgs,
+) -> CliResult {
+    let mut gctx = GlobalContext::default()?;
+    args.update_config(&mut gctx)?;
+
+    let lockfile_path = match &args.lockfile {
+        Some(lockfile_path) => lockfile_path.clone(),
+        None => get_workspace(&gctx, args.manifest_path.clone())?
+            .root()
+            .join("Cargo.lock"),
+    };
+    let new_metadata =
+        get_lockfile_metadata(&lockfile_path, args.package.as_deref(), &gctx)?;
+    // The path packages of the old lockfile are the ones of the current
+    // workspace, wherever the old lockfile is stored.
+    let old_metadata = get_lockfile_metadata_in_workspace(
+        &update_impact_args.old_lockfile_path,
+        Path::new(new_metadata.workspace_root.as_str()),
+        args.package.as_deref(),
+        &gctx,
+    )?;
+
+    let update_impact =
+        scan_update_impact(args, &old_metadata, &new_metadata, &gctx)?;
+    for line in update_impact_to_scan_result(&update_impact, args.output_format)
+        .scan_output_lines
+    {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
 fn main() {
     let args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
     if let Err(e) = cli_result_main(&args) {
@@ -6,8 +6,9 @@ use metadata::package_id::ToCargoMetadataPackage;
 
 use ::krates::Krates;
 use cargo::core::dependency::DepKind;
+use cargo::CargoResult;
 use krates::cm::Metadata;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::path::PathBuf;
 
@@ -39,6 +40,15 @@ pub trait DepsNotReplaced {
     ) -> Option<Vec<(CargoMetadataPackageId, HashSet<CargoMetadataDependency>)>>;
 }
 
+pub trait GetPatchedPackages {
+    /// Map every package which a dependency was resolved to from another
+    /// source than the one the dependency declares, as done by `[patch]` and
+    /// `[replace]`, to that dependency
+    fn get_patched_packages(
+        &self,
+    ) -> HashMap<CargoMetadataPackageId, CargoMetadataDependency>;
+}
+
 pub trait GetPackageIdInformation {
     fn get_package_id_licence<T: GetPackage>(
         &self,
@@ -101,14 +111,14 @@ pub trait ToCargoGeigerPackageId {
     fn to_cargo_geiger_package_id(
         &self,
         metadata: &Metadata,
-    ) -> Option<CargoGeigerSerdePackageId>;
+    ) -> CargoResult<CargoGeigerSerdePackageId>;
 }
 
 pub trait ToCargoGeigerSource {
     fn to_cargo_geiger_source(
         &self,
         metadata: &Metadata,
-    ) -> CargoGeigerSerdeSource;
+    ) -> CargoResult<CargoGeigerSerdeSource>;
 }
 
 pub trait ToCargoMetadataPackageId: GetDependencyInformation {
@@ -1,7 +1,10 @@
 use super::metadata::package_id::{GetPackageIdRepr, ToCargoMetadataPackage};
 use super::ToCargoGeigerSource;
 
+use anyhow::{anyhow, bail};
+use cargo::CargoResult;
 use krates::cm::Metadata;
+use krates::cm::Package as CargoMetadataPackage;
 use url::Url;
 
 use cargo_geiger_serde::Source as CargoGeigerSerdeSource;
@@ -11,51 +14,86 @@ impl ToCargoGeigerSource for CargoMetadataPackageId {
     fn to_cargo_geiger_source(
         &self,
         metadata: &Metadata,
-    ) -> CargoGeigerSerdeSource {
-        let package = self.to_cargo_metadata_package(metadata).unwrap();
+    ) -> CargoResult<CargoGeigerSerdeSource> {
+        let package =
+            self.to_cargo_metadata_package(metadata).ok_or_else(|| {
+                anyhow!("Failed to convert PackageId: {} to Package", self)
+            })?;
+
+        // With source replacement, cargo metadata still reports the source
+        // which was replaced, so packages vendored by `cargo vendor` are
+        // recognised by the checksum file it writes next to their manifests.
+        if let Some(vendor_dir) = vendored_package_directory_source(&package) {
+            return Ok(CargoGeigerSerdeSource::Directory(vendor_dir));
+        }
 
         match package.source {
             Some(source) => handle_source_repr(&source.repr),
-            None => handle_path_source(self),
+            None => Ok(handle_path_source(self)),
         }
     }
 }
 
-fn handle_source_repr(source_repr: &str) -> CargoGeigerSerdeSource {
-    let mut source_repr_vec = source_repr.split('+').collect::<Vec<&str>>();
+fn vendored_package_directory_source(
+    package: &CargoMetadataPackage,
+) -> Option<Url> {
+    package.source.as_ref()?;
+    let package_dir = package.manifest_path.parent()?;
+    if !package_dir.join(".cargo-checksum.json").exists() {
+        return None;
+    }
+    Url::from_directory_path(package_dir.parent()?).ok()
+}
+
+fn handle_source_repr(
+    source_repr: &str,
+) -> CargoResult<CargoGeigerSerdeSource> {
+    let (source_type, raw_url) = source_repr
+        .split_once('+')
+        .ok_or_else(|| anyhow!("Unrecognised source: {}", source_repr))?;
 
-    let source_type = source_repr_vec[0];
+    let url = Url::parse(raw_url).map_err(|error| {
+        anyhow!("Failed to parse source url: {}, {}", raw_url, error)
+    })?;
 
     match SourceType::from(source_type) {
         SourceType::Registry => {
-            CargoGeigerSerdeSource::Registry {
+            Ok(CargoGeigerSerdeSource::Registry {
                 // It looks like cargo metadata drops this information
                 name: String::from("crates.io"),
-                url: Url::parse(source_repr_vec.pop().unwrap()).unwrap(),
-            }
+                url,
+            })
         }
         SourceType::Git => {
-            let raw_git_representation = source_repr_vec.pop().unwrap();
-            let raw_git_url = Url::parse(raw_git_representation).unwrap();
             let git_url_without_query = format!(
                 "{}://{}{}",
-                raw_git_url.scheme(),
-                raw_git_url.host_str().unwrap(),
-                raw_git_url.path()
+                url.scheme(),
+                url.host_str().unwrap_or_default(),
+                url.path()
             );
-            let revision = raw_git_url
+            let revision = url
                 .query_pairs()
                 .find(|(query_key, _)| query_key == "rev")
                 .map(|(_, rev)| String::from(rev))
                 .unwrap_or_default();
 
-            CargoGeigerSerdeSource::Git {
-                url: Url::parse(&git_url_without_query).unwrap(),
+            Ok(CargoGeigerSerdeSource::Git {
+                url: Url::parse(&git_url_without_query).map_err(|error| {
+                    anyhow!(
+                        "Failed to parse source url: {}, {}",
+                        git_url_without_query,
+                        error
+                    )
+                })?,
                 rev: revision,
-            }
+            })
+        }
+        SourceType::Directory => Ok(CargoGeigerSerdeSource::Directory(url)),
+        SourceType::LocalRegistry => {
+            Ok(CargoGeigerSerdeSource::LocalRegistry(url))
         }
-        _ => {
-            panic!("Unrecognised source type: {}", source_type)
+        SourceType::Unrecognised => {
+            bail!("Unrecognised source type: {}", source_type)
         }
     }
 }
@@ -83,14 +121,18 @@ fn handle_path_source<T: GetPackageIdRepr>(
 enum SourceType {
     Registry,
     Git,
+    Directory,
+    LocalRegistry,
     Unrecognised,
 }
 
 impl SourceType {
     fn from(raw: &str) -> Self {
         match raw {
-            "registry" => SourceType::Registry,
+            "registry" | "sparse" => SourceType::Registry,
             "git" => SourceType::Git,
+            "directory" => SourceType::Directory,
+            "local-registry" => SourceType::LocalRegistry,
             _ => SourceType::Unrecognised,
         }
     }
@@ -126,6 +168,25 @@ mod geiger_tests {
                 url: Url::parse("https://github.com/rust-itertools/itertools.git").unwrap(),
                 rev: String::from("")
             }
+        ),
+        case(
+            "sparse+https://index.crates.io/",
+            CargoGeigerSerdeSource::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://index.crates.io/").unwrap()
+            }
+        ),
+        case(
+            "directory+file:///build/vendor",
+            CargoGeigerSerdeSource::Directory(
+                Url::parse("file:///build/vendor").unwrap()
+            )
+        ),
+        case(
+            "local-registry+file:///build/local-registry",
+            CargoGeigerSerdeSource::LocalRegistry(
+                Url::parse("file:///build/local-registry").unwrap()
+            )
         )
     )]
     fn handle_source_repr_test(
@@ -133,7 +194,17 @@ mod geiger_tests {
         expected_source: CargoGeigerSerdeSource,
     ) {
         let source = handle_source_repr(input_source_repr);
-        assert_eq!(source, expected_source);
+        assert_eq!(source.unwrap(), expected_source);
+    }
+
+    #[rstest(
+        input_source_repr,
+        case("unknown+https://example.com/"),
+        case("registry+not a url"),
+        case("no-separator")
+    )]
+    fn handle_source_repr_test_unrecognised(input_source_repr: &str) {
+        assert!(handle_source_repr(input_source_repr).is_err());
     }
 
     #[rstest]
@@ -3,16 +3,18 @@ pub mod package;
 pub mod package_id;
 
 use super::{
-    DepsNotReplaced, GetPackageIdInformation, MatchesIgnoringSource,
-    ToCargoGeigerPackageId, ToCargoMetadataPackageId,
+    DepsNotReplaced, GetPackageIdInformation, GetPatchedPackages,
+    MatchesIgnoringSource, ToCargoGeigerPackageId, ToCargoMetadataPackageId,
 };
 use package_id::ToCargoMetadataPackage;
 
 use crate::mapping::krates_mapping::GetPackage;
 use crate::mapping::{ToCargoGeigerDependencyKind, ToCargoGeigerSource};
 
+use anyhow::anyhow;
+use cargo::CargoResult;
 use krates::cm::Metadata;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::slice::Iter;
 
@@ -64,6 +66,58 @@ impl DepsNotReplaced for Metadata {
     }
 }
 
+impl GetPatchedPackages for Metadata {
+    fn get_patched_packages(
+        &self,
+    ) -> HashMap<CargoMetadataPackageId, CargoMetadataDependency> {
+        let mut patched_packages = HashMap::new();
+
+        for dependency in self.packages.iter().flat_map(|p| &p.dependencies) {
+            let dependency_source = match &dependency.source {
+                Some(source) => source,
+                None => continue,
+            };
+
+            let candidates = self
+                .packages
+                .iter()
+                .filter(|package| {
+                    package.name == dependency.name
+                        && dependency.req.matches(&package.version)
+                })
+                .collect::<Vec<&CargoMetadataPackage>>();
+
+            let resolved_from_declared_source =
+                candidates.iter().any(|package| {
+                    package.source.as_ref().is_some_and(|source| {
+                        is_same_source(dependency_source, &source.repr)
+                    })
+                });
+
+            if resolved_from_declared_source {
+                continue;
+            }
+
+            for package in candidates {
+                patched_packages
+                    .entry(package.id.clone())
+                    .or_insert_with(|| dependency.clone());
+            }
+        }
+
+        patched_packages
+    }
+}
+
+/// The source of a resolved git package also contains the precise commit,
+/// which is not part of the source declared by the dependency
+fn is_same_source(dependency_source: &str, package_source: &str) -> bool {
+    let package_source = package_source
+        .split_once('#')
+        .map_or(package_source, |(source, _)| source);
+    dependency_source == package_source
+}
+
 impl MatchesIgnoringSource for CargoMetadataDependency {
     fn matches_ignoring_source<
         T: GetPackage,
@@ -114,21 +168,16 @@ impl ToCargoGeigerPackageId for CargoMetadataPackageId {
     fn to_cargo_geiger_package_id(
         &self,
         metadata: &Metadata,
-    ) -> Option<cargo_geiger_serde::PackageId> {
-        match self.to_cargo_metadata_package(metadata) {
-            Some(package) => {
-                let metadata_source = self.to_cargo_geiger_source(metadata);
-                Some(cargo_geiger_serde::PackageId {
-                    name: package.name,
-                    version: package.version,
-                    source: metadata_source,
-                })
-            }
-            None => {
-                eprintln!("Failed to convert PackageId: {} to Package", self);
-                None
-            }
-        }
+    ) -> CargoResult<cargo_geiger_serde::PackageId> {
+        let package =
+            self.to_cargo_metadata_package(metadata).ok_or_else(|| {
+                anyhow!("Failed to convert PackageId: {} to Package", self)
+            })?;
+        Ok(cargo_geiger_serde::PackageId {
+            name: package.name,
+            version: package.version,
+            source: self.to_cargo_geiger_source(metadata)?,
+        })
     }
 }
 
@@ -170,6 +219,43 @@ mod metadata_tests {
     use rstest::*;
     use std::path::PathBuf;
 
+    #[rstest]
+    fn get_patched_packages_test() {
+        let (_, metadata) = construct_krates_and_metadata();
+        assert!(metadata.get_patched_packages().is_empty());
+    }
+
+    #[rstest(
+        input_dependency_source,
+        input_package_source,
+        expected_is_same_source,
+        case(
+            "registry+https://github.com/rust-lang/crates.io-index",
+            "registry+https://github.com/rust-lang/crates.io-index",
+            true
+        ),
+        case(
+            "git+https://github.com/seanmonstar/num_cpus",
+            "git+https://github.com/seanmonstar/num_cpus#1a2b3c4d",
+            true
+        ),
+        case(
+            "registry+https://github.com/rust-lang/crates.io-index",
+            "git+https://github.com/seanmonstar/num_cpus#1a2b3c4d",
+            false
+        )
+    )]
+    fn is_same_source_test(
+        input_dependency_source: &str,
+        input_package_source: &str,
+        expected_is_same_source: bool,
+    ) {
+        assert_eq!(
+            is_same_source(input_dependency_source, input_package_source),
+            expected_is_same_source
+        );
+    }
+
     #[rstest]
     fn deps_not_replaced_test() {
         let args = FeaturesArgs::default();
@@ -0,0 +1,299 @@
+use crate::mapping::ToCargoGeigerPackageId;
+
+use anyhow::anyhow;
+use cargo::CargoResult;
+use cargo_geiger_serde::{PackageId, SafetyReport};
+use krates::cm::Metadata;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The name of the policy file looked up in the workspace root
+pub const POLICY_FILE_NAME: &str = "geiger.toml";
+
+/// Rules the unsafe usage found by a scan is checked against with `--check`.
+///
+/// Crates are matched by name, so a rule applies to all versions of a crate.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Policy {
+    /// Crates which may contain unsafe code used by the build
+    pub allow_unsafe: Vec<String>,
+    /// Crates which must not contain unsafe code used by the build
+    pub deny_unsafe: Vec<String>,
+    /// Whether only the crates in `allow-unsafe` and the workspace members
+    /// may contain unsafe code used by the build
+    pub deny_unlisted_unsafe: bool,
+    /// Whether all workspace members must declare `#![forbid(unsafe_code)]`
+    pub members_forbid_unsafe: bool,
+    /// The most unsafe usage a crate may use in the build
+    pub budgets: BTreeMap<String, UnsafeBudget>,
+}
+
+/// The most unsafe items of each kind a crate may use in the build, with no
+/// limit for the kinds left out
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UnsafeBudget {
+    pub functions: Option<u64>,
+    pub exprs: Option<u64>,
+    pub item_impls: Option<u64>,
+    pub item_traits: Option<u64>,
+    pub methods: Option<u64>,
+}
+
+/// A package breaking a rule of the policy
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyViolation {
+    pub package: PackageId,
+    /// The rule which was broken, as written in the policy
+    pub rule: String,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} (rule: `{}`)",
+            self.package.name, self.package.version, self.message, self.rule
+        )
+    }
+}
+
+/// Read the policy from `geiger.toml` in the workspace root, or else from the
+/// `[workspace.metadata.geiger]` table of the workspace manifest
+pub fn find_policy(metadata: &Metadata) -> CargoResult<Policy> {
+    find_optional_policy(metadata)?.ok_or_else(|| {
+        anyhow!(
+            "no policy found, add a `{}` to `{}` or a \
+             `[workspace.metadata.geiger]` table to its manifest",
+            POLICY_FILE_NAME,
+            metadata.workspace_root
+        )
+    })
+}
+
+/// Like `find_policy`, but returns `None` if the workspace has no policy
+pub fn find_optional_policy(
+    metadata: &Metadata,
+) -> CargoResult<Option<Policy>> {
+    let policy_path =
+        Path::new(metadata.workspace_root.as_str()).join(POLICY_FILE_NAME);
+    if policy_path.exists() {
+        let policy = fs::read_to_string(&policy_path)?;
+        return toml::from_str(&policy).map(Some).map_err(|e| {
+            anyhow!("failed to parse `{}`: {}", policy_path.display(), e)
+        });
+    }
+    metadata
+        .workspace_metadata
+        .get("geiger")
+        .map(|policy| {
+            Policy::deserialize(policy).map_err(|e| {
+                anyhow!("failed to parse `[workspace.metadata.geiger]`: {}", e)
+            })
+        })
+        .transpose()
+}
+
+/// The ids of the workspace members, as found in the `SafetyReport`
+pub fn workspace_member_ids(metadata: &Metadata) -> HashSet<PackageId> {
+    metadata
+        .workspace_members
+        .iter()
+        .filter_map(|package_id| {
+            package_id.to_cargo_geiger_package_id(metadata).ok()
+        })
+        .collect()
+}
+
+/// Evaluate the rules of the policy against the scanned packages, returning
+/// the violations ordered by package
+pub fn check_policy(
+    policy: &Policy,
+    report: &SafetyReport,
+    workspace_member_ids: &HashSet<PackageId>,
+) -> Vec<PolicyViolation> {
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+
+    let mut violations = vec![];
+    for entry in entries {
+        let package_id = &entry.package.id;
+        let name = package_id.name.as_str();
+        let is_member = workspace_member_ids.contains(package_id);
+        let used = &entry.unsafety.used;
+        let mut violation = |rule: String, message: String| {
+            violations.push(PolicyViolation {
+                package: package_id.clone(),
+                rule,
+                message,
+            })
+        };
+
+        if is_member
+            && policy.members_forbid_unsafe
+            && !entry.unsafety.forbids_unsafe
+        {
+            violation(
+                String::from("members-forbid-unsafe"),
+                String::from(
+                    "workspace member does not declare \
+                     #![forbid(unsafe_code)]",
+                ),
+            );
+        }
+        if used.has_unsafe() {
+            if policy.deny_unsafe.iter().any(|denied| denied == name) {
+                violation(
+                    String::from("deny-unsafe"),
+                    String::from("unsafe code is used by the build"),
+                );
+            } else if policy.deny_unlisted_unsafe
+                && !is_member
+                && !policy.allow_unsafe.iter().any(|allowed| allowed == name)
+            {
+                violation(
+                    String::from("deny-unlisted-unsafe"),
+                    String::from(
+                        "unsafe code is used by the build, but the crate is \
+                         not listed in `allow-unsafe`",
+                    ),
+                );
+            }
+        }
+        if let Some(budget) = policy.budgets.get(name) {
+            let limits = [
+                ("functions", budget.functions, &used.functions),
+                ("exprs", budget.exprs, &used.exprs),
+                ("item-impls", budget.item_impls, &used.item_impls),
+                ("item-traits", budget.item_traits, &used.item_traits),
+                ("methods", budget.methods, &used.methods),
+            ];
+            for (kind, limit, count) in limits.iter() {
+                if let Some(limit) = limit {
+                    if count.unsafe_ > *limit {
+                        violation(
+                            format!("budgets.{}.{} = {}", name, kind, limit),
+                            format!(
+                                "{} unsafe {} are used by the build",
+                                count.unsafe_, kind
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry};
+    use rstest::*;
+
+    #[rstest]
+    fn policy_deserialize_test() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            allow-unsafe = ["libc"]
+            deny-unsafe = ["openssl"]
+            deny-unlisted-unsafe = true
+            members-forbid-unsafe = true
+
+            [budgets.hashbrown]
+            exprs = 50
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.allow_unsafe, vec![String::from("libc")]);
+        assert_eq!(policy.deny_unsafe, vec![String::from("openssl")]);
+        assert!(policy.deny_unlisted_unsafe);
+        assert!(policy.members_forbid_unsafe);
+        assert_eq!(policy.budgets["hashbrown"].exprs, Some(50));
+        assert_eq!(policy.budgets["hashbrown"].functions, None);
+
+        assert!(toml::from_str::<Policy>("allow = [\"libc\"]").is_err());
+    }
+
+    #[rstest]
+    fn check_policy_test() {
+        let policy = Policy {
+            allow_unsafe: vec![String::from("libc"), String::from("hashbrown")],
+            deny_unsafe: vec![String::from("openssl")],
+            deny_unlisted_unsafe: true,
+            members_forbid_unsafe: true,
+            budgets: vec![(
+                String::from("hashbrown"),
+                UnsafeBudget {
+                    exprs: Some(50),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let report = SafetyReport {
+            packages: vec![
+                report_entry(package_id("member", "1.0.0"), 0, false),
+                report_entry(package_id("libc", "1.0.0"), 100, false),
+                report_entry(package_id("hashbrown", "1.0.0"), 51, false),
+                report_entry(package_id("openssl", "1.0.0"), 1, false),
+                report_entry(package_id("itoa", "1.0.0"), 2, false),
+                report_entry(package_id("ref_slice", "1.0.0"), 0, true),
+            ]
+            .into_iter()
+            .map(|entry| (entry.package.id.clone(), entry))
+            .collect(),
+            ..Default::default()
+        };
+        let workspace_member_ids =
+            vec![package_id("member", "1.0.0")].into_iter().collect();
+
+        let violations = check_policy(&policy, &report, &workspace_member_ids)
+            .into_iter()
+            .map(|violation| {
+                (violation.package.name, violation.rule, violation.message)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    String::from("hashbrown"),
+                    String::from("budgets.hashbrown.exprs = 50"),
+                    String::from("51 unsafe exprs are used by the build")
+                ),
+                (
+                    String::from("itoa"),
+                    String::from("deny-unlisted-unsafe"),
+                    String::from(
+                        "unsafe code is used by the build, but the crate is \
+                         not listed in `allow-unsafe`"
+                    )
+                ),
+                (
+                    String::from("member"),
+                    String::from("members-forbid-unsafe"),
+                    String::from(
+                        "workspace member does not declare \
+                         #![forbid(unsafe_code)]"
+                    )
+                ),
+                (
+                    String::from("openssl"),
+                    String::from("deny-unsafe"),
+                    String::from("unsafe code is used by the build")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::args::Args;
+
+use anyhow::{anyhow, bail};
+use cargo::{CargoResult, GlobalContext};
+use krates::semver::Version;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Name of the package created to depend on the crate which is scanned
+const TEMPORARY_ROOT_PACKAGE_NAME: &str = "cargo-geiger-crate-root";
+
+/// A temporary package depending on a single crate from the registry, which
+/// lets the crate be scanned like a dependency of a project. The package is
+/// removed when this is dropped.
+pub struct TemporaryCrateRoot {
+    pub crate_name: String,
+    pub crate_version: Version,
+    dir: TempDir,
+}
+
+impl TemporaryCrateRoot {
+    pub fn manifest_path(&self) -> PathBuf {
+        self.dir.path().join("Cargo.toml")
+    }
+
+    /// The arguments to scan the crate with, so that the crate is the root of
+    /// the printed tree. The scan always runs offline, so a crate whose
+    /// dependencies are not all cached fails instead of downloading them.
+    pub fn scan_args(&self, args: &Args) -> Args {
+        let mut scan_args = args.clone();
+        scan_args.manifest_path = Some(self.manifest_path());
+        scan_args.offline = true;
+        scan_args.package =
+            Some(format!("{}@{}", self.crate_name, self.crate_version));
+        scan_args
+    }
+}
+
+/// Find the crate given with `--crate name@version` in the local registry
+/// cache and create a temporary package depending on exactly that version.
+pub fn create_temporary_crate_root(
+    crate_spec: &str,
+    gctx: &GlobalContext,
+) -> CargoResult<TemporaryCrateRoot> {
+    let (crate_name, crate_version) = parse_crate_spec(crate_spec)?;
+
+    let registry_dir = gctx.home().as_path_unlocked().join("registry");
+    if find_in_registry(&registry_dir, &crate_name, &crate_version).is_none() {
+        bail!(
+            "`{}@{}` was not found in the local registry cache at `{}`",
+            crate_name,
+            crate_version,
+            registry_dir.display()
+        );
+    }
+
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-geiger-crate-")
+        .tempdir()?;
+    fs::create_dir(dir.path().join("src"))?;
+    fs::write(dir.path().join("src").join("lib.rs"), "")?;
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        format!(
+            "[package]\n\
+             name = \"{}\"\n\
+             version = \"0.0.0\"\n\
+             edition = \"2018\"\n\
+             publish = false\n\
+             \n\
+             [dependencies]\n\
+             {} = \"={}\"\n\
+             \n\
+             [workspace]\n",
+            TEMPORARY_ROOT_PACKAGE_NAME, crate_name, crate_version
+        ),
+    )?;
+
+    Ok(TemporaryCrateRoot {
+        crate_name,
+        crate_version,
+        dir,
+    })
+}
+
+fn parse_crate_spec(crate_spec: &str) -> CargoResult<(String, Version)> {
+    let (name, version) = crate_spec.split_once('@').ok_or_else(|| {
+        anyhow!("expected `--crate <NAME@VERSION>`, got `{}`", crate_spec)
+    })?;
+    if name.is_empty() {
+        bail!("expected `--crate <NAME@VERSION>`, got `{}`", crate_spec);
+    }
+    let version = Version::parse(version).map_err(|error| {
+        anyhow!("invalid version in `--crate {}`: {}", crate_spec, error)
+    })?;
+    Ok((String::from(name), version))
+}
+
+/// Look for the unpacked sources of the crate in `registry/src`, or for the
+/// downloaded `.crate` file in `registry/cache`
+fn find_in_registry(
+    registry_dir: &Path,
+    crate_name: &str,
+    crate_version: &Version,
+) -> Option<PathBuf> {
+    let package_dir_name = format!("{}-{}", crate_name, crate_version);
+    let crate_file_name = format!("{}.crate", package_dir_name);
+
+    [("src", package_dir_name), ("cache", crate_file_name)]
+        .iter()
+        .flat_map(|(kind, file_name)| {
+            fs::read_dir(registry_dir.join(kind))
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .map(move |index_dir| index_dir.path().join(file_name))
+        })
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod registry_crate_tests {
+    use super::*;
+
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest(
+        input_crate_spec,
+        expected_result,
+        case("itoa@1.0.18", Some((String::from("itoa"), Version::new(1, 0, 18)))),
+        case("itoa", None),
+        case("@1.0.18", None),
+        case("itoa@1.0", None)
+    )]
+    fn parse_crate_spec_test(
+        input_crate_spec: &str,
+        expected_result: Option<(String, Version)>,
+    ) {
+        assert_eq!(parse_crate_spec(input_crate_spec).ok(), expected_result);
+    }
+
+    #[rstest(
+        input_crate_name,
+        input_crate_version,
+        expected_file_name,
+        case("itoa", "1.0.18", Some("itoa-1.0.18")),
+        case("ryu", "1.0.20", Some("ryu-1.0.20.crate")),
+        case("ryu", "1.0.19", None)
+    )]
+    fn find_in_registry_test(
+        input_crate_name: &str,
+        input_crate_version: &str,
+        expected_file_name: Option<&str>,
+    ) {
+        let registry_dir = tempdir().unwrap();
+        let index_dir = "index.crates.io-1949cf8c6b5b557f";
+        fs::create_dir_all(
+            registry_dir
+                .path()
+                .join("src")
+                .join(index_dir)
+                .join("itoa-1.0.18"),
+        )
+        .unwrap();
+        fs::create_dir_all(registry_dir.path().join("cache").join(index_dir))
+            .unwrap();
+        fs::write(
+            registry_dir
+                .path()
+                .join("cache")
+                .join(index_dir)
+                .join("ryu-1.0.20.crate"),
+            "",
+        )
+        .unwrap();
+
+        let found = find_in_registry(
+            registry_dir.path(),
+            input_crate_name,
+            &Version::parse(input_crate_version).unwrap(),
+        );
+
+        assert_eq!(
+            found.map(|path| {
+                path.file_name().unwrap().to_str().unwrap().to_string()
+            }),
+            expected_file_name.map(String::from)
+        );
+    }
+
+    #[rstest]
+    fn scan_args_test() {
+        let temporary_crate_root = TemporaryCrateRoot {
+            crate_name: String::from("itoa"),
+            crate_version: Version::new(1, 0, 18),
+            dir: tempdir().unwrap(),
+        };
+
+        let scan_args = temporary_crate_root.scan_args(&Args::default());
+
+        assert!(scan_args.offline);
+        assert_eq!(scan_args.package, Some(String::from("itoa@1.0.18")));
+        assert_eq!(
+            scan_args.manifest_path,
+            Some(temporary_crate_root.manifest_path())
+        );
+    }
+}
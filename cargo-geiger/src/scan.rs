@@ -1,7 +1,15 @@
 mod default;
+mod duplicates;
+mod feature_matrix;
 mod find;
 mod forbid;
+mod package_files;
+mod patched;
 mod rs_file;
+mod rust_std;
+mod targets;
+mod unsafe_sites;
+mod update_impact;
 
 use crate::args::Args;
 use crate::format::print_config::PrintConfig;
@@ -11,15 +19,33 @@ use crate::mapping::{
     ToCargoGeigerPackageId,
 };
 
+pub use feature_matrix::{
+    scan_feature_matrix, FeatureMatrix, FeatureMatrixEntry, FeatureSet,
+};
+pub use package_files::PackageFile;
+pub use patched::registry_index_dir_name;
 pub use rs_file::RsFileMetricsWrapper;
+pub use rust_std::{RustStd, RustStdPackage, RUST_STD_GROUP_NAME};
+pub use targets::{
+    compare_targets, scan_targets, target_comparison_to_scan_result,
+    TargetComparison, TargetPackageEntry, TargetUnsafety,
+};
+pub use unsafe_sites::UsedUnsafeSite;
+pub use update_impact::{
+    scan_update_impact, update_impact_to_scan_result, UpdateImpact,
+    UpdateImpactEntry,
+};
 
-use default::scan_unsafe;
+use default::{
+    find_scan_details, find_scan_details_without_build, scan_to_safety_report,
+    scan_unsafe, scan_unsafe_without_build,
+};
 use forbid::scan_forbid_unsafe;
 
 use cargo::core::Workspace;
-use cargo::{CliError, GlobalContext};
+use cargo::{CargoResult, CliError, GlobalContext};
 use cargo_geiger_serde::{
-    CounterBlock, DependencyKind, PackageInfo, UnsafeInfo,
+    CounterBlock, DependencyKind, PackageInfo, SafetyReport, UnsafeInfo,
 };
 use krates::cm::PackageId;
 use krates::NodeId;
@@ -112,6 +138,103 @@ pub fn scan(
     }
 }
 
+/// Scan for unsafe usage the same way as `scan`, but return the collected
+/// `SafetyReport` instead of formatted output lines.
+pub fn scan_report(
+    args: &Args,
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    gctx: &GlobalContext,
+    graph: &Graph,
+    root_package_id: PackageId,
+    workspace: &Workspace,
+) -> Result<SafetyReport, CliError> {
+    let print_config = PrintConfig::new(args)?;
+
+    let scan_parameters = ScanParameters {
+        args,
+        gctx,
+        print_config: &print_config,
+    };
+
+    let scan_details = find_scan_details(
+        cargo_metadata_parameters,
+        &scan_parameters,
+        workspace,
+    )?;
+    scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        &scan_parameters,
+        scan_details,
+    )
+}
+
+/// Scan for unsafe usage the same way as `scan_without_build`, but return the
+/// collected `SafetyReport` instead of formatted output lines.
+pub fn scan_report_without_build(
+    args: &Args,
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    gctx: &GlobalContext,
+    graph: &Graph,
+    root_package_id: PackageId,
+) -> Result<SafetyReport, CliError> {
+    let print_config = PrintConfig::new(args)?;
+
+    let scan_parameters = ScanParameters {
+        args,
+        gctx,
+        print_config: &print_config,
+    };
+
+    let scan_details = find_scan_details_without_build(
+        cargo_metadata_parameters,
+        &scan_parameters,
+    )?;
+    scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        &scan_parameters,
+        scan_details,
+    )
+}
+
+/// Scan for unsafe usage the same way as `scan`, but without building the
+/// root package, which leaves no way to tell the `.rs` files used by the
+/// build apart. Every scanned file is counted as used.
+pub fn scan_without_build(
+    args: &Args,
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    gctx: &GlobalContext,
+    graph: &Graph,
+    root_package_id: PackageId,
+) -> Result<ScanResult, CliError> {
+    let print_config = PrintConfig::new(args)?;
+
+    let scan_parameters = ScanParameters {
+        args,
+        gctx,
+        print_config: &print_config,
+    };
+
+    if args.forbid_only {
+        scan_forbid_unsafe(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &scan_parameters,
+        )
+    } else {
+        scan_unsafe_without_build(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &scan_parameters,
+        )
+    }
+}
+
 pub fn unsafe_stats(
     package_metrics: &PackageMetrics,
     rs_files_used: &HashSet<PathBuf>,
@@ -151,6 +274,8 @@ pub fn unsafe_stats(
 struct ScanDetails {
     rs_files_used: HashSet<PathBuf>,
     geiger_context: GeigerContext,
+    /// The standard library, scanned with `--include-std`
+    rust_std: Option<RustStd>,
 }
 
 fn construct_rs_files_used_lines(
@@ -197,7 +322,7 @@ fn package_metrics(
     geiger_context: &GeigerContext,
     graph: &Graph,
     root_package_id: PackageId,
-) -> Vec<(PackageInfo, Option<PackageMetrics>)> {
+) -> CargoResult<Vec<(PackageInfo, Option<PackageMetrics>)>> {
     let mut package_metrics =
         Vec::<(PackageInfo, Option<PackageMetrics>)>::new();
     let root_index = graph.nodes[&root_package_id];
@@ -207,44 +332,40 @@ fn package_metrics(
     while let Some(index) = indices.pop() {
         let package_id = graph.graph[index].clone();
 
-        if let Some(package) = package_id
-            .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
-        {
-            let mut package_info = PackageInfo::new(package);
-
-            for edge in graph.graph.edges(index) {
-                let dep_index = edge.target();
-
-                let dependency_kind_option =
-                    edge.weight().to_cargo_geiger_dependency_kind();
-
-                add_dependency_to_package_info(
-                    cargo_metadata_parameters,
-                    dep_index,
-                    dependency_kind_option,
-                    graph,
-                    &mut indices,
-                    &mut package_info,
-                    &mut visited,
-                );
-            }
+        let package = package_id
+            .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)?;
+        let mut package_info = PackageInfo::new(package);
+
+        for edge in graph.graph.edges(index) {
+            let dep_index = edge.target();
+
+            let dependency_kind_option =
+                edge.weight().to_cargo_geiger_dependency_kind();
+
+            add_dependency_to_package_info(
+                cargo_metadata_parameters,
+                dep_index,
+                dependency_kind_option,
+                graph,
+                &mut indices,
+                &mut package_info,
+                &mut visited,
+            )?;
+        }
 
-            match geiger_context.package_id_to_metrics.get(&package_id) {
-                Some(m) => {
-                    package_metrics.push((package_info, Some(m.clone())))
-                }
-                None => {
-                    eprintln!(
-                        "WARNING: No metrics found for package: {}",
-                        package_id
-                    );
-                    package_metrics.push((package_info, None))
-                }
+        match geiger_context.package_id_to_metrics.get(&package_id) {
+            Some(m) => package_metrics.push((package_info, Some(m.clone()))),
+            None => {
+                eprintln!(
+                    "WARNING: No metrics found for package: {}",
+                    package_id
+                );
+                package_metrics.push((package_info, None))
             }
         }
     }
 
-    package_metrics
+    Ok(package_metrics)
 }
 
 fn add_dependency_to_package_info(
@@ -255,31 +376,26 @@ fn add_dependency_to_package_info(
     indices: &mut Vec<NodeIndex>,
     package_info: &mut PackageInfo,
     visited: &mut HashSet<NodeId>,
-) {
+) -> CargoResult<()> {
     if visited.insert(dependency_index) {
         indices.push(dependency_index);
     }
 
-    let dependency_package_id_option = graph.graph[dependency_index]
-        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata);
+    let dependency_package_id = graph.graph[dependency_index]
+        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)?;
 
-    match (dependency_package_id_option, dependency_kind_option) {
-        (Some(dependency_package_id), Some(dependency_kind)) => {
+    match dependency_kind_option {
+        Some(dependency_kind) => {
             package_info.add_dependency(dependency_package_id, dependency_kind);
         }
-        (Some(dependency_package_id), None) => {
+        None => {
             eprintln!(
                 "Failed to add dependency for: {} {:?}",
                 dependency_package_id.name, dependency_package_id.version
             )
         }
-        _ => {
-            eprintln!(
-                "Error converting: {} to Cargo Geiger Package Id",
-                graph.graph[dependency_index]
-            )
-        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -355,7 +471,8 @@ mod scan_tests {
             &mut indices,
             &mut package_info,
             &mut visited,
-        );
+        )
+        .unwrap();
 
         assert_eq!(visited, vec![dependency_index].iter().cloned().collect());
         assert_eq!(
@@ -394,6 +511,7 @@ mod scan_tests {
                 RsFileMetricsWrapper {
                     metrics: Default::default(),
                     is_crate_entry_point: false,
+                    unsafe_sites: vec![],
                 },
             )],
             vec![
@@ -412,17 +530,20 @@ mod scan_tests {
                 RsFileMetricsWrapper {
                     metrics: Default::default(),
                     is_crate_entry_point: false,
+                    unsafe_sites: vec![],
                 }),
                 (
                 PathBuf::from("second/file/path.rs"),
                 RsFileMetricsWrapper {
                 metrics: Default::default(),
                 is_crate_entry_point: false,
+                unsafe_sites: vec![],
                 }),
                 (PathBuf::from("third/file/path.rs"),
                 RsFileMetricsWrapper {
                     metrics: Default::default(),
                     is_crate_entry_point: false,
+                    unsafe_sites: vec![],
                 }
             )],
             vec![
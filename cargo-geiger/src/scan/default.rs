@@ -1,12 +1,32 @@
 mod table;
 
-use crate::args::FeaturesArgs;
+use crate::args::{FeaturesArgs, TargetArgs};
+use crate::format::code_quality::unsafe_sites_to_code_quality;
+use crate::format::csv::{
+    package_files_to_csv, safety_report_to_csv, Separator,
+};
+use crate::format::cyclonedx::safety_report_to_cyclonedx;
+use crate::format::dependency_graph::{
+    safety_report_to_dot, safety_report_to_mermaid,
+};
+use crate::format::github_annotations::unsafe_sites_to_github_annotations;
+use crate::format::html::safety_report_to_html;
+use crate::format::junit::safety_report_to_junit;
+use crate::format::openmetrics::safety_report_to_openmetrics;
 use crate::format::print_config::OutputFormat;
+use crate::format::sarif::unsafe_sites_to_sarif;
+use crate::format::spdx::safety_report_to_spdx;
 use crate::graph::Graph;
-use crate::mapping::CargoMetadataParameters;
+use crate::mapping::{CargoMetadataParameters, ToCargoGeigerPackageId};
+use crate::policy::{check_policy, find_optional_policy, workspace_member_ids};
 use crate::scan::rs_file::resolve_rs_file_deps;
 
+use super::duplicates::find_duplicate_versions;
 use super::find::find_unsafe;
+use super::package_files::find_package_files;
+use super::patched::find_patched_packages;
+use super::rust_std::{find_rust_std, rust_std_report_entries};
+use super::unsafe_sites::find_used_unsafe_sites;
 use super::{
     list_files_used_but_not_scanned, package_metrics, unsafe_stats,
     ScanDetails, ScanMode, ScanParameters, ScanResult,
@@ -14,13 +34,16 @@ use super::{
 
 use table::scan_to_table;
 
-use cargo::core::compiler::CompileMode;
+use cargo::core::compiler::{CompileKind, CompileMode};
 use cargo::core::resolver::features::CliFeatures;
 use cargo::core::Workspace;
 use cargo::ops::CompileOptions;
+use cargo::util::CargoResult;
 use cargo::{CliError, GlobalContext};
 use cargo_geiger_serde::{ReportEntry, SafetyReport};
 use krates::cm::PackageId;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub fn scan_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
@@ -28,22 +51,126 @@ pub fn scan_unsafe(
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
+) -> Result<ScanResult, CliError> {
+    let scan_details = find_scan_details(
+        cargo_metadata_parameters,
+        scan_parameters,
+        workspace,
+    )?;
+    scan_details_to_scan_result(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )
+}
+
+/// Scan for unsafe usage without building the root package, counting every
+/// scanned `.rs` file as used by the build.
+pub fn scan_unsafe_without_build(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+) -> Result<ScanResult, CliError> {
+    let scan_details = find_scan_details_without_build(
+        cargo_metadata_parameters,
+        scan_parameters,
+    )?;
+    scan_details_to_scan_result(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )
+}
+
+/// Scan all packages for unsafe usage, counting every scanned `.rs` file as
+/// used by the build
+pub fn find_scan_details_without_build(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    scan_parameters: &ScanParameters,
+) -> Result<ScanDetails, CliError> {
+    let geiger_context = find_unsafe(
+        cargo_metadata_parameters,
+        scan_parameters.gctx,
+        ScanMode::Full,
+        scan_parameters.print_config,
+    )?;
+    let rs_files_used = geiger_context
+        .package_id_to_metrics
+        .values()
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.keys())
+        .cloned()
+        .collect();
+    Ok(ScanDetails {
+        rs_files_used,
+        geiger_context,
+        rust_std: find_rust_std(scan_parameters)?,
+    })
+}
+
+fn scan_details_to_scan_result(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: ScanDetails,
 ) -> Result<ScanResult, CliError> {
     match scan_parameters.args.output_format {
-        OutputFormat::Json => scan_to_report(
+        OutputFormat::CycloneDx
+        | OutputFormat::Dot
+        | OutputFormat::Json
+        | OutputFormat::Mermaid
+        | OutputFormat::OpenMetrics
+        | OutputFormat::Spdx => scan_to_report(
             cargo_metadata_parameters,
             graph,
             scan_parameters.args.output_format,
             root_package_id,
             scan_parameters,
-            workspace,
+            scan_details,
+        ),
+        OutputFormat::Csv | OutputFormat::Tsv => scan_to_csv(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            scan_parameters,
+            scan_details,
+        ),
+        OutputFormat::GitHubAnnotations => scan_to_github_annotations(
+            cargo_metadata_parameters,
+            graph,
+            scan_details,
+        ),
+        OutputFormat::GitLabCodeQuality => {
+            scan_to_code_quality(cargo_metadata_parameters, graph, scan_details)
+        }
+        OutputFormat::Html => scan_to_html(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            scan_parameters,
+            scan_details,
         ),
+        OutputFormat::JUnit => scan_to_junit(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            scan_parameters,
+            scan_details,
+        ),
+        OutputFormat::Sarif => {
+            scan_to_sarif(cargo_metadata_parameters, graph, scan_details)
+        }
         _ => scan_to_table(
             cargo_metadata_parameters,
             graph,
             root_package_id,
             scan_parameters,
-            workspace,
+            scan_details,
         ),
     }
 }
@@ -54,10 +181,11 @@ pub fn scan_unsafe(
 /// Tracker rust-secure-code/cargo-geiger/issues/226
 fn build_compile_options<'a>(
     args: &'a FeaturesArgs,
+    target_args: &'a TargetArgs,
     gctx: &'a GlobalContext,
-) -> CompileOptions {
+) -> CargoResult<CompileOptions> {
     let mut compile_options =
-        CompileOptions::new(gctx, CompileMode::Check { test: false }).unwrap();
+        CompileOptions::new(gctx, CompileMode::Check { test: false })?;
 
     let uses_default_features = !args.no_default_features;
 
@@ -65,8 +193,14 @@ fn build_compile_options<'a>(
         &args.features,
         args.all_features,
         uses_default_features,
-    )
-    .unwrap();
+    )?;
+
+    // The dependency graph is resolved for the requested target, so the build
+    // used to find the used .rs files has to be done for the same target.
+    if let Some(target) = target_args.target() {
+        compile_options.build_config.requested_kinds =
+            CompileKind::from_requested_targets(gctx, &[target.to_owned()])?;
+    }
 
     // TODO: Investigate if this is relevant to cargo-geiger.
     //let mut bins = Vec::new();
@@ -89,18 +223,22 @@ fn build_compile_options<'a>(
     //     );
     // }
 
-    compile_options
+    Ok(compile_options)
 }
 
-fn scan(
+/// Build the root package to find the `.rs` files used by the build, and scan
+/// all packages for unsafe usage
+pub fn find_scan_details(
     cargo_metadata_parameters: &CargoMetadataParameters,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> Result<ScanDetails, CliError> {
     let compile_options = build_compile_options(
         &scan_parameters.args.features_args,
+        &scan_parameters.args.target_args,
         scan_parameters.gctx,
-    );
+    )
+    .map_err(|e| CliError::new(e, 1))?;
 
     match resolve_rs_file_deps(&compile_options, workspace) {
         Ok(rs_files_used) => {
@@ -113,31 +251,34 @@ fn scan(
             Ok(ScanDetails {
                 rs_files_used,
                 geiger_context,
+                rust_std: find_rust_std(scan_parameters)?,
             })
         }
         Err(rs_resolve_error) => Err(CliError::new(rs_resolve_error.into(), 1)),
     }
 }
 
-fn scan_to_report(
+/// Scan the packages in the dependency graph and collect the results in a
+/// `SafetyReport`, without formatting them for output.
+pub fn scan_to_safety_report(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
-    output_format: OutputFormat,
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
-    workspace: &Workspace,
-) -> Result<ScanResult, CliError> {
+    scan_details: ScanDetails,
+) -> Result<SafetyReport, CliError> {
     let ScanDetails {
         rs_files_used,
         geiger_context,
-    } = scan(cargo_metadata_parameters, scan_parameters, workspace)?;
+        rust_std,
+    } = scan_details;
     let mut report = SafetyReport::default();
     for (package, package_metrics_option) in package_metrics(
         cargo_metadata_parameters,
         &geiger_context,
         graph,
         root_package_id,
-    ) {
+    )? {
         let package_metrics = match package_metrics_option {
             Some(m) => m,
             None => {
@@ -156,17 +297,261 @@ fn scan_to_report(
         list_files_used_but_not_scanned(&geiger_context, &rs_files_used)
             .into_iter()
             .collect();
-    let json_string = match output_format {
+    report.duplicate_versions = find_duplicate_versions(
+        report
+            .packages
+            .iter()
+            .map(|(id, entry)| (id, &entry.unsafety)),
+    );
+    report.patched_packages = find_patched_packages(
+        cargo_metadata_parameters,
+        &geiger_context,
+        scan_parameters.gctx,
+        graph,
+        scan_parameters.print_config.include_tests,
+    );
+    if let Some(rust_std) = rust_std {
+        report.rust_std = rust_std_report_entries(&rust_std);
+    }
+
+    Ok(report)
+}
+
+fn scan_to_report(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    output_format: OutputFormat,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let cargo_geiger_root_package_id = root_package_id
+        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+        .ok();
+    let report = scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )?;
+    let output = match output_format {
+        OutputFormat::CycloneDx => serde_json::to_string(
+            &safety_report_to_cyclonedx(
+                &report,
+                cargo_geiger_root_package_id.as_ref(),
+            ),
+        )
+        .unwrap(),
+        OutputFormat::Dot => safety_report_to_dot(
+            &report,
+            cargo_geiger_root_package_id.as_ref(),
+            scan_parameters.args.collapse_forbidding,
+        ),
         OutputFormat::Json => serde_json::to_string(&report).unwrap(),
-        _ => panic!("Only implemented for OutputFormat::Json"),
+        OutputFormat::Mermaid => safety_report_to_mermaid(
+            &report,
+            cargo_geiger_root_package_id.as_ref(),
+            scan_parameters.args.collapse_forbidding,
+        ),
+        OutputFormat::OpenMetrics => safety_report_to_openmetrics(&report),
+        OutputFormat::Spdx => serde_json::to_string(&safety_report_to_spdx(
+            &report,
+            cargo_geiger_root_package_id.as_ref(),
+            SystemTime::now(),
+        ))
+        .unwrap(),
+        _ => panic!(
+            "Only implemented for OutputFormat::CycloneDx, OutputFormat::Dot, OutputFormat::Json, OutputFormat::Mermaid, OutputFormat::OpenMetrics and OutputFormat::Spdx"
+        ),
     };
 
     Ok(ScanResult {
-        scan_output_lines: vec![json_string],
+        scan_output_lines: vec![output],
         warning_count: 0,
     })
 }
 
+fn scan_to_csv(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let separator = match scan_parameters.args.output_format {
+        OutputFormat::Tsv => Separator::Tab,
+        _ => Separator::Comma,
+    };
+    let package_files = if scan_parameters.args.per_file {
+        Some(find_package_files(
+            cargo_metadata_parameters,
+            &scan_details.geiger_context,
+            &scan_details.rs_files_used,
+        ))
+    } else {
+        None
+    };
+    let report = scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )?;
+    let output = match package_files {
+        Some(package_files) => {
+            package_files_to_csv(&report, &package_files, separator)
+        }
+        None => safety_report_to_csv(&report, separator),
+    };
+
+    Ok(ScanResult {
+        scan_output_lines: vec![output],
+        warning_count: 0,
+    })
+}
+
+fn scan_to_code_quality(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let unsafe_sites = find_used_unsafe_sites(
+        cargo_metadata_parameters,
+        &scan_details.geiger_context,
+        graph,
+        &scan_details.rs_files_used,
+    );
+    let issues = unsafe_sites_to_code_quality(
+        &unsafe_sites,
+        &canonical_workspace_root(cargo_metadata_parameters),
+    );
+
+    Ok(ScanResult {
+        scan_output_lines: vec![serde_json::to_string(&issues).unwrap()],
+        warning_count: 0,
+    })
+}
+
+fn scan_to_github_annotations(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let unsafe_sites = find_used_unsafe_sites(
+        cargo_metadata_parameters,
+        &scan_details.geiger_context,
+        graph,
+        &scan_details.rs_files_used,
+    );
+
+    Ok(ScanResult {
+        scan_output_lines: unsafe_sites_to_github_annotations(
+            &unsafe_sites,
+            &canonical_workspace_root(cargo_metadata_parameters),
+        ),
+        warning_count: 0,
+    })
+}
+
+fn scan_to_html(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let package_files = find_package_files(
+        cargo_metadata_parameters,
+        &scan_details.geiger_context,
+        &scan_details.rs_files_used,
+    );
+    let cargo_geiger_root_package_id = root_package_id
+        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+        .ok();
+    let report = scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )?;
+    let html = safety_report_to_html(
+        &report,
+        cargo_geiger_root_package_id.as_ref(),
+        &package_files,
+    );
+
+    Ok(ScanResult {
+        scan_output_lines: vec![html],
+        warning_count: 0,
+    })
+}
+
+fn scan_to_junit(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let metadata = cargo_metadata_parameters.metadata;
+    let policy =
+        find_optional_policy(metadata).map_err(|e| CliError::new(e, 1))?;
+    let report = scan_to_safety_report(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        scan_details,
+    )?;
+    let violations = policy
+        .map(|policy| {
+            check_policy(&policy, &report, &workspace_member_ids(metadata))
+        })
+        .unwrap_or_default();
+
+    Ok(ScanResult {
+        scan_output_lines: vec![safety_report_to_junit(&report, &violations)],
+        warning_count: 0,
+    })
+}
+
+fn scan_to_sarif(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    scan_details: ScanDetails,
+) -> Result<ScanResult, CliError> {
+    let unsafe_sites = find_used_unsafe_sites(
+        cargo_metadata_parameters,
+        &scan_details.geiger_context,
+        graph,
+        &scan_details.rs_files_used,
+    );
+    let sarif = unsafe_sites_to_sarif(
+        &unsafe_sites,
+        &canonical_workspace_root(cargo_metadata_parameters),
+    );
+
+    Ok(ScanResult {
+        scan_output_lines: vec![serde_json::to_string(&sarif).unwrap()],
+        warning_count: 0,
+    })
+}
+
+/// The scanned paths are canonicalized, so the workspace root has to be as
+/// well for them to be made relative to it.
+fn canonical_workspace_root(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+) -> PathBuf {
+    let workspace_root =
+        Path::new(cargo_metadata_parameters.metadata.workspace_root.as_str());
+    workspace_root
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_root.to_path_buf())
+}
+
 #[cfg(test)]
 mod default_tests {
     use super::*;
@@ -190,10 +575,13 @@ mod default_tests {
             all_features: rand::random(),
             features: input_features,
             no_default_features: rand::random(),
+            ..Default::default()
         };
 
         let gctx = GlobalContext::default().unwrap();
-        let compile_options = build_compile_options(&args, &gctx);
+        let compile_options =
+            build_compile_options(&args, &TargetArgs::default(), &gctx)
+                .unwrap();
         let expected_cli_features =
             CliFeatures::from_command_line(&args.features, false, false)
                 .unwrap();
@@ -211,4 +599,41 @@ mod default_tests {
             args.no_default_features
         );
     }
+
+    #[rstest(
+        input_targets,
+        expected_requested_kinds,
+        case(vec![], vec![CompileKind::Host]),
+        case(
+            vec![String::from("wasm32-unknown-unknown")],
+            vec![CompileKind::Target(
+                cargo::core::compiler::CompileTarget::new(
+                    "wasm32-unknown-unknown"
+                )
+                .unwrap()
+            )]
+        )
+    )]
+    fn build_compile_options_test_target(
+        input_targets: Vec<String>,
+        expected_requested_kinds: Vec<CompileKind>,
+    ) {
+        let target_args = TargetArgs {
+            all_targets: false,
+            targets: input_targets,
+        };
+
+        let gctx = GlobalContext::default().unwrap();
+        let compile_options = build_compile_options(
+            &FeaturesArgs::default(),
+            &target_args,
+            &gctx,
+        )
+        .unwrap();
+
+        assert_eq!(
+            compile_options.build_config.requested_kinds,
+            expected_requested_kinds
+        );
+    }
 }
@@ -9,13 +9,18 @@ use crate::graph::Graph;
 use crate::mapping::CargoMetadataParameters;
 use crate::tree::traversal::walk_dependency_tree;
 
+use super::super::duplicates::{
+    construct_duplicate_versions_lines, find_duplicate_versions_in_graph,
+};
+use super::super::patched::{
+    construct_patched_packages_lines, find_patched_packages,
+    patched_package_ids,
+};
 use super::super::{
     construct_rs_files_used_lines, list_files_used_but_not_scanned,
     ScanDetails, ScanParameters, ScanResult,
 };
-use super::scan;
 
-use cargo::core::Workspace;
 use cargo::CliError;
 use colored::Colorize;
 use krates::cm::PackageId;
@@ -25,14 +30,15 @@ pub fn scan_to_table(
     graph: &Graph,
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
-    workspace: &Workspace,
+    scan_details: ScanDetails,
 ) -> Result<ScanResult, CliError> {
     let mut combined_scan_output_lines = Vec::<String>::new();
 
     let ScanDetails {
         rs_files_used,
         geiger_context,
-    } = scan(cargo_metadata_parameters, scan_parameters, workspace)?;
+        rust_std,
+    } = scan_details;
 
     if scan_parameters.args.verbosity != Verbosity::Quiet {
         let mut rs_files_used_lines =
@@ -54,10 +60,14 @@ pub fn scan_to_table(
         scan_parameters.print_config,
         root_package_id,
     );
+    let patched_package_ids = patched_package_ids(cargo_metadata_parameters);
     let table_parameters = TableParameters {
         geiger_context: &geiger_context,
+        include_std_in_totals: scan_parameters.args.include_std_in_totals,
+        patched_package_ids: &patched_package_ids,
         print_config: scan_parameters.print_config,
         rs_files_used: &rs_files_used,
+        rust_std: rust_std.as_ref(),
     };
 
     let ScanResult {
@@ -70,6 +80,29 @@ pub fn scan_to_table(
     );
     combined_scan_output_lines.append(&mut scan_output_lines);
 
+    let duplicate_versions = find_duplicate_versions_in_graph(
+        cargo_metadata_parameters,
+        &geiger_context,
+        graph,
+        &rs_files_used,
+    );
+    combined_scan_output_lines.append(&mut construct_duplicate_versions_lines(
+        &duplicate_versions,
+        scan_parameters.print_config.output_format,
+    ));
+
+    let patched_packages = find_patched_packages(
+        cargo_metadata_parameters,
+        &geiger_context,
+        scan_parameters.gctx,
+        graph,
+        scan_parameters.print_config.include_tests,
+    );
+    combined_scan_output_lines.append(&mut construct_patched_packages_lines(
+        &patched_packages,
+        scan_parameters.print_config.output_format,
+    ));
+
     let used_but_not_scanned =
         list_files_used_but_not_scanned(&geiger_context, &rs_files_used);
     warning_count += used_but_not_scanned.len() as u64;
@@ -0,0 +1,228 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::print_config::{colorize, OutputFormat};
+use crate::format::table::{table_row, UNSAFE_COUNTERS_HEADER};
+use crate::format::{CrateDetectionStatus, SymbolKind};
+use crate::graph::Graph;
+use crate::mapping::{CargoMetadataParameters, ToCargoGeigerPackageId};
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo_geiger_serde::{
+    DuplicateVersionEntry, DuplicateVersions, PackageId, UnsafeInfo,
+};
+use colored::Colorize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+/// Group the packages by crate name and return every crate which is found in
+/// more than one version, ordered by name.
+pub fn find_duplicate_versions<'a>(
+    packages: impl IntoIterator<Item = (&'a PackageId, &'a UnsafeInfo)>,
+) -> Vec<DuplicateVersions> {
+    let mut name_to_versions =
+        BTreeMap::<String, Vec<DuplicateVersionEntry>>::new();
+    for (id, unsafety) in packages {
+        name_to_versions.entry(id.name.clone()).or_default().push(
+            DuplicateVersionEntry {
+                id: id.clone(),
+                unsafety: unsafety.clone(),
+            },
+        );
+    }
+
+    name_to_versions
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, mut versions)| {
+            versions.sort_by(|a, b| a.id.cmp(&b.id));
+            let older_has_more_unsafe =
+                versions.iter().enumerate().any(|(index, older)| {
+                    versions[index + 1..].iter().any(|newer| {
+                        older.unsafety.used.unsafe_total()
+                            > newer.unsafety.used.unsafe_total()
+                    })
+                });
+            DuplicateVersions {
+                name,
+                versions,
+                older_has_more_unsafe,
+            }
+        })
+        .collect()
+}
+
+/// Find the duplicate crate versions among the packages of the dependency
+/// graph which have metrics
+pub fn find_duplicate_versions_in_graph(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<DuplicateVersions> {
+    let packages = graph
+        .nodes
+        .keys()
+        .filter_map(|package_id| {
+            let package_metrics =
+                geiger_context.package_id_to_metrics.get(package_id)?;
+            let id = package_id
+                .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+                .ok()?;
+            Some((id, unsafe_stats(package_metrics, rs_files_used)))
+        })
+        .collect::<Vec<(PackageId, UnsafeInfo)>>();
+
+    find_duplicate_versions(
+        packages.iter().map(|(id, unsafety)| (id, unsafety)),
+    )
+}
+
+pub fn construct_duplicate_versions_lines(
+    duplicate_versions: &[DuplicateVersions],
+    output_format: OutputFormat,
+) -> Vec<String> {
+    if duplicate_versions.is_empty() {
+        return vec![];
+    }
+
+    let emoji_symbols = EmojiSymbols::new(output_format);
+    let mut lines = vec![
+        String::new(),
+        format!(
+            "Crates found in more than one version ({}):",
+            duplicate_versions.len()
+        ),
+    ];
+
+    for duplicate in duplicate_versions {
+        lines.push(String::new());
+        if duplicate.older_has_more_unsafe {
+            lines.push(format!(
+                "{} {}",
+                duplicate.name,
+                "(an older version uses more unsafe code than a newer one)"
+                    .yellow()
+            ));
+        } else {
+            lines.push(duplicate.name.clone());
+        }
+        lines.push(
+            UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                .join(" ")
+                + "    Version",
+        );
+
+        for entry in &duplicate.versions {
+            let status = CrateDetectionStatus::from(&entry.unsafety);
+            let icon = match status {
+                CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+                    emoji_symbols.emoji(SymbolKind::Lock)
+                }
+                CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+                    emoji_symbols.emoji(SymbolKind::QuestionMark)
+                }
+                CrateDetectionStatus::UnsafeDetected => {
+                    emoji_symbols.emoji(SymbolKind::Rads)
+                }
+            };
+            lines.push(format!(
+                "{}  {: <2} {}",
+                colorize(
+                    &status,
+                    output_format,
+                    table_row(
+                        &entry.unsafety.used,
+                        &entry.unsafety.unused,
+                        output_format
+                    )
+                ),
+                icon,
+                colorize(&status, output_format, entry.id.version.to_string())
+            ));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod duplicates_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry};
+    use rstest::*;
+
+    #[rstest(
+        input_unsafe_exprs,
+        expected_older_has_more_unsafe,
+        case([5, 2], true),
+        case([2, 5], false),
+        case([3, 3], false)
+    )]
+    fn find_duplicate_versions_test(
+        input_unsafe_exprs: [u64; 2],
+        expected_older_has_more_unsafe: bool,
+    ) {
+        let entries = [
+            report_entry(package_id("rand", "8.0.0"), 0, false),
+            report_entry(
+                package_id("syn", "2.0.0"),
+                input_unsafe_exprs[1],
+                false,
+            ),
+            report_entry(
+                package_id("syn", "1.0.0"),
+                input_unsafe_exprs[0],
+                false,
+            ),
+        ];
+
+        let duplicate_versions = find_duplicate_versions(
+            entries
+                .iter()
+                .map(|entry| (&entry.package.id, &entry.unsafety)),
+        );
+
+        assert_eq!(duplicate_versions.len(), 1);
+        assert_eq!(duplicate_versions[0].name, "syn");
+        assert_eq!(
+            duplicate_versions[0]
+                .versions
+                .iter()
+                .map(|entry| entry.id.version.major)
+                .collect::<Vec<u64>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            duplicate_versions[0].older_has_more_unsafe,
+            expected_older_has_more_unsafe
+        );
+    }
+
+    #[rstest]
+    fn construct_duplicate_versions_lines_test() {
+        assert!(construct_duplicate_versions_lines(&[], OutputFormat::Ascii)
+            .is_empty());
+
+        let entries = [
+            report_entry(package_id("syn", "1.0.0"), 1, false),
+            report_entry(package_id("syn", "2.0.0"), 0, false),
+        ];
+        let duplicate_versions = find_duplicate_versions(
+            entries
+                .iter()
+                .map(|entry| (&entry.package.id, &entry.unsafety)),
+        );
+
+        let lines = construct_duplicate_versions_lines(
+            &duplicate_versions,
+            OutputFormat::Ascii,
+        );
+
+        assert_eq!(lines[1], "Crates found in more than one version (1):");
+        assert!(lines[3].starts_with("syn "));
+        assert!(lines[3].contains("an older version uses more unsafe code"));
+        assert!(lines[5].contains("1.0.0"));
+        assert!(lines[6].contains("2.0.0"));
+    }
+}
@@ -0,0 +1,375 @@
+use crate::args::{Args, FeaturesArgs};
+use crate::cli::{get_cargo_metadata, get_krates};
+use crate::format::print_config::OutputFormat;
+use crate::format::table::{table_row_delta, UNSAFE_COUNTERS_HEADER};
+use crate::graph::build_graph;
+use crate::mapping::CargoMetadataParameters;
+
+use super::{scan_report, ScanResult};
+
+use cargo::core::Workspace;
+use cargo::{CliError, GlobalContext};
+use cargo_geiger_serde::{
+    CounterBlock, CounterBlockDelta, PackageId, SafetyReport,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// A set of features the root package is scanned with
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct FeatureSet {
+    pub no_default_features: bool,
+    pub features: Vec<String>,
+}
+
+impl FeatureSet {
+    pub fn label(&self) -> String {
+        match (self.no_default_features, self.features.is_empty()) {
+            (true, true) => String::from("no-default-features"),
+            (true, false) => {
+                format!("no-default-features + {}", self.features.join(" + "))
+            }
+            (false, true) => String::from("default"),
+            (false, false) => {
+                format!("default + {}", self.features.join(" + "))
+            }
+        }
+    }
+
+    fn to_features_args(&self) -> FeaturesArgs {
+        FeaturesArgs {
+            features: self.features.clone(),
+            no_default_features: self.no_default_features,
+            ..Default::default()
+        }
+    }
+}
+
+/// How the used unsafe code changes for every scanned feature set, compared
+/// with the default features.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub struct FeatureMatrix {
+    /// Used unsafe code found when scanning with the default features
+    pub baseline: CounterBlock,
+    pub entries: Vec<FeatureMatrixEntry>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct FeatureMatrixEntry {
+    pub feature_set: FeatureSet,
+    /// Change of the used unsafe code summed over all packages
+    pub total: CounterBlockDelta,
+    /// Change of the used unsafe code of every package where it changed,
+    /// including packages which are only pulled in by one of the two feature
+    /// sets
+    pub packages: BTreeMap<PackageId, CounterBlockDelta>,
+}
+
+/// Scan the root package once for every feature set of the matrix and
+/// compare each result with the scan using the default features.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_feature_matrix(
+    args: &Args,
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    config_host: &str,
+    gctx: &GlobalContext,
+    global_rustc_path: &PathBuf,
+    graph_root_package_id: krates::cm::PackageId,
+    root_package_id: krates::cm::PackageId,
+    workspace: &Workspace,
+) -> Result<ScanResult, CliError> {
+    let declared_features = cargo_metadata_parameters
+        .metadata
+        .packages
+        .iter()
+        .find(|package| package.id == root_package_id)
+        .map(|package| {
+            package
+                .features
+                .keys()
+                .filter(|feature| feature.as_str() != "default")
+                .cloned()
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    let mut feature_set_reports = Vec::new();
+
+    for feature_set in construct_feature_sets(
+        &declared_features,
+        args.features_args.feature_matrix_powerset,
+    ) {
+        let mut feature_set_args = args.clone();
+        feature_set_args.features_args = feature_set.to_features_args();
+
+        let cargo_metadata = get_cargo_metadata(&feature_set_args, gctx)?;
+        let krates = get_krates(&cargo_metadata)?;
+        let feature_set_cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &cargo_metadata,
+            krates: &krates,
+        };
+
+        let graph = build_graph(
+            &feature_set_args,
+            &feature_set_cargo_metadata_parameters,
+            config_host,
+            global_rustc_path,
+            graph_root_package_id.clone(),
+        )?;
+
+        let report = scan_report(
+            &feature_set_args,
+            &feature_set_cargo_metadata_parameters,
+            gctx,
+            &graph,
+            root_package_id.clone(),
+            workspace,
+        )?;
+
+        feature_set_reports.push((feature_set, report));
+    }
+
+    Ok(feature_matrix_to_scan_result(
+        &compare_feature_sets(feature_set_reports),
+        args.output_format,
+    ))
+}
+
+/// The first feature set returned is the default one, which the others are
+/// compared with.
+fn construct_feature_sets(
+    declared_features: &[String],
+    powerset_max_size: Option<usize>,
+) -> Vec<FeatureSet> {
+    let mut feature_sets = vec![
+        FeatureSet::default(),
+        FeatureSet {
+            no_default_features: true,
+            features: vec![],
+        },
+    ];
+
+    let max_size = powerset_max_size.unwrap_or(1).max(1);
+    for size in 1..=max_size.min(declared_features.len()) {
+        for features in combinations(declared_features, size) {
+            feature_sets.push(FeatureSet {
+                no_default_features: false,
+                features,
+            });
+        }
+    }
+
+    feature_sets
+}
+
+fn combinations(items: &[String], size: usize) -> Vec<Vec<String>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        for mut rest in combinations(&items[index + 1..], size - 1) {
+            rest.insert(0, item.clone());
+            result.push(rest);
+        }
+    }
+    result
+}
+
+fn compare_feature_sets(
+    feature_set_reports: Vec<(FeatureSet, SafetyReport)>,
+) -> FeatureMatrix {
+    let mut feature_set_reports = feature_set_reports.into_iter();
+    let baseline_report = match feature_set_reports.next() {
+        Some((_, report)) => report,
+        None => return FeatureMatrix::default(),
+    };
+
+    let mut matrix = FeatureMatrix {
+        baseline: used_total(&baseline_report),
+        entries: vec![],
+    };
+
+    for (feature_set, report) in feature_set_reports {
+        let package_ids = baseline_report
+            .packages
+            .keys()
+            .chain(report.packages.keys())
+            .collect::<BTreeSet<&PackageId>>();
+
+        let mut total = CounterBlockDelta::default();
+        let mut packages = BTreeMap::new();
+        for package_id in package_ids {
+            let delta = CounterBlockDelta::new(
+                &used_counters(&baseline_report, package_id),
+                &used_counters(&report, package_id),
+            );
+            if delta.has_unsafe_changes() {
+                total += delta.clone();
+                packages.insert(package_id.clone(), delta);
+            }
+        }
+
+        matrix.entries.push(FeatureMatrixEntry {
+            feature_set,
+            total,
+            packages,
+        });
+    }
+
+    matrix
+}
+
+fn used_counters(
+    report: &SafetyReport,
+    package_id: &PackageId,
+) -> CounterBlock {
+    report
+        .packages
+        .get(package_id)
+        .map(|entry| entry.unsafety.used.clone())
+        .unwrap_or_default()
+}
+
+fn used_total(report: &SafetyReport) -> CounterBlock {
+    report
+        .packages
+        .values()
+        .fold(CounterBlock::default(), |total, entry| {
+            total + entry.unsafety.used.clone()
+        })
+}
+
+fn feature_matrix_to_scan_result(
+    matrix: &FeatureMatrix,
+    output_format: OutputFormat,
+) -> ScanResult {
+    let scan_output_lines = match output_format {
+        OutputFormat::Json => vec![serde_json::to_string(matrix).unwrap()],
+        _ => {
+            let mut lines = vec![
+                String::new(),
+                String::from(
+                    "Change of used unsafe code compared with the default features:",
+                ),
+                String::new(),
+                format!(
+                    "{}Features",
+                    UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                        .join(" ")
+                ),
+            ];
+            for entry in &matrix.entries {
+                lines.push(format!(
+                    "{} {}",
+                    table_row_delta(&entry.total),
+                    entry.feature_set.label()
+                ));
+                for (package_id, delta) in &entry.packages {
+                    lines.push(format!(
+                        "{}     {} {}",
+                        table_row_delta(delta),
+                        package_id.name,
+                        package_id.version
+                    ));
+                }
+            }
+            lines.push(String::new());
+            lines
+        }
+    };
+
+    ScanResult {
+        scan_output_lines,
+        warning_count: 0,
+    }
+}
+
+#[cfg(test)]
+mod feature_matrix_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+
+    #[rstest(
+        input_powerset_max_size,
+        expected_labels,
+        case(
+            None,
+            vec![
+                "default",
+                "no-default-features",
+                "default + a",
+                "default + b",
+                "default + c"
+            ]
+        ),
+        case(
+            Some(2),
+            vec![
+                "default",
+                "no-default-features",
+                "default + a",
+                "default + b",
+                "default + c",
+                "default + a + b",
+                "default + a + c",
+                "default + b + c"
+            ]
+        )
+    )]
+    fn construct_feature_sets_test(
+        input_powerset_max_size: Option<usize>,
+        expected_labels: Vec<&str>,
+    ) {
+        let declared_features =
+            vec![String::from("a"), String::from("b"), String::from("c")];
+
+        let labels =
+            construct_feature_sets(&declared_features, input_powerset_max_size)
+                .iter()
+                .map(FeatureSet::label)
+                .collect::<Vec<String>>();
+
+        assert_eq!(labels, expected_labels);
+    }
+
+    #[rstest]
+    fn compare_feature_sets_reports_changed_packages_only() {
+        let baseline = safety_report(vec![
+            report_entry(package_id("unchanged", "1.0.0"), 1, false),
+            report_entry(package_id("grows", "1.0.0"), 2, false),
+        ]);
+        let with_feature = safety_report(vec![
+            report_entry(package_id("unchanged", "1.0.0"), 1, false),
+            report_entry(package_id("grows", "1.0.0"), 5, false),
+            report_entry(package_id("optional_dependency", "1.0.0"), 7, false),
+        ]);
+        let feature_set = FeatureSet {
+            no_default_features: false,
+            features: vec![String::from("extra")],
+        };
+
+        let matrix = compare_feature_sets(vec![
+            (FeatureSet::default(), baseline),
+            (feature_set.clone(), with_feature),
+        ]);
+
+        assert_eq!(matrix.baseline.exprs.unsafe_, 3);
+        assert_eq!(matrix.entries.len(), 1);
+
+        let entry = &matrix.entries[0];
+        assert_eq!(entry.feature_set, feature_set);
+        assert_eq!(entry.total.exprs.unsafe_, 10);
+        assert_eq!(
+            entry
+                .packages
+                .iter()
+                .map(|(id, delta)| (id.name.as_str(), delta.exprs.unsafe_))
+                .collect::<Vec<(&str, i64)>>(),
+            vec![("grows", 3), ("optional_dependency", 7)]
+        );
+    }
+}
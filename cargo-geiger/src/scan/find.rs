@@ -9,9 +9,9 @@ use crate::scan::PackageMetrics;
 use super::{GeigerContext, ScanMode};
 
 use cargo::{CargoResult, CliError, GlobalContext};
-use geiger::find::find_unsafe_in_file;
-use geiger::{IncludeTests, RsFileMetrics, ScanFileError};
-use krates::cm::PackageId;
+use geiger::find::{find_unsafe_and_sites_in_file, find_unsafe_in_file};
+use geiger::{IncludeTests, RsFileMetrics, ScanFileError, UnsafeSite};
+use krates::cm::{Package, PackageId};
 use rayon::{in_place_scope, prelude::*};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -26,12 +26,29 @@ pub fn find_unsafe(
     gctx: &GlobalContext,
     mode: ScanMode,
     print_config: &PrintConfig,
+) -> Result<GeigerContext, CliError> {
+    find_unsafe_in_package_list(
+        &cargo_metadata_parameters.metadata.packages,
+        gctx,
+        mode,
+        print_config,
+    )
+}
+
+/// Scan the given packages for unsafe usage, whether or not they are part of
+/// the `cargo metadata`
+pub fn find_unsafe_in_package_list(
+    packages: &[Package],
+    gctx: &GlobalContext,
+    mode: ScanMode,
+    print_config: &PrintConfig,
 ) -> Result<GeigerContext, CliError> {
     let mut progress = cargo::util::Progress::new("Scanning", gctx);
     let geiger_context = find_unsafe_in_packages_with_progress(
         print_config.allow_partial_results,
-        cargo_metadata_parameters,
+        packages,
         print_config.include_tests,
+        print_config.output_format.uses_unsafe_sites(),
         mode,
         |progress_count, count| {
             progress.tick(progress_count, count, "find_unsafe_tick")
@@ -44,8 +61,9 @@ pub fn find_unsafe(
 
 fn find_unsafe_in_packages_with_progress<F>(
     allow_partial_results: bool,
-    cargo_metadata_parameters: &CargoMetadataParameters,
+    packages: &[Package],
     include_tests: IncludeTests,
+    collect_unsafe_sites: bool,
     mode: ScanMode,
     mut progress_fn: F,
 ) -> GeigerContext
@@ -61,8 +79,9 @@ where
         s.spawn(|_| {
             res = Some(find_unsafe_in_packages(
                 allow_partial_results,
-                cargo_metadata_parameters,
+                packages,
                 include_tests,
+                collect_unsafe_sites,
                 mode,
                 Some(on_processed),
             ))
@@ -77,8 +96,9 @@ where
 
 fn find_unsafe_in_packages<F>(
     allow_partial_results: bool,
-    cargo_metadata_parameters: &CargoMetadataParameters,
+    packages: &[Package],
     include_tests: IncludeTests,
+    collect_unsafe_sites: bool,
     mode: ScanMode,
     on_processed: Option<F>,
 ) -> GeigerContext
@@ -87,9 +107,8 @@ where
 {
     let package_id_to_metrics = Arc::new(Mutex::new(HashMap::new()));
     let ignored = Arc::new(Mutex::new(HashSet::new()));
-    let packages = cargo_metadata_parameters.metadata.packages.to_vec();
     let package_code_files: Vec<_> =
-        find_rs_files_in_packages(&packages).collect();
+        find_rs_files_in_packages(packages).collect();
     let package_code_file_count = package_code_files.len();
     let processed_count = AtomicUsize::new(0);
     package_code_files.into_par_iter().for_each_with(
@@ -106,7 +125,13 @@ where
             {
                 return;
             }
-            match find_unsafe_in_file(&path_buf, include_tests) {
+            let scanned = if collect_unsafe_sites {
+                find_unsafe_and_sites_in_file(&path_buf, include_tests)
+            } else {
+                find_unsafe_in_file(&path_buf, include_tests)
+                    .map(|rs_file_metrics| (rs_file_metrics, vec![]))
+            };
+            match scanned {
                 Err(error) => {
                     handle_unsafe_in_file_error(
                         allow_partial_results,
@@ -114,7 +139,7 @@ where
                         &path_buf,
                     );
                 }
-                Ok(rs_file_metrics) => {
+                Ok((rs_file_metrics, unsafe_sites)) => {
                     let package_id_to_metrics =
                         &mut package_id_to_metrics.lock().unwrap();
                     update_package_id_to_metrics_with_rs_file_metrics(
@@ -123,6 +148,7 @@ where
                         package_id_to_metrics,
                         path_buf,
                         rs_file_metrics,
+                        unsafe_sites,
                     );
                 }
             }
@@ -151,8 +177,14 @@ where
     }
 }
 
-fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
-    let walker = WalkDir::new(dir).into_iter();
+pub fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    // Packages vendored by `cargo vendor` into a directory below this one
+    // are scanned as packages of their own.
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !entry.file_type().is_dir()
+            || !entry.path().join(".cargo-checksum.json").exists()
+    });
     walker.filter_map(|entry| {
         let entry = entry.expect("walkdir error."); // TODO: Return result.
         if !is_file_with_ext(&entry, "rs") {
@@ -235,6 +267,7 @@ fn update_package_id_to_metrics_with_rs_file_metrics(
     package_id_to_metrics: &mut HashMap<PackageId, PackageMetrics>,
     path_buf: PathBuf,
     rs_file_metrics: RsFileMetrics,
+    unsafe_sites: Vec<UnsafeSite>,
 ) {
     let package_metrics = package_id_to_metrics
         .entry(package_id)
@@ -244,6 +277,7 @@ fn update_package_id_to_metrics_with_rs_file_metrics(
         .entry(path_buf)
         .or_insert_with(RsFileMetricsWrapper::default);
     wrapper.metrics = rs_file_metrics;
+    wrapper.unsafe_sites = unsafe_sites;
     wrapper.is_crate_entry_point = is_entry_point;
 }
 
@@ -253,7 +287,7 @@ mod find_tests {
 
     use krates::cm::{CargoOpt, MetadataCommand};
     use rstest::*;
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::io;
     use std::io::ErrorKind;
     use tempfile::tempdir;
@@ -293,6 +327,26 @@ mod find_tests {
         assert_eq!(actual_rs_file_names, rs_file_names);
     }
 
+    #[rstest]
+    fn find_rs_files_in_dir_test_skips_vendored_packages() {
+        let temp_dir = tempdir().unwrap();
+        let vendored_package_dir = temp_dir.path().join("vendor/itoa");
+        fs::create_dir_all(vendored_package_dir.join("src")).unwrap();
+        File::create(vendored_package_dir.join(".cargo-checksum.json"))
+            .unwrap();
+        File::create(vendored_package_dir.join("src/lib.rs")).unwrap();
+        File::create(temp_dir.path().join("lib.rs")).unwrap();
+
+        let rs_files = find_rs_files_in_dir(temp_dir.path())
+            .map(|f| f.file_name().unwrap().to_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(rs_files, vec![String::from("lib.rs")]);
+
+        let vendored_rs_files =
+            find_rs_files_in_dir(&vendored_package_dir).count();
+        assert_eq!(vendored_rs_files, 1);
+    }
+
     #[rstest]
     fn find_rs_file_in_package() {
         let package = get_current_workspace_package();
@@ -370,6 +424,7 @@ mod find_tests {
             &mut package_id_to_metrics,
             package.manifest_path.clone().into(),
             rs_file_metrics.clone(),
+            vec![],
         );
 
         assert!(package_id_to_metrics.contains_key(&package.id));
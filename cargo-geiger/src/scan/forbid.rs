@@ -58,7 +58,7 @@ fn scan_forbid_to_report(
         &geiger_context,
         graph,
         root_package_id,
-    ) {
+    )? {
         let pack_metrics = match package_metrics {
             Some(m) => m,
             None => {
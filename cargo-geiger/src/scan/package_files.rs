@@ -0,0 +1,60 @@
+use crate::mapping::{CargoMetadataParameters, ToCargoGeigerPackageId};
+
+use super::GeigerContext;
+
+use cargo_geiger_serde::PackageId;
+use geiger::RsFileMetrics;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The scan result of a single `.rs` file of a package
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackageFile {
+    /// The path relative to the package root, or the canonicalized path if
+    /// the file is outside of it
+    pub path: PathBuf,
+    /// Whether the file is used by the build
+    pub is_used: bool,
+    pub metrics: RsFileMetrics,
+}
+
+/// Collect the scanned `.rs` files of each package, ordered by path
+pub fn find_package_files(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    geiger_context: &GeigerContext,
+    rs_files_used: &HashSet<PathBuf>,
+) -> HashMap<PackageId, Vec<PackageFile>> {
+    let metadata = cargo_metadata_parameters.metadata;
+    let package_roots = metadata
+        .packages
+        .iter()
+        .filter_map(|package| {
+            let root = Path::new(package.manifest_path.parent()?.as_str());
+            Some((&package.id, root.canonicalize().ok()?))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut package_files = HashMap::new();
+    for (package_id, package_metrics) in &geiger_context.package_id_to_metrics {
+        let package = match package_id.to_cargo_geiger_package_id(metadata) {
+            Ok(package) => package,
+            Err(_) => continue,
+        };
+        let package_root = package_roots.get(package_id);
+        let mut files = package_metrics
+            .rs_path_to_metrics
+            .iter()
+            .map(|(path, rs_file_metrics_wrapper)| PackageFile {
+                path: package_root
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(path)
+                    .to_path_buf(),
+                is_used: rs_files_used.contains(path),
+                metrics: rs_file_metrics_wrapper.metrics.clone(),
+            })
+            .collect::<Vec<PackageFile>>();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        package_files.insert(package, files);
+    }
+    package_files
+}
@@ -0,0 +1,384 @@
+use crate::format::print_config::{colorize, OutputFormat};
+use crate::format::table::{
+    table_row_columns, table_row_delta, UNSAFE_COUNTERS_HEADER,
+};
+use crate::format::CrateDetectionStatus;
+use crate::graph::Graph;
+use crate::mapping::{
+    CargoMetadataParameters, GetPatchedPackages, ToCargoGeigerPackageId,
+};
+
+use super::find::find_rs_files_in_dir;
+use super::GeigerContext;
+
+use cargo::core::SourceId;
+use cargo::util::hex::short_hash;
+use cargo::GlobalContext;
+use cargo_geiger_serde::{
+    CounterBlock, CounterBlockDelta, PackageId, PatchedPackage,
+    PatchedPackageOriginal, Source,
+};
+use geiger::find::find_unsafe_in_file;
+use geiger::IncludeTests;
+use krates::cm::Dependency;
+use krates::semver::Version;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Find the packages of the dependency graph which were patched or replaced,
+/// and compare each with the registry package it replaces, if that is found
+/// in the local registry cache.
+pub fn find_patched_packages(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    geiger_context: &GeigerContext,
+    gctx: &GlobalContext,
+    graph: &Graph,
+    include_tests: IncludeTests,
+) -> Vec<PatchedPackage> {
+    let registry_src =
+        gctx.home().as_path_unlocked().join("registry").join("src");
+
+    let mut patched_packages = cargo_metadata_parameters
+        .metadata
+        .get_patched_packages()
+        .into_iter()
+        .filter(|(package_id, _)| graph.nodes.contains_key(package_id))
+        .filter_map(|(package_id, dependency)| {
+            let id = package_id
+                .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+                .ok()?;
+            let unsafety = geiger_context
+                .package_id_to_metrics
+                .get(&package_id)
+                .map(|package_metrics| {
+                    package_metrics.rs_path_to_metrics.values().fold(
+                        CounterBlock::default(),
+                        |total, wrapper| {
+                            total + wrapper.metrics.counters.clone()
+                        },
+                    )
+                })
+                .unwrap_or_default();
+            let original = find_original(
+                gctx,
+                &registry_src,
+                &dependency,
+                &id.version,
+                include_tests,
+            )
+            .map(|(original_id, original_unsafety)| PatchedPackageOriginal {
+                id: original_id,
+                delta: CounterBlockDelta::new(&original_unsafety, &unsafety),
+                unsafety: original_unsafety,
+            });
+            Some(PatchedPackage { id, original })
+        })
+        .collect::<Vec<PatchedPackage>>();
+
+    patched_packages.sort_by(|a, b| a.id.cmp(&b.id));
+    patched_packages
+}
+
+/// The packages of the dependency graph which were patched or replaced
+pub fn patched_package_ids(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+) -> HashSet<krates::cm::PackageId> {
+    cargo_metadata_parameters
+        .metadata
+        .get_patched_packages()
+        .into_keys()
+        .collect()
+}
+
+pub fn construct_patched_packages_lines(
+    patched_packages: &[PatchedPackage],
+    output_format: OutputFormat,
+) -> Vec<String> {
+    if patched_packages.is_empty() {
+        return vec![];
+    }
+
+    let mut lines = vec![
+        String::new(),
+        format!("Patched or replaced packages ({}):", patched_packages.len()),
+        String::new(),
+        format!(
+            "{}Package",
+            UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                .join(" ")
+        ),
+    ];
+
+    for patched_package in patched_packages {
+        let original = match &patched_package.original {
+            Some(original) => original,
+            None => {
+                lines.push(format!(
+                    "{} {} {} (patched, original not found in the local registry cache)",
+                    table_row_columns([""; 5]),
+                    patched_package.id.name,
+                    patched_package.id.version
+                ));
+                continue;
+            }
+        };
+
+        lines.push(format!(
+            "{} {} {} (original)",
+            table_row_columns([
+                original.unsafety.functions.unsafe_,
+                original.unsafety.exprs.unsafe_,
+                original.unsafety.item_impls.unsafe_,
+                original.unsafety.item_traits.unsafe_,
+                original.unsafety.methods.unsafe_,
+            ]),
+            original.id.name,
+            original.id.version
+        ));
+
+        let status = if original.delta.unsafe_total() > 0 {
+            CrateDetectionStatus::UnsafeDetected
+        } else {
+            CrateDetectionStatus::NoneDetectedAllowsUnsafe
+        };
+        lines.push(format!(
+            "{}",
+            colorize(
+                &status,
+                output_format,
+                format!(
+                    "{} {} {} (patched)",
+                    table_row_delta(&original.delta),
+                    patched_package.id.name,
+                    patched_package.id.version
+                )
+            )
+        ));
+    }
+
+    lines
+}
+
+/// Look up the registry package a patched dependency would otherwise have
+/// resolved to in the local registry cache, preferring the version of the
+/// patched package, and count all unsafe usage found in it.
+fn find_original(
+    gctx: &GlobalContext,
+    registry_src: &Path,
+    dependency: &Dependency,
+    patched_version: &Version,
+    include_tests: IncludeTests,
+) -> Option<(PackageId, CounterBlock)> {
+    let source_id = SourceId::from_url(dependency.source.as_ref()?).ok()?;
+    if !source_id.is_registry() {
+        return None;
+    }
+    let registry_url = source_id.url().clone();
+    let registry_name = if source_id.is_crates_io() {
+        String::from("crates.io")
+    } else {
+        dependency
+            .registry
+            .clone()
+            .or_else(|| source_id.url().host_str().map(String::from))?
+    };
+
+    let (version, path) = find_in_registry_src(
+        &registry_src.join(registry_index_dir_name(gctx, source_id)?),
+        dependency,
+        patched_version,
+    )?;
+
+    let unsafety = find_rs_files_in_dir(&path)
+        .filter_map(|path_buf| {
+            find_unsafe_in_file(&path_buf, include_tests).ok()
+        })
+        .fold(CounterBlock::default(), |total, rs_file_metrics| {
+            total + rs_file_metrics.counters
+        });
+
+    Some((
+        PackageId {
+            name: dependency.name.clone(),
+            version,
+            source: Source::Registry {
+                name: registry_name,
+                url: registry_url,
+            },
+        },
+        unsafety,
+    ))
+}
+
+/// The directory of `registry/src` the packages of a registry are unpacked
+/// to, named the way cargo names it: the host of the index followed by a
+/// hash of its source id. The crates.io index is stored under the sparse
+/// protocol's name unless the git protocol is configured.
+pub fn registry_index_dir_name(
+    gctx: &GlobalContext,
+    source_id: SourceId,
+) -> Option<String> {
+    let source_id = if source_id.is_crates_io() {
+        SourceId::crates_io_maybe_sparse_http(gctx).ok()?
+    } else {
+        source_id
+    };
+    Some(format!(
+        "{}-{}",
+        source_id.url().host_str().unwrap_or_default(),
+        short_hash(&source_id)
+    ))
+}
+
+/// Find the unpacked versions of a dependency in the directory of its
+/// registry index
+fn find_in_registry_src(
+    index_dir: &Path,
+    dependency: &Dependency,
+    patched_version: &Version,
+) -> Option<(Version, PathBuf)> {
+    let prefix = format!("{}-", dependency.name);
+
+    let mut matching_versions = fs::read_dir(index_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|package_dir| {
+            let file_name = package_dir.file_name();
+            let version = Version::parse(
+                file_name.to_str()?.strip_prefix(prefix.as_str())?,
+            )
+            .ok()?;
+            dependency
+                .req
+                .matches(&version)
+                .then(|| (version, package_dir.path()))
+        })
+        .collect::<Vec<(Version, PathBuf)>>();
+
+    matching_versions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    match matching_versions
+        .iter()
+        .position(|(version, _)| version == patched_version)
+    {
+        Some(index) => Some(matching_versions.swap_remove(index)),
+        None => matching_versions.pop(),
+    }
+}
+
+#[cfg(test)]
+mod patched_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[rstest(
+        input_patched_version,
+        input_req,
+        expected_version,
+        case("1.2.0", "1", Some("1.2.0")),
+        case("1.4.0", "1", Some("1.3.1")),
+        case("1.5.0", "1", Some("1.3.1")),
+        case("1.4.0", "^1.3.2", None)
+    )]
+    fn find_in_registry_src_test(
+        input_patched_version: &str,
+        input_req: &str,
+        expected_version: Option<&str>,
+    ) {
+        let registry_src = tempdir().unwrap();
+        for package_dir in [
+            "index.crates.io-0123456789abcdef/num_cpus-1.2.0",
+            "index.crates.io-0123456789abcdef/num_cpus-1.3.1",
+            "index.crates.io-0123456789abcdef/num_cpus_extra-1.9.0",
+            "github.com-fedcba9876543210/num_cpus-1.5.0",
+        ] {
+            fs::create_dir_all(registry_src.path().join(package_dir)).unwrap();
+        }
+
+        let dependency = dependency(input_req);
+
+        let found = find_in_registry_src(
+            &registry_src.path().join("index.crates.io-0123456789abcdef"),
+            &dependency,
+            &Version::parse(input_patched_version).unwrap(),
+        );
+
+        assert_eq!(
+            found.map(|(version, _)| version),
+            expected_version.map(|v| Version::parse(v).unwrap())
+        );
+    }
+
+    #[rstest(
+        input_source,
+        case("registry+https://github.com/rust-lang/crates.io-index"),
+        case("sparse+https://index.crates.io/")
+    )]
+    fn registry_index_dir_name_test(input_source: &str) {
+        let gctx = GlobalContext::default().unwrap();
+
+        assert_eq!(
+            registry_index_dir_name(
+                &gctx,
+                SourceId::from_url(input_source).unwrap()
+            ),
+            Some(String::from("index.crates.io-1949cf8c6b5b557f"))
+        );
+    }
+
+    #[rstest]
+    fn find_original_counts_unsafe_usage_test() {
+        let gctx = GlobalContext::default().unwrap();
+        let source_id = SourceId::from_url(
+            "registry+https://github.com/rust-lang/crates.io-index",
+        )
+        .unwrap();
+        let registry_src = tempdir().unwrap();
+        let package_dir = registry_src
+            .path()
+            .join(registry_index_dir_name(&gctx, source_id).unwrap())
+            .join("num_cpus-1.2.0/src");
+        fs::create_dir_all(&package_dir).unwrap();
+        let mut file = File::create(package_dir.join("lib.rs")).unwrap();
+        writeln!(file, "pub unsafe fn f() {{}}").unwrap();
+
+        let (id, unsafety) = find_original(
+            &gctx,
+            registry_src.path(),
+            &dependency("1"),
+            &Version::new(1, 2, 0),
+            IncludeTests::Yes,
+        )
+        .unwrap();
+
+        assert_eq!(id.name, "num_cpus");
+        assert_eq!(id.version, Version::new(1, 2, 0));
+        assert!(matches!(
+            id.source,
+            Source::Registry { ref name, .. } if name == "crates.io"
+        ));
+        assert_eq!(unsafety.functions.unsafe_, 1);
+    }
+
+    fn dependency(req: &str) -> Dependency {
+        serde_json::from_value(serde_json::json!({
+            "name": "num_cpus",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "req": req,
+            "kind": null,
+            "rename": null,
+            "optional": false,
+            "uses_default_features": true,
+            "features": [],
+            "target": null,
+            "registry": null,
+            "path": null
+        }))
+        .unwrap()
+    }
+}
@@ -2,7 +2,7 @@ mod custom_executor;
 
 use custom_executor::{CustomExecutor, CustomExecutorInnerContext};
 
-use cargo::core::compiler::Executor;
+use cargo::core::compiler::{CompileKind, Executor};
 use cargo::core::manifest::TargetKind;
 use cargo::core::Workspace;
 use cargo::ops;
@@ -10,7 +10,7 @@ use cargo::ops::{CleanOptions, CompileOptions};
 use cargo::util::{interning::InternedString, CargoResult};
 use cargo::GlobalContext;
 use cargo_util::paths;
-use geiger::RsFileMetrics;
+use geiger::{RsFileMetrics, UnsafeSite};
 use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
@@ -47,6 +47,10 @@ pub struct RsFileMetricsWrapper {
     /// and cannot know if a file is a crate entry point or not, so we add this
     /// information here.
     pub is_crate_entry_point: bool,
+
+    /// Where each unsafe item or block of the file starts, only collected
+    /// for the output formats which list them
+    pub unsafe_sites: Vec<UnsafeSite>,
 }
 
 #[derive(Debug)]
@@ -157,13 +161,26 @@ pub fn resolve_rs_file_deps(
         .package_ids()
         .map(|package_id| package_id.name().as_str().to_owned())
         .collect();
+    // Clean the output directories of the targets being built for, otherwise
+    // a previous build for the same target could leave the rustc calls fresh.
+    let targets = compile_options
+        .build_config
+        .requested_kinds
+        .iter()
+        .filter_map(|compile_kind| match compile_kind {
+            CompileKind::Host => None,
+            CompileKind::Target(target) => {
+                Some(target.rustc_target().to_string())
+            }
+        })
+        .collect();
     // Need to run a cargo clean to identify all new .d deps files.
     // TODO: Figure out how this can be avoided to improve performance, clean
     // Rust builds are __slow__.
     let clean_options = CleanOptions {
         gctx,
         spec: packages,
-        targets: vec![],
+        targets,
         profile_specified: false,
         // A temporary hack to get cargo 0.43 to build, TODO: look closer at the updated cargo API
         // later.
@@ -0,0 +1,459 @@
+use crate::cli::get_cfgs;
+use crate::format::print_config::PrintConfig;
+
+use super::find::find_unsafe_in_package_list;
+use super::{unsafe_stats, GeigerContext, ScanMode, ScanParameters};
+
+use anyhow::anyhow;
+use cargo::{CargoResult, CliError, GlobalContext};
+use cargo_geiger_serde::{DependencyKind, PackageInfo, ReportEntry, Source};
+use cargo_platform::{Cfg, Platform};
+use krates::cm::semver::{Version, VersionReq};
+use krates::cm::{Package, PackageId};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use url::Url;
+
+/// The name of the group the standard library packages are listed under
+pub const RUST_STD_GROUP_NAME: &str = "rust-std";
+
+/// The standard library packages scanned from the sources of the `rust-src`
+/// component
+const RUST_STD_ROOT_PACKAGES: [&str; 3] = ["core", "alloc", "std"];
+
+/// The scanned packages of the Rust standard library, which are not part of
+/// the dependency graph
+pub struct RustStd {
+    pub packages: Vec<RustStdPackage>,
+    pub geiger_context: GeigerContext,
+    /// Every scanned `.rs` file, there is no build to tell the files used by
+    /// it apart
+    pub rs_files_used: HashSet<PathBuf>,
+}
+
+pub struct RustStdPackage {
+    pub package: Package,
+    /// The standard library packages this package depends on
+    pub dependencies: Vec<PackageId>,
+}
+
+/// Scan the standard library sources of the active toolchain, if requested
+/// with `--include-std`
+pub fn find_rust_std(
+    scan_parameters: &ScanParameters,
+) -> Result<Option<RustStd>, CliError> {
+    if !scan_parameters.args.include_std {
+        return Ok(None);
+    }
+    let packages =
+        find_rust_std_packages(scan_parameters.args, scan_parameters.gctx)
+            .map_err(|e| CliError::new(e, 1))?;
+    Ok(Some(scan_rust_std_packages(
+        packages,
+        scan_parameters.gctx,
+        scan_parameters.print_config,
+    )?))
+}
+
+fn scan_rust_std_packages(
+    packages: Vec<RustStdPackage>,
+    gctx: &GlobalContext,
+    print_config: &PrintConfig,
+) -> Result<RustStd, CliError> {
+    let geiger_context = find_unsafe_in_package_list(
+        &packages
+            .iter()
+            .map(|rust_std_package| rust_std_package.package.clone())
+            .collect::<Vec<Package>>(),
+        gctx,
+        ScanMode::Full,
+        print_config,
+    )?;
+    let rs_files_used = geiger_context
+        .package_id_to_metrics
+        .values()
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.keys())
+        .cloned()
+        .collect();
+    Ok(RustStd {
+        packages,
+        geiger_context,
+        rs_files_used,
+    })
+}
+
+fn find_rust_std_packages(
+    args: &crate::args::Args,
+    gctx: &GlobalContext,
+) -> CargoResult<Vec<RustStdPackage>> {
+    let rustc = gctx.load_global_rustc(None)?;
+    let sysroot = String::from_utf8(
+        cargo_util::ProcessBuilder::new(&rustc.path)
+            .arg("--print=sysroot")
+            .env_remove("RUST_LOG")
+            .exec_with_output()?
+            .stdout,
+    )?;
+    let library_dir =
+        Path::new(sysroot.trim()).join("lib/rustlib/src/rust/library");
+    if !library_dir.join("std/Cargo.toml").exists() {
+        return Err(anyhow!(
+            "the standard library sources were not found in `{}`, install \
+             them with `rustup component add rust-src`",
+            library_dir.display()
+        ));
+    }
+
+    let target = args.target_args.target().unwrap_or(&rustc.host);
+    let cfgs = get_cfgs(&rustc.path, &Some(String::from(target)))?;
+    read_rust_std_packages(&library_dir, target, cfgs.as_deref())
+}
+
+/// Read `core`, `alloc` and `std` from the `library` directory of the
+/// `rust-src` component, and the path and vendored packages they depend on
+/// for the target. Optional dependencies are left out.
+fn read_rust_std_packages(
+    library_dir: &Path,
+    target: &str,
+    cfgs: Option<&[Cfg]>,
+) -> CargoResult<Vec<RustStdPackage>> {
+    let mut pending_manifest_paths = RUST_STD_ROOT_PACKAGES
+        .iter()
+        .rev()
+        .map(|name| library_dir.join(name).join("Cargo.toml").canonicalize())
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    let mut manifest_dependencies = HashMap::new();
+    let mut packages = vec![];
+    while let Some(manifest_path) = pending_manifest_paths.pop() {
+        if manifest_dependencies.contains_key(&manifest_path) {
+            continue;
+        }
+        let manifest = fs::read_to_string(&manifest_path)?
+            .parse::<toml::Value>()
+            .map_err(|e| {
+                anyhow!("failed to parse `{}`: {}", manifest_path.display(), e)
+            })?;
+        let dependency_manifest_paths = dependency_manifest_paths(
+            &manifest,
+            &manifest_path,
+            library_dir,
+            target,
+            cfgs,
+        );
+        pending_manifest_paths
+            .extend(dependency_manifest_paths.iter().rev().cloned());
+        packages.push(to_package(&manifest, &manifest_path)?);
+        manifest_dependencies.insert(manifest_path, dependency_manifest_paths);
+    }
+
+    let package_ids = packages
+        .iter()
+        .map(|package| {
+            (
+                package.manifest_path.clone().into_std_path_buf(),
+                package.id.clone(),
+            )
+        })
+        .collect::<HashMap<PathBuf, PackageId>>();
+    Ok(packages
+        .into_iter()
+        .map(|package| {
+            let dependencies = manifest_dependencies
+                [package.manifest_path.as_std_path()]
+            .iter()
+            .map(|manifest_path| package_ids[manifest_path].clone())
+            .collect();
+            RustStdPackage {
+                package,
+                dependencies,
+            }
+        })
+        .collect())
+}
+
+/// The manifests of the non optional dependencies declared for the target,
+/// found by their path or in the `vendor` directory
+fn dependency_manifest_paths(
+    manifest: &toml::Value,
+    manifest_path: &Path,
+    library_dir: &Path,
+    target: &str,
+    cfgs: Option<&[Cfg]>,
+) -> Vec<PathBuf> {
+    let mut dependency_tables = vec![manifest.get("dependencies")];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        dependency_tables.extend(
+            targets
+                .iter()
+                .filter(|(platform, _)| {
+                    Platform::from_str(platform).is_ok_and(|platform| {
+                        platform.matches(target, cfgs.unwrap_or(&[]))
+                    })
+                })
+                .map(|(_, target_table)| target_table.get("dependencies")),
+        );
+    }
+
+    let manifest_dir = manifest_path.parent().unwrap();
+    let mut manifest_paths = vec![];
+    for (name, dependency) in dependency_tables
+        .into_iter()
+        .flatten()
+        .filter_map(|dependencies| dependencies.as_table())
+        .flatten()
+    {
+        if dependency.get("optional").and_then(|o| o.as_bool()) == Some(true) {
+            continue;
+        }
+        let manifest_path =
+            match dependency.get("path").and_then(|p| p.as_str()) {
+                Some(path) => Some(manifest_dir.join(path).join("Cargo.toml")),
+                None => {
+                    let package_name = dependency
+                        .get("package")
+                        .and_then(|p| p.as_str())
+                        .unwrap_or(name);
+                    let version_req = dependency
+                        .as_str()
+                        .or_else(|| dependency.get("version")?.as_str());
+                    version_req.and_then(|version_req| {
+                        find_vendored_manifest(
+                            library_dir,
+                            package_name,
+                            version_req,
+                        )
+                    })
+                }
+            };
+        if let Some(Ok(manifest_path)) =
+            manifest_path.map(|manifest_path| manifest_path.canonicalize())
+        {
+            if !manifest_paths.contains(&manifest_path) {
+                manifest_paths.push(manifest_path);
+            }
+        }
+    }
+    manifest_paths
+}
+
+/// The newest vendored version of a package matching the version requirement,
+/// vendored as `vendor/<name>-<version>`
+fn find_vendored_manifest(
+    library_dir: &Path,
+    package_name: &str,
+    version_req: &str,
+) -> Option<PathBuf> {
+    let version_req = VersionReq::parse(version_req).ok()?;
+    let prefix = format!("{}-", package_name);
+    fs::read_dir(library_dir.join("vendor"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let version =
+                Version::parse(file_name.strip_prefix(&prefix)?).ok()?;
+            version_req
+                .matches(&version)
+                .then(|| (version, entry.path().join("Cargo.toml")))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, manifest_path)| manifest_path)
+}
+
+/// Construct the `cargo metadata` representation of a standard library
+/// package, which can't be read by cargo since it uses unstable features
+fn to_package(
+    manifest: &toml::Value,
+    manifest_path: &Path,
+) -> CargoResult<Package> {
+    let package = manifest.get("package").ok_or_else(|| {
+        anyhow!("no [package] section in `{}`", manifest_path.display())
+    })?;
+    let name =
+        package
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| {
+                anyhow!("no package name in `{}`", manifest_path.display())
+            })?;
+    let version = package
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("0.0.0");
+    let lib = manifest.get("lib");
+    let lib_name = lib
+        .and_then(|l| l.get("name"))
+        .and_then(|n| n.as_str())
+        .map_or_else(|| name.replace('-', "_"), String::from);
+    let manifest_dir = manifest_path.parent().unwrap();
+    let lib_path = manifest_dir.join(
+        lib.and_then(|l| l.get("path"))
+            .and_then(|p| p.as_str())
+            .unwrap_or("src/lib.rs"),
+    );
+    let mut target = json!({
+        "name": lib_name,
+        "kind": ["lib"],
+        "crate_types": ["lib"],
+        "src_path": lib_path,
+    });
+    // An edition inherited from a workspace is left at the default
+    if let Some(edition) = package.get("edition").and_then(|e| e.as_str()) {
+        target["edition"] = json!(edition);
+    }
+
+    let source_url = Url::from_directory_path(manifest_dir)
+        .map_err(|_| anyhow!("invalid path `{}`", manifest_dir.display()))?;
+    Ok(serde_json::from_value(json!({
+        "name": name,
+        "version": version,
+        "id": format!("path+{}#{}@{}", source_url, name, version),
+        "dependencies": [],
+        "targets": [target],
+        "features": {},
+        "manifest_path": manifest_path,
+    }))?)
+}
+
+/// The report entries of the scanned standard library packages, keyed by their
+/// `cargo_geiger_serde` id
+pub fn rust_std_report_entries(
+    rust_std: &RustStd,
+) -> HashMap<cargo_geiger_serde::PackageId, ReportEntry> {
+    let package_ids = rust_std
+        .packages
+        .iter()
+        .filter_map(|rust_std_package| {
+            let package_id =
+                to_cargo_geiger_package_id(&rust_std_package.package)?;
+            Some((rust_std_package.package.id.clone(), package_id))
+        })
+        .collect::<HashMap<PackageId, cargo_geiger_serde::PackageId>>();
+
+    rust_std
+        .packages
+        .iter()
+        .filter_map(|rust_std_package| {
+            let package_id = package_ids.get(&rust_std_package.package.id)?;
+            let mut package_info = PackageInfo::new(package_id.clone());
+            for dependency in rust_std_package
+                .dependencies
+                .iter()
+                .filter_map(|dependency| package_ids.get(dependency))
+            {
+                package_info
+                    .add_dependency(dependency.clone(), DependencyKind::Normal);
+            }
+            let package_metrics = rust_std
+                .geiger_context
+                .package_id_to_metrics
+                .get(&rust_std_package.package.id)
+                .cloned()
+                .unwrap_or_default();
+            let entry = ReportEntry {
+                package: package_info,
+                unsafety: unsafe_stats(
+                    &package_metrics,
+                    &rust_std.rs_files_used,
+                ),
+            };
+            Some((package_id.clone(), entry))
+        })
+        .collect()
+}
+
+/// The `cargo_geiger_serde` id of a standard library package
+fn to_cargo_geiger_package_id(
+    package: &Package,
+) -> Option<cargo_geiger_serde::PackageId> {
+    let package_dir = package.manifest_path.parent()?;
+    Some(cargo_geiger_serde::PackageId {
+        name: package.name.clone(),
+        version: package.version.clone(),
+        source: Source::Path(
+            Url::from_directory_path(package_dir.as_std_path()).ok()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod rust_std_tests {
+    use super::*;
+
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn read_rust_std_packages_test() {
+        let library_dir = tempdir().unwrap();
+        let write_manifest = |dir: &str, manifest: &str| {
+            let dir = library_dir.path().join(dir);
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+            fs::write(dir.join("src/lib.rs"), "").unwrap();
+        };
+        write_manifest(
+            "core",
+            "[package]\nname = \"core\"\nversion = \"0.0.0\"\n",
+        );
+        write_manifest(
+            "alloc",
+            "[package]\nname = \"alloc\"\nversion = \"0.0.0\"\n\
+             [dependencies]\ncore = { path = \"../core\" }\n",
+        );
+        write_manifest(
+            "std",
+            "[package]\nname = \"std\"\nversion = \"0.0.0\"\n\
+             [dependencies]\n\
+             alloc = { path = \"../alloc\" }\n\
+             cfg-if = \"1.0\"\n\
+             miniz_oxide = { version = \"0.8\", optional = true }\n\
+             [target.'cfg(windows)'.dependencies]\n\
+             windows-targets = { path = \"../windows_targets\" }\n",
+        );
+        write_manifest(
+            "vendor/cfg-if-0.1.10",
+            "[package]\nname = \"cfg-if\"\nversion = \"0.1.10\"\n",
+        );
+        write_manifest(
+            "vendor/cfg-if-1.0.0",
+            "[package]\nname = \"cfg-if\"\nversion = \"1.0.0\"\n",
+        );
+        write_manifest(
+            "windows_targets",
+            "[package]\nname = \"windows-targets\"\nversion = \"0.0.0\"\n",
+        );
+
+        let packages = read_rust_std_packages(
+            library_dir.path(),
+            "x86_64-unknown-linux-gnu",
+            Some(&[Cfg::from_str("unix").unwrap()]),
+        )
+        .unwrap();
+
+        let names_and_versions = packages
+            .iter()
+            .map(|rust_std_package| {
+                format!(
+                    "{} {}",
+                    rust_std_package.package.name,
+                    rust_std_package.package.version
+                )
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            names_and_versions,
+            vec!["core 0.0.0", "alloc 0.0.0", "std 0.0.0", "cfg-if 1.0.0"]
+        );
+        assert_eq!(
+            packages[2].dependencies,
+            vec![
+                packages[1].package.id.clone(),
+                packages[3].package.id.clone()
+            ]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::args::Args;
+use crate::format::print_config::{colorize, OutputFormat};
+use crate::format::table::{table_row, UNSAFE_COUNTERS_HEADER};
+use crate::format::CrateDetectionStatus;
+use crate::graph::build_graph;
+use crate::mapping::CargoMetadataParameters;
+
+use super::{scan_report, ScanResult};
+
+use cargo::core::Workspace;
+use cargo::{CliError, GlobalContext};
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport, UnsafeInfo};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// The unsafe usage of several target triples, split into the part which is
+/// shared by every target and the parts which are unique to a single target.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub struct TargetComparison {
+    /// The target triples which were compared, in the order they were given
+    pub targets: Vec<String>,
+    /// Packages built with identical unsafety for every target
+    pub shared: TargetUnsafety,
+    /// Packages which are only built, or built with different unsafety, for a
+    /// given target
+    pub unique: BTreeMap<String, TargetUnsafety>,
+}
+
+/// A group of packages and their accumulated unsafe usage
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub struct TargetUnsafety {
+    pub packages: Vec<TargetPackageEntry>,
+    pub used: CounterBlock,
+    pub unused: CounterBlock,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct TargetPackageEntry {
+    pub id: PackageId,
+    pub unsafety: UnsafeInfo,
+}
+
+impl TargetUnsafety {
+    fn push(&mut self, id: PackageId, unsafety: UnsafeInfo) {
+        self.used += unsafety.used.clone();
+        self.unused += unsafety.unused.clone();
+        self.packages.push(TargetPackageEntry { id, unsafety });
+    }
+}
+
+/// Build the dependency graph and scan it once for every target triple given
+/// with `--target`, then compare the results.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_targets(
+    args: &Args,
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    config_host: &str,
+    gctx: &GlobalContext,
+    global_rustc_path: &PathBuf,
+    graph_root_package_id: krates::cm::PackageId,
+    root_package_id: krates::cm::PackageId,
+    workspace: &Workspace,
+) -> Result<ScanResult, CliError> {
+    let mut target_reports = Vec::new();
+
+    for target in &args.target_args.targets {
+        let mut target_args = args.clone();
+        target_args.target_args.targets = vec![target.clone()];
+
+        let graph = build_graph(
+            &target_args,
+            cargo_metadata_parameters,
+            config_host,
+            global_rustc_path,
+            graph_root_package_id.clone(),
+        )?;
+
+        let report = scan_report(
+            &target_args,
+            cargo_metadata_parameters,
+            gctx,
+            &graph,
+            root_package_id.clone(),
+            workspace,
+        )?;
+
+        target_reports.push((target.clone(), report));
+    }
+
+    Ok(target_comparison_to_scan_result(
+        &compare_targets(target_reports),
+        args.output_format,
+    ))
+}
+
+/// Compare the reports produced by scanning the same root package for
+/// different target triples
+pub fn compare_targets(
+    target_reports: Vec<(String, SafetyReport)>,
+) -> TargetComparison {
+    let mut comparison = TargetComparison {
+        targets: target_reports
+            .iter()
+            .map(|(target, _)| target.clone())
+            .collect(),
+        ..Default::default()
+    };
+
+    let package_ids = target_reports
+        .iter()
+        .flat_map(|(_, report)| report.packages.keys())
+        .cloned()
+        .collect::<BTreeSet<PackageId>>();
+
+    for package_id in package_ids {
+        let target_unsafety = target_reports
+            .iter()
+            .filter_map(|(target, report)| {
+                report
+                    .packages
+                    .get(&package_id)
+                    .map(|entry| (target, &entry.unsafety))
+            })
+            .collect::<Vec<(&String, &UnsafeInfo)>>();
+
+        let is_shared = target_unsafety.len() == target_reports.len()
+            && target_unsafety
+                .windows(2)
+                .all(|pair| pair[0].1 == pair[1].1);
+
+        if is_shared {
+            comparison
+                .shared
+                .push(package_id, target_unsafety[0].1.clone());
+            continue;
+        }
+
+        for (target, unsafety) in target_unsafety {
+            comparison
+                .unique
+                .entry(target.clone())
+                .or_default()
+                .push(package_id.clone(), unsafety.clone());
+        }
+    }
+
+    comparison
+}
+
+pub fn target_comparison_to_scan_result(
+    comparison: &TargetComparison,
+    output_format: OutputFormat,
+) -> ScanResult {
+    let scan_output_lines = match output_format {
+        OutputFormat::Json => {
+            vec![serde_json::to_string(comparison).unwrap()]
+        }
+        _ => {
+            let mut lines = target_unsafety_to_lines(
+                "Unsafe usage shared by all targets",
+                &comparison.shared,
+                output_format,
+            );
+            for target in &comparison.targets {
+                let title = format!("Unsafe usage unique to target {}", target);
+                let empty = TargetUnsafety::default();
+                let target_unsafety =
+                    comparison.unique.get(target).unwrap_or(&empty);
+                lines.append(&mut target_unsafety_to_lines(
+                    &title,
+                    target_unsafety,
+                    output_format,
+                ));
+            }
+            lines
+        }
+    };
+
+    ScanResult {
+        scan_output_lines,
+        warning_count: 0,
+    }
+}
+
+fn target_unsafety_to_lines(
+    title: &str,
+    target_unsafety: &TargetUnsafety,
+    output_format: OutputFormat,
+) -> Vec<String> {
+    let mut lines = vec![
+        String::new(),
+        format!("{} ({} packages):", title, target_unsafety.packages.len()),
+        String::new(),
+        UNSAFE_COUNTERS_HEADER.join(" "),
+    ];
+
+    for entry in &target_unsafety.packages {
+        let status = CrateDetectionStatus::from(&entry.unsafety);
+        lines.push(format!(
+            "{}",
+            colorize(
+                &status,
+                output_format,
+                format!(
+                    "{} {} {}",
+                    table_row(
+                        &entry.unsafety.used,
+                        &entry.unsafety.unused,
+                        output_format
+                    ),
+                    entry.id.name,
+                    entry.id.version
+                )
+            )
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(table_row(
+        &target_unsafety.used,
+        &target_unsafety.unused,
+        output_format,
+    ));
+
+    lines
+}
+
+#[cfg(test)]
+mod targets_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+
+    #[rstest]
+    fn compare_targets_splits_shared_and_unique_packages() {
+        let shared = report_entry(package_id("shared", "1.0.0"), 1, false);
+        let linux_only =
+            report_entry(package_id("linux_only", "1.0.0"), 2, false);
+        let differing_on_linux =
+            report_entry(package_id("differing", "1.0.0"), 3, false);
+        let differing_on_wasm =
+            report_entry(package_id("differing", "1.0.0"), 4, false);
+
+        let comparison = compare_targets(vec![
+            (
+                String::from("x86_64-unknown-linux-gnu"),
+                safety_report(vec![
+                    shared.clone(),
+                    linux_only.clone(),
+                    differing_on_linux.clone(),
+                ]),
+            ),
+            (
+                String::from("wasm32-unknown-unknown"),
+                safety_report(vec![shared.clone(), differing_on_wasm.clone()]),
+            ),
+        ]);
+
+        assert_eq!(
+            comparison.targets,
+            vec![
+                String::from("x86_64-unknown-linux-gnu"),
+                String::from("wasm32-unknown-unknown")
+            ]
+        );
+        assert_eq!(
+            package_names(&comparison.shared),
+            vec![String::from("shared")]
+        );
+        assert_eq!(comparison.shared.used.exprs.unsafe_, 1);
+        assert_eq!(
+            package_names(&comparison.unique["x86_64-unknown-linux-gnu"]),
+            vec![String::from("differing"), String::from("linux_only")]
+        );
+        assert_eq!(
+            comparison.unique["x86_64-unknown-linux-gnu"]
+                .used
+                .exprs
+                .unsafe_,
+            5
+        );
+        assert_eq!(
+            package_names(&comparison.unique["wasm32-unknown-unknown"]),
+            vec![String::from("differing")]
+        );
+        assert_eq!(
+            comparison.unique["wasm32-unknown-unknown"]
+                .used
+                .exprs
+                .unsafe_,
+            4
+        );
+    }
+
+    #[rstest]
+    fn target_comparison_to_scan_result_lists_every_target() {
+        let comparison = compare_targets(vec![
+            (
+                String::from("x86_64-unknown-linux-gnu"),
+                safety_report(vec![report_entry(
+                    package_id("shared", "1.0.0"),
+                    1,
+                    false,
+                )]),
+            ),
+            (
+                String::from("thumbv7em-none-eabihf"),
+                safety_report(vec![report_entry(
+                    package_id("shared", "1.0.0"),
+                    1,
+                    false,
+                )]),
+            ),
+        ]);
+
+        let scan_result =
+            target_comparison_to_scan_result(&comparison, OutputFormat::Ascii);
+
+        let titles = scan_result
+            .scan_output_lines
+            .iter()
+            .filter(|line| line.starts_with("Unsafe usage"))
+            .cloned()
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            titles,
+            vec![
+                String::from("Unsafe usage shared by all targets (1 packages):"),
+                String::from(
+                    "Unsafe usage unique to target x86_64-unknown-linux-gnu (0 packages):"
+                ),
+                String::from(
+                    "Unsafe usage unique to target thumbv7em-none-eabihf (0 packages):"
+                ),
+            ]
+        );
+    }
+
+    fn package_names(target_unsafety: &TargetUnsafety) -> Vec<String> {
+        target_unsafety
+            .packages
+            .iter()
+            .map(|entry| entry.id.name.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,72 @@
+use crate::graph::Graph;
+use crate::mapping::{CargoMetadataParameters, ToCargoGeigerPackageId};
+
+use super::GeigerContext;
+
+use cargo_geiger_serde::PackageId;
+use geiger::UnsafeSite;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// An unsafe function, block, impl, trait or method in a `.rs` file used by
+/// the build
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsedUnsafeSite {
+    pub package: PackageId,
+    /// Whether the package is a member of the workspace
+    pub is_workspace_member: bool,
+    /// The canonicalized path of the `.rs` file
+    pub path: PathBuf,
+    pub site: UnsafeSite,
+}
+
+/// The unsafe sites of every scanned `.rs` file of the packages in the
+/// dependency graph which is used by the build, ordered by package, path and
+/// position. The sites are only collected by the scan for the output formats
+/// which use them.
+pub fn find_used_unsafe_sites(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<UsedUnsafeSite> {
+    let metadata = cargo_metadata_parameters.metadata;
+    let mut used_unsafe_sites = vec![];
+    for (package_id, package_metrics) in &geiger_context.package_id_to_metrics {
+        if !graph.nodes.contains_key(package_id) {
+            continue;
+        }
+        let package = match package_id.to_cargo_geiger_package_id(metadata) {
+            Ok(package) => package,
+            Err(_) => continue,
+        };
+        let is_workspace_member =
+            metadata.workspace_members.contains(package_id);
+        for (path, rs_file_metrics_wrapper) in
+            &package_metrics.rs_path_to_metrics
+        {
+            if !rs_files_used.contains(path) {
+                continue;
+            }
+            used_unsafe_sites.extend(
+                rs_file_metrics_wrapper.unsafe_sites.iter().map(|site| {
+                    UsedUnsafeSite {
+                        package: package.clone(),
+                        is_workspace_member,
+                        path: path.clone(),
+                        site: site.clone(),
+                    }
+                }),
+            );
+        }
+    }
+    used_unsafe_sites.sort_by(|a, b| {
+        (&a.package, &a.path, a.site.line, a.site.column).cmp(&(
+            &b.package,
+            &b.path,
+            b.site.line,
+            b.site.column,
+        ))
+    });
+    used_unsafe_sites
+}
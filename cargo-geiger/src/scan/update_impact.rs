@@ -0,0 +1,298 @@
+use crate::args::Args;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::table::{table_row_delta, UNSAFE_COUNTERS_HEADER};
+use crate::mapping::ToCargoGeigerPackageId;
+
+use super::find::find_unsafe_in_package_list;
+use super::{unsafe_stats, GeigerContext, ScanMode, ScanResult};
+
+use cargo::{CargoResult, CliError, GlobalContext};
+use cargo_geiger_serde::{
+    CounterBlock, CounterBlockDelta, PackageId, PackageInfo, ReportEntry,
+    SafetyReport, SafetyReportDiff,
+};
+use krates::cm::{Metadata, Package};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// How the used unsafe code changes when going from the packages pinned by
+/// an old lockfile to the ones pinned by a new lockfile
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub struct UpdateImpact {
+    /// Change of the used unsafe code summed over all packages
+    pub total: CounterBlockDelta,
+    /// The packages which were updated, added or removed, ordered by name
+    pub packages: Vec<UpdateImpactEntry>,
+    /// Packages which differ between the lockfiles but could not be scanned
+    pub packages_without_metrics: Vec<PackageId>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct UpdateImpactEntry {
+    /// The package pinned by the old lockfile, `None` if it was added
+    pub old: Option<PackageId>,
+    /// The package pinned by the new lockfile, `None` if it was removed
+    pub new: Option<PackageId>,
+    /// Change of the used unsafe code
+    pub used: CounterBlockDelta,
+}
+
+impl UpdateImpactEntry {
+    pub fn label(&self) -> String {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) if old.version == new.version => {
+                format!("{} {} (source changed)", new.name, new.version)
+            }
+            (Some(old), Some(new)) => {
+                format!("{} {} -> {}", new.name, old.version, new.version)
+            }
+            (None, Some(new)) => {
+                format!("{} {} (added)", new.name, new.version)
+            }
+            (Some(old), None) => {
+                format!("{} {} (removed)", old.name, old.version)
+            }
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Scan only the packages which differ between the old and the new lockfile,
+/// counting every scanned file as used, and compare their unsafe usage
+pub fn scan_update_impact(
+    args: &Args,
+    old_metadata: &Metadata,
+    new_metadata: &Metadata,
+    gctx: &GlobalContext,
+) -> Result<UpdateImpact, CliError> {
+    let print_config = PrintConfig::new(args)?;
+
+    let old_packages = packages_not_in(old_metadata, new_metadata);
+    let new_packages = packages_not_in(new_metadata, old_metadata);
+    let changed_packages = old_packages
+        .iter()
+        .chain(&new_packages)
+        .cloned()
+        .collect::<Vec<Package>>();
+    let geiger_context = find_unsafe_in_package_list(
+        &changed_packages,
+        gctx,
+        ScanMode::Full,
+        &print_config,
+    )?;
+
+    let old_report =
+        lockfile_report(&geiger_context, &old_packages, old_metadata)?;
+    let new_report =
+        lockfile_report(&geiger_context, &new_packages, new_metadata)?;
+    Ok(compare_reports(&old_report, &new_report))
+}
+
+pub fn update_impact_to_scan_result(
+    update_impact: &UpdateImpact,
+    output_format: OutputFormat,
+) -> ScanResult {
+    let scan_output_lines = match output_format {
+        OutputFormat::Json => {
+            vec![serde_json::to_string(update_impact).unwrap()]
+        }
+        _ if update_impact.packages.is_empty() => {
+            vec![String::from("No packages differ between the lockfiles")]
+        }
+        _ => {
+            let mut lines = vec![
+                String::new(),
+                String::from(
+                    "Change of used unsafe code brought by the lockfile update:",
+                ),
+                String::new(),
+                format!(
+                    "{}Dependency",
+                    UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                        .join(" ")
+                ),
+                format!("{} Total", table_row_delta(&update_impact.total)),
+            ];
+            for entry in &update_impact.packages {
+                lines.push(format!(
+                    "{}     {}",
+                    table_row_delta(&entry.used),
+                    entry.label()
+                ));
+            }
+            for package_id in &update_impact.packages_without_metrics {
+                lines.push(format!(
+                    "WARNING: No metrics found for package: {} {}",
+                    package_id.name, package_id.version
+                ));
+            }
+            lines.push(String::new());
+            lines
+        }
+    };
+
+    ScanResult {
+        scan_output_lines,
+        warning_count: 0,
+    }
+}
+
+/// The packages of `metadata` which are not part of `other_metadata`
+fn packages_not_in(
+    metadata: &Metadata,
+    other_metadata: &Metadata,
+) -> Vec<Package> {
+    let other_package_ids = other_metadata
+        .packages
+        .iter()
+        .map(|package| &package.id)
+        .collect::<HashSet<_>>();
+    metadata
+        .packages
+        .iter()
+        .filter(|package| !other_package_ids.contains(&package.id))
+        .cloned()
+        .collect()
+}
+
+fn lockfile_report(
+    geiger_context: &GeigerContext,
+    packages: &[Package],
+    metadata: &Metadata,
+) -> CargoResult<SafetyReport> {
+    let rs_files_used = geiger_context
+        .package_id_to_metrics
+        .values()
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.keys())
+        .cloned()
+        .collect::<HashSet<PathBuf>>();
+
+    let mut report = SafetyReport::default();
+    for package in packages {
+        let package_id = package.id.to_cargo_geiger_package_id(metadata)?;
+        match geiger_context.package_id_to_metrics.get(&package.id) {
+            Some(package_metrics) => {
+                report.packages.insert(
+                    package_id.clone(),
+                    ReportEntry {
+                        package: PackageInfo::new(package_id),
+                        unsafety: unsafe_stats(package_metrics, &rs_files_used),
+                    },
+                );
+            }
+            None => {
+                report.packages_without_metrics.insert(package_id);
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn compare_reports(
+    old_report: &SafetyReport,
+    new_report: &SafetyReport,
+) -> UpdateImpact {
+    let used = |report: &SafetyReport, package_id: &PackageId| {
+        report
+            .packages
+            .get(package_id)
+            .map(|entry| entry.unsafety.used.clone())
+            .unwrap_or_default()
+    };
+    let diff = SafetyReportDiff::new(old_report, new_report);
+
+    let mut packages =
+        diff.changed_packages
+            .iter()
+            .map(|change| UpdateImpactEntry {
+                old: Some(change.old.clone()),
+                new: Some(change.new.clone()),
+                used: CounterBlockDelta::new(
+                    &used(old_report, &change.old),
+                    &used(new_report, &change.new),
+                ),
+            })
+            .chain(diff.added_packages.iter().map(|package_id| {
+                UpdateImpactEntry {
+                    old: None,
+                    new: Some(package_id.clone()),
+                    used: CounterBlockDelta::new(
+                        &CounterBlock::default(),
+                        &used(new_report, package_id),
+                    ),
+                }
+            }))
+            .chain(diff.removed_packages.iter().map(|package_id| {
+                UpdateImpactEntry {
+                    old: Some(package_id.clone()),
+                    new: None,
+                    used: CounterBlockDelta::new(
+                        &used(old_report, package_id),
+                        &CounterBlock::default(),
+                    ),
+                }
+            }))
+            .collect::<Vec<_>>();
+    packages.sort_by(|a, b| {
+        let key = |entry: &UpdateImpactEntry| {
+            entry.new.clone().or_else(|| entry.old.clone())
+        };
+        key(a).cmp(&key(b))
+    });
+
+    let mut packages_without_metrics = old_report
+        .packages_without_metrics
+        .iter()
+        .chain(&new_report.packages_without_metrics)
+        .cloned()
+        .collect::<Vec<_>>();
+    packages_without_metrics.sort();
+
+    UpdateImpact {
+        total: packages
+            .iter()
+            .fold(CounterBlockDelta::default(), |total, entry| {
+                total + entry.used.clone()
+            }),
+        packages,
+        packages_without_metrics,
+    }
+}
+
+#[cfg(test)]
+mod update_impact_tests {
+    use super::*;
+
+    use crate::lib_tests::{package_id, report_entry, safety_report};
+    use rstest::*;
+
+    #[rstest]
+    fn compare_reports_test() {
+        let old_report = safety_report(vec![
+            report_entry(package_id("bumped", "1.0.0"), 2, false),
+            report_entry(package_id("removed", "0.1.0"), 1, false),
+        ]);
+        let new_report = safety_report(vec![
+            report_entry(package_id("added", "2.0.0"), 4, false),
+            report_entry(package_id("bumped", "1.1.0"), 5, false),
+        ]);
+
+        let update_impact = compare_reports(&old_report, &new_report);
+
+        assert_eq!(
+            update_impact
+                .packages
+                .iter()
+                .map(|entry| (entry.label(), entry.used.exprs.unsafe_))
+                .collect::<Vec<_>>(),
+            vec![
+                (String::from("added 2.0.0 (added)"), 4),
+                (String::from("bumped 1.0.0 -> 1.1.0"), 3),
+                (String::from("removed 0.1.0 (removed)"), -1),
+            ]
+        );
+        assert_eq!(update_impact.total.exprs.unsafe_, 6);
+        assert!(update_impact.packages_without_metrics.is_empty());
+    }
+}
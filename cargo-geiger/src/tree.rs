@@ -27,7 +27,9 @@ pub struct TreeSymbols {
     pub right: &'static str,
 }
 
-fn construct_tree_vines_string(
+/// The tree vines in front of a package, with one entry in `levels_continue`
+/// per level, telling whether more packages follow at that level
+pub fn construct_tree_vines_string(
     levels_continue: &mut [bool],
     print_config: &PrintConfig,
 ) -> String {
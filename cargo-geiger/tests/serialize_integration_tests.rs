@@ -13,8 +13,8 @@ use self::integration_test::IntegrationTest;
 use self::report::{merge_test_reports, single_entry_safety_report, to_set};
 
 use cargo_geiger_serde::{
-    Count, CounterBlock, PackageInfo, ReportEntry, SafetyReport, Source,
-    UnsafeInfo,
+    Count, CounterBlock, PackageInfo, PatchedPackage, ReportEntry,
+    SafetyReport, Source, UnsafeInfo,
 };
 use rstest::rstest;
 use std::path::PathBuf;
@@ -307,6 +307,10 @@ impl IntegrationTest for Test7 {
             &mut report,
             external_package_reports::num_cpus_safety_report(cx),
         );
+        report.patched_packages = vec![PatchedPackage {
+            id: external_package_reports::num_cpus_package_id(cx),
+            original: None,
+        }];
         report
     }
 
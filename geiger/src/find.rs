@@ -1,4 +1,4 @@
-use super::{IncludeTests, RsFileMetrics, ScanFileError};
+use super::{IncludeTests, RsFileMetrics, ScanFileError, UnsafeSite};
 
 use crate::geiger_syn_visitor::GeigerSynVisitor;
 
@@ -11,13 +11,7 @@ pub fn find_unsafe_in_file(
     path: &Path,
     include_tests: IncludeTests,
 ) -> Result<RsFileMetrics, ScanFileError> {
-    let mut file = File::open(path)
-        .map_err(|e| ScanFileError::Io(e, path.to_path_buf()))?;
-    let mut src = vec![];
-    file.read_to_end(&mut src)
-        .map_err(|e| ScanFileError::Io(e, path.to_path_buf()))?;
-    let src = String::from_utf8(src)
-        .map_err(|e| ScanFileError::Utf8(e, path.to_path_buf()))?;
+    let src = read_rs_file(path)?;
     find_unsafe_in_string(&src, include_tests)
         .map_err(|e| ScanFileError::Syn(e, path.to_path_buf()))
 }
@@ -26,17 +20,89 @@ pub fn find_unsafe_in_string(
     src: &str,
     include_tests: IncludeTests,
 ) -> Result<RsFileMetrics, syn::Error> {
+    scan_string(src, include_tests, false).map(|(metrics, _)| metrics)
+}
+
+/// Scan a single file for `unsafe` usage, and find where each counted unsafe
+/// function, block, impl, trait and method starts.
+pub fn find_unsafe_and_sites_in_file(
+    path: &Path,
+    include_tests: IncludeTests,
+) -> Result<(RsFileMetrics, Vec<UnsafeSite>), ScanFileError> {
+    let src = read_rs_file(path)?;
+    find_unsafe_and_sites_in_string(&src, include_tests)
+        .map_err(|e| ScanFileError::Syn(e, path.to_path_buf()))
+}
+
+pub fn find_unsafe_and_sites_in_string(
+    src: &str,
+    include_tests: IncludeTests,
+) -> Result<(RsFileMetrics, Vec<UnsafeSite>), syn::Error> {
+    scan_string(src, include_tests, true)
+}
+
+fn scan_string(
+    src: &str,
+    include_tests: IncludeTests,
+    collect_unsafe_sites: bool,
+) -> Result<(RsFileMetrics, Vec<UnsafeSite>), syn::Error> {
     use syn::visit::Visit;
     let syntax = syn::parse_file(src)?;
-    let mut vis = GeigerSynVisitor::new(include_tests);
+    let mut vis = GeigerSynVisitor::new(include_tests, collect_unsafe_sites);
     vis.visit_file(&syntax);
-    Ok(vis.metrics)
+    // With the `span-locations` feature, the source of every parsed file is
+    // kept by the current thread until the spans are invalidated.
+    drop(syntax);
+    proc_macro2::extra::invalidate_current_thread_spans();
+    Ok((vis.metrics, vis.unsafe_sites))
+}
+
+/// The tokens of an unsafe site found in `src` separated by single spaces,
+/// which do not change when the code is only reformatted
+pub fn unsafe_site_tokens(src: &str, site: &UnsafeSite) -> Option<String> {
+    let mut site_src = String::new();
+    for (index, line) in src
+        .lines()
+        .enumerate()
+        .skip(site.line - 1)
+        .take(site.end_line + 1 - site.line)
+    {
+        let start = if index + 1 == site.line {
+            site.column - 1
+        } else {
+            0
+        };
+        let end = if index + 1 == site.end_line {
+            site.end_column
+        } else {
+            usize::MAX
+        };
+        site_src.extend(line.chars().take(end).skip(start));
+        site_src.push('\n');
+    }
+    let tokens = site_src
+        .parse::<proc_macro2::TokenStream>()
+        .ok()
+        .map(|tokens| tokens.to_string());
+    proc_macro2::extra::invalidate_current_thread_spans();
+    tokens
+}
+
+fn read_rs_file(path: &Path) -> Result<String, ScanFileError> {
+    let mut file = File::open(path)
+        .map_err(|e| ScanFileError::Io(e, path.to_path_buf()))?;
+    let mut src = vec![];
+    file.read_to_end(&mut src)
+        .map_err(|e| ScanFileError::Io(e, path.to_path_buf()))?;
+    String::from_utf8(src)
+        .map_err(|e| ScanFileError::Utf8(e, path.to_path_buf()))
 }
 
 #[cfg(test)]
 mod find_tests {
     use super::*;
 
+    use crate::UnsafeKind;
     use cargo_geiger_serde::{Count, CounterBlock};
     use tempfile::tempdir;
 
@@ -153,6 +219,67 @@ mod tests {
         assert_eq!(from_string, expected);
     }
 
+    #[test]
+    fn find_unsafe_sites() {
+        let src = "\
+unsafe trait T {}
+unsafe impl T for u8 {}
+#[no_mangle]
+pub fn f() {}
+impl S {
+    pub unsafe fn m() {
+        let x = unsafe { g() };
+    }
+}
+#[cfg(test)]
+mod tests {
+    unsafe fn t() {}
+}
+";
+        let position = |site: &UnsafeSite| {
+            (
+                site.kind,
+                site.line,
+                site.column,
+                site.end_line,
+                site.end_column,
+            )
+        };
+
+        let (metrics, sites) =
+            find_unsafe_and_sites_in_string(src, IncludeTests::No).unwrap();
+        assert_eq!(
+            sites.iter().map(position).collect::<Vec<_>>(),
+            vec![
+                (UnsafeKind::Trait, 1, 1, 1, 17),
+                (UnsafeKind::Impl, 2, 1, 2, 23),
+                (UnsafeKind::Function, 4, 5, 4, 13),
+                (UnsafeKind::Method, 6, 9, 8, 5),
+                (UnsafeKind::Block, 7, 17, 7, 30),
+            ]
+        );
+        assert_eq!(
+            unsafe_site_tokens(src, &sites[3]).as_deref(),
+            Some("unsafe fn m () { let x = unsafe { g () } ; }")
+        );
+        assert_eq!(
+            unsafe_site_tokens(src, &sites[4]).as_deref(),
+            Some("unsafe { g () }")
+        );
+        assert_eq!(
+            find_unsafe_and_sites_in_string(src, IncludeTests::Yes)
+                .unwrap()
+                .1
+                .last()
+                .map(position),
+            Some((UnsafeKind::Function, 12, 5, 12, 20))
+        );
+        assert_eq!(
+            find_unsafe_in_string(src, IncludeTests::No).unwrap(),
+            metrics
+        );
+    }
+
     #[test]
     fn forbids_unsafe() {
         let expected = RsFileMetrics {
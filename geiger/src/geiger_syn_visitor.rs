@@ -1,8 +1,10 @@
 use super::{
     file_forbids_unsafe, has_unsafe_attributes, is_test_fn, is_test_mod,
-    IncludeTests, RsFileMetrics,
+    IncludeTests, RsFileMetrics, UnsafeKind, UnsafeSite,
 };
 
+use proc_macro2::Span;
+use syn::spanned::Spanned;
 use syn::{
     visit, Expr, ExprUnsafe, ImplItemFn, ItemFn, ItemImpl, ItemMod, ItemTrait,
 };
@@ -11,9 +13,15 @@ pub struct GeigerSynVisitor {
     /// Count unsafe usage inside tests
     include_tests: IncludeTests,
 
+    /// Record where each unsafe item or block starts in `unsafe_sites`
+    collect_unsafe_sites: bool,
+
     /// The resulting data from a single file scan.
     pub metrics: RsFileMetrics,
 
+    /// Where each unsafe item or block found by the scan starts, if collected
+    pub unsafe_sites: Vec<UnsafeSite>,
+
     /// The number of nested unsafe scopes that the GeigerSynVisitor are
     /// currently in. For example, if the visitor is inside an unsafe function
     /// and inside an unnecessary unsafe block inside that function, then this
@@ -25,10 +33,15 @@ pub struct GeigerSynVisitor {
 }
 
 impl GeigerSynVisitor {
-    pub fn new(include_tests: IncludeTests) -> Self {
+    pub fn new(
+        include_tests: IncludeTests,
+        collect_unsafe_sites: bool,
+    ) -> Self {
         GeigerSynVisitor {
             include_tests,
+            collect_unsafe_sites,
             metrics: Default::default(),
+            unsafe_sites: vec![],
             unsafe_scopes: 0,
         }
     }
@@ -40,6 +53,27 @@ impl GeigerSynVisitor {
     pub fn exit_unsafe_scope(&mut self) {
         self.unsafe_scopes -= 1;
     }
+
+    /// Record an unsafe site which starts at `span` and spans all of `item`
+    fn add_unsafe_site<T: Spanned>(
+        &mut self,
+        kind: UnsafeKind,
+        span: Span,
+        item: &T,
+    ) {
+        if !self.collect_unsafe_sites {
+            return;
+        }
+        let start = span.start();
+        let end = item.span().end();
+        self.unsafe_sites.push(UnsafeSite {
+            kind,
+            line: start.line,
+            column: start.column + 1,
+            end_line: end.line,
+            end_column: end.column,
+        });
+    }
 }
 
 impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
@@ -56,7 +90,12 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
         let unsafe_fn =
             item_fn.sig.unsafety.is_some() || has_unsafe_attributes(item_fn);
         if unsafe_fn {
-            self.enter_unsafe_scope()
+            self.enter_unsafe_scope();
+            let span = match &item_fn.sig.unsafety {
+                Some(unsafety) => unsafety.span,
+                None => item_fn.sig.fn_token.span,
+            };
+            self.add_unsafe_site(UnsafeKind::Function, span, item_fn);
         }
         self.metrics.counters.functions.count(unsafe_fn);
         visit::visit_item_fn(self, item_fn);
@@ -81,6 +120,7 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
 
     fn visit_expr_unsafe(&mut self, i: &ExprUnsafe) {
         self.enter_unsafe_scope();
+        self.add_unsafe_site(UnsafeKind::Block, i.unsafe_token.span, i);
         visit::visit_expr_unsafe(self, i);
         self.exit_unsafe_scope();
     }
@@ -95,6 +135,9 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
     fn visit_item_impl(&mut self, i: &ItemImpl) {
         // unsafe trait impl's
         self.metrics.counters.item_impls.count(i.unsafety.is_some());
+        if let Some(unsafety) = &i.unsafety {
+            self.add_unsafe_site(UnsafeKind::Impl, unsafety.span, i);
+        }
         visit::visit_item_impl(self, i);
     }
 
@@ -104,12 +147,16 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
             .counters
             .item_traits
             .count(i.unsafety.is_some());
+        if let Some(unsafety) = &i.unsafety {
+            self.add_unsafe_site(UnsafeKind::Trait, unsafety.span, i);
+        }
         visit::visit_item_trait(self, i);
     }
 
     fn visit_impl_item_fn(&mut self, i: &ImplItemFn) {
-        if i.sig.unsafety.is_some() {
-            self.enter_unsafe_scope()
+        if let Some(unsafety) = &i.sig.unsafety {
+            self.enter_unsafe_scope();
+            self.add_unsafe_site(UnsafeKind::Method, unsafety.span, i);
         }
         self.metrics
             .counters
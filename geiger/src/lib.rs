@@ -13,6 +13,8 @@ pub use find::*; // preserve APIs
 mod geiger_syn_visitor;
 
 use cargo_geiger_serde::CounterBlock;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
 use std::io;
@@ -28,6 +30,7 @@ pub enum IncludeTests {
 
 /// Scan result for a single `.rs` file.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RsFileMetrics {
     /// Metrics storage.
     pub counters: CounterBlock,
@@ -36,6 +39,37 @@ pub struct RsFileMetrics {
     pub forbids_unsafe: bool,
 }
 
+/// The kinds of unsafe code counted in a `CounterBlock`
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum UnsafeKind {
+    /// `unsafe fn`, or a function with `#[no_mangle]` or `#[export_name]`
+    Function,
+    /// `unsafe { .. }` block expression
+    Block,
+    /// `unsafe impl`
+    Impl,
+    /// `unsafe trait`
+    Trait,
+    /// `unsafe fn` in an impl block
+    Method,
+}
+
+/// Where unsafe code starts in a `.rs` file
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct UnsafeSite {
+    pub kind: UnsafeKind,
+    /// 1-based line of the `unsafe` keyword, or of the item when it has none
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// 1-based last line of the item or block
+    pub end_line: usize,
+    /// 1-based column of the last character of the item or block
+    pub end_column: usize,
+}
+
 #[derive(Debug)]
 pub enum ScanFileError {
     Io(io::Error, PathBuf),
@@ -0,0 +1,243 @@
+//! The `geiger` executable, scanning directories and files for unsafe Rust
+//! without requiring `cargo metadata`.
+
+#![forbid(unsafe_code)]
+#![deny(warnings)]
+
+use cargo_geiger_serde::CounterBlock;
+use geiger::{find_unsafe_in_file, IncludeTests, RsFileMetrics};
+use pico_args::Arguments;
+use serde::Serialize;
+use std::io;
+use std::path::PathBuf;
+use std::process::exit;
+use std::str::FromStr;
+use walkdir::WalkDir;
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+const HELP: &str = "Detects usage of unsafe Rust in directories and files.
+
+USAGE:
+    geiger [OPTIONS] <PATH>...
+
+Directories are walked recursively for .rs files, files are scanned as given.
+
+OPTIONS:
+        --include-tests           Count unsafe usage in tests.
+        --output-format <FORMAT>  Output format: Text, Json [default: Text]
+    -h, --help                    Prints help information.
+    -V, --version                 Prints version information.
+";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Json" => Ok(OutputFormat::Json),
+            "Text" => Ok(OutputFormat::Text),
+            other => Err(format!("unknown output format `{}`", other)),
+        }
+    }
+}
+
+const COUNTERS_HEADER: &str =
+    "Functions  Expressions  Impls  Traits  Methods  File";
+
+#[derive(Debug, Serialize)]
+struct Report {
+    files: Vec<FileReport>,
+    total: CounterBlock,
+    /// Files which could not be read or parsed
+    errors: Vec<FileError>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileReport {
+    path: PathBuf,
+    metrics: RsFileMetrics,
+}
+
+#[derive(Debug, Serialize)]
+struct FileError {
+    path: PathBuf,
+    error: String,
+}
+
+fn main() {
+    let mut raw_args = Arguments::from_env();
+    if raw_args.contains(["-h", "--help"]) {
+        println!("{}", HELP);
+        return;
+    }
+    if raw_args.contains(["-V", "--version"]) {
+        println!("geiger {}", VERSION.unwrap_or("unknown version"));
+        return;
+    }
+    let include_tests = if raw_args.contains("--include-tests") {
+        IncludeTests::Yes
+    } else {
+        IncludeTests::No
+    };
+    let output_format = raw_args
+        .opt_value_from_str("--output-format")
+        .unwrap_or_else(|e| exit_with_error(e))
+        .unwrap_or(OutputFormat::Text);
+    let paths = raw_args
+        .finish()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<PathBuf>>();
+    if paths.is_empty() {
+        exit_with_error("at least one path is required, see `geiger --help`");
+    }
+
+    let rs_files = find_rs_files(&paths).unwrap_or_else(|e| exit_with_error(e));
+    let report = scan(rs_files, include_tests);
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(&report).unwrap())
+        }
+        OutputFormat::Text => print_text(&report),
+    }
+
+    if !report.errors.is_empty() {
+        exit(1);
+    }
+}
+
+/// Collect the `.rs` files below each of `paths`, in sorted order. Paths to
+/// files are returned as given, whatever their extension. Symbolic links to
+/// directories are not followed.
+fn find_rs_files(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut rs_files = vec![];
+    for path in paths {
+        if !path.is_dir() {
+            rs_files.push(path.clone());
+            continue;
+        }
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file()
+                && entry.path().extension().is_some_and(|ext| ext == "rs")
+            {
+                rs_files.push(entry.into_path());
+            }
+        }
+    }
+    Ok(rs_files)
+}
+
+fn scan(rs_files: Vec<PathBuf>, include_tests: IncludeTests) -> Report {
+    let mut report = Report {
+        files: vec![],
+        total: CounterBlock::default(),
+        errors: vec![],
+    };
+    for path in rs_files {
+        match find_unsafe_in_file(&path, include_tests) {
+            Ok(metrics) => {
+                report.total += metrics.counters.clone();
+                report.files.push(FileReport { path, metrics });
+            }
+            Err(e) => report.errors.push(FileError {
+                path,
+                error: e.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+fn print_text(report: &Report) {
+    println!("{}", COUNTERS_HEADER);
+    for file in &report.files {
+        println!(
+            "{}  {}",
+            counters_row(&file.metrics.counters),
+            file.path.display()
+        );
+    }
+    println!();
+    println!("{}  Total", counters_row(&report.total));
+
+    for error in &report.errors {
+        eprintln!("Failed to scan {}: {}", error.path.display(), error.error);
+    }
+}
+
+/// The unsafe and total count of every counter, aligned with the header
+fn counters_row(counters: &CounterBlock) -> String {
+    let [functions, exprs, item_impls, item_traits, methods] = [
+        &counters.functions,
+        &counters.exprs,
+        &counters.item_impls,
+        &counters.item_traits,
+        &counters.methods,
+    ]
+    .map(|count| format!("{}/{}", count.unsafe_, count.safe + count.unsafe_));
+    format!(
+        "{: <10} {: <12} {: <6} {: <7} {: <7}",
+        functions, exprs, item_impls, item_traits, methods
+    )
+}
+
+fn exit_with_error(error: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", error);
+    exit(2)
+}
+
+#[cfg(test)]
+mod main_tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn output_format_from_str_test() {
+        assert_eq!(OutputFormat::from_str("Json"), Ok(OutputFormat::Json));
+        assert_eq!(OutputFormat::from_str("Text"), Ok(OutputFormat::Text));
+        assert_eq!(
+            OutputFormat::from_str("json"),
+            Err(String::from("unknown output format `json`"))
+        );
+    }
+
+    #[test]
+    fn find_rs_files_test() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("gen/nested")).unwrap();
+        for file_name in ["b.rs", "a.rs", "BUILD", "gen/nested/c.rs"] {
+            std::fs::write(temp_dir.path().join(file_name), "").unwrap();
+        }
+        let extra_file = temp_dir.path().join("BUILD");
+
+        let rs_files =
+            find_rs_files(&[temp_dir.path().to_path_buf(), extra_file.clone()])
+                .unwrap();
+
+        assert_eq!(
+            rs_files,
+            vec![
+                temp_dir.path().join("a.rs"),
+                temp_dir.path().join("b.rs"),
+                temp_dir.path().join("gen/nested/c.rs"),
+                extra_file
+            ]
+        );
+
+        let missing_file = temp_dir.path().join("missing.rs");
+        assert_eq!(
+            find_rs_files(std::slice::from_ref(&missing_file)).unwrap(),
+            vec![missing_file]
+        );
+    }
+}